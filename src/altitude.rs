@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{ActiveDrone, Drone, arg_value, has_flag};
+
+/// Longest distance the rangefinder can measure (m).
+const RANGEFINDER_MAX: f32 = 10.0;
+
+/// Blends a barometer (world Y) with a downward rangefinder (height above
+/// whatever is below the drone). The rangefinder is trusted close to the
+/// ground and faded out between `range_near` and `range_far`, above which only
+/// the barometer is used.
+#[derive(Resource)]
+pub struct AltitudeFusion {
+    pub enabled: bool,
+    /// Rangefinder weight while below `range_near`, 0..=1.
+    pub range_weight: f32,
+    pub range_near: f32,
+    pub range_far: f32,
+}
+
+impl AltitudeFusion {
+    pub fn from_args() -> Self {
+        let range_near = arg_value("--range-near")
+            .and_then(|near| near.parse::<f32>().ok())
+            .unwrap_or(2.0)
+            .max(0.0);
+        Self {
+            enabled: has_flag("--altitude-fusion"),
+            range_weight: arg_value("--range-weight")
+                .and_then(|weight| weight.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0),
+            range_near,
+            range_far: arg_value("--range-far")
+                .and_then(|far| far.parse::<f32>().ok())
+                .unwrap_or(4.0)
+                .clamp(range_near, RANGEFINDER_MAX),
+        }
+    }
+
+    /// How much of the fused altitude comes from the rangefinder.
+    pub fn weight(&self, range: Option<f32>) -> f32 {
+        let Some(range) = range else {
+            return 0.0;
+        };
+        let fade = if range <= self.range_near {
+            1.0
+        } else if range >= self.range_far {
+            0.0
+        } else {
+            (self.range_far - range) / (self.range_far - self.range_near)
+        };
+        self.range_weight * fade
+    }
+}
+
+/// Barometer (B), rangefinder (R) and fused (F) altitude. The fused value
+/// replaces the true height in the hover controller.
+#[derive(Component, Default)]
+pub struct AltitudeEstimate {
+    pub baro: f32,
+    /// `None` when nothing is within range.
+    pub range: Option<f32>,
+    pub fused: f32,
+}
+
+#[derive(Component)]
+pub struct AltitudeText;
+
+pub fn altitude_plugin(app: &mut App) {
+    let fusion = AltitudeFusion::from_args();
+    let enabled = fusion.enabled;
+
+    app.insert_resource(fusion).add_systems(
+        Update,
+        (
+            add_altitude_estimate,
+            estimate_altitude,
+            update_altitude_text,
+        )
+            .chain()
+            .run_if(move || enabled),
+    );
+}
+
+pub fn add_altitude_estimate(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<AltitudeEstimate>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(AltitudeEstimate::default());
+    }
+}
+
+pub fn estimate_altitude(
+    fusion: Res<AltitudeFusion>,
+    rapier_context: ReadRapierContext,
+    mut drone_query: Query<(Entity, &Transform, &mut AltitudeEstimate), With<Drone>>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+
+    for (entity, tf, mut estimate) in drone_query.iter_mut() {
+        estimate.baro = tf.translation.y;
+
+        // The rangefinder points along the body's down axis, so project the
+        // slant distance back onto the vertical when tilted
+        let down = tf.rotation * Vec3::NEG_Y;
+        estimate.range = if down.y < 0.0 {
+            context
+                .cast_ray(
+                    tf.translation,
+                    down,
+                    RANGEFINDER_MAX,
+                    true,
+                    QueryFilter::default()
+                        .exclude_sensors()
+                        .exclude_rigid_body(entity),
+                )
+                .map(|(_, toi)| toi * -down.y)
+        } else {
+            None
+        };
+
+        let weight = fusion.weight(estimate.range);
+        estimate.fused = weight * estimate.range.unwrap_or(0.0) + (1.0 - weight) * estimate.baro;
+    }
+}
+
+pub fn update_altitude_text(
+    drone_query: Query<&AltitudeEstimate, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<AltitudeText>>,
+) {
+    for estimate in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            let range = estimate
+                .range
+                .map_or("-".to_string(), |range| format!("{range:.2}"));
+            *text = format!(
+                "Alt B:{:.2} R:{range} F:{:.2}",
+                estimate.baro, estimate.fused
+            )
+            .into();
+        }
+    }
+}
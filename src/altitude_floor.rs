@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ActiveDrone, AltitudeEstimate, Drone, EngineState, GRAVITY, HoverPid, arg_value,
+    clamp_setpoints, landing::LandingState, position_control,
+};
+
+const FLOOR_KEY: KeyCode = KeyCode::Semicolon;
+/// Deceleration the floor leaves room for when the drone is coming down
+/// fast (m/s²), well within what the hover controller can pull.
+const BRAKE_ACCEL: f32 = 0.5 * GRAVITY;
+
+/// "Training wheels": an invisible floor `clearance` above the ground that
+/// the altitude setpoint can't be taken below, so a beginner can't fly into
+/// the ground. The height above ground comes from the rangefinder when
+/// `--altitude-fusion` is on, otherwise from the height above the floor.
+/// Off unless `--alt-floor <clearance>` is given; `;` toggles it.
+#[derive(Resource)]
+pub struct AltitudeFloor {
+    pub enabled: bool,
+    /// Lowest height above the ground the drone is held at (m).
+    pub clearance: f32,
+}
+
+impl AltitudeFloor {
+    pub fn from_args() -> Self {
+        let clearance =
+            arg_value("--alt-floor").and_then(|clearance| clearance.parse::<f32>().ok());
+        Self {
+            enabled: clearance.is_some(),
+            clearance: clearance.unwrap_or(1.0).max(0.0),
+        }
+    }
+}
+
+/// Per-drone floor state.
+#[derive(Component, Default)]
+pub struct FloorGuard {
+    /// Set once the drone has climbed through the floor; a drone taking off
+    /// isn't yanked up to it.
+    pub armed: bool,
+    /// The floor raised the setpoint this frame.
+    pub active: bool,
+}
+
+#[derive(Component)]
+pub struct AltProtectText;
+
+pub fn altitude_floor_plugin(app: &mut App) {
+    app.insert_resource(AltitudeFloor::from_args())
+        .add_systems(
+            Update,
+            (
+                toggle_altitude_floor,
+                (add_floor_guard, protect_altitude)
+                    .chain()
+                    .after(clamp_setpoints)
+                    .after(position_control)
+                    .run_if(in_state(EngineState::On)),
+                update_alt_protect_text,
+            ),
+        )
+        .add_systems(OnExit(EngineState::On), disarm_floor_guards);
+}
+
+pub fn toggle_altitude_floor(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut floor: ResMut<AltitudeFloor>,
+) {
+    if keyboard.just_pressed(FLOOR_KEY) {
+        floor.enabled = !floor.enabled;
+        info!(
+            "Altitude floor {}",
+            if floor.enabled { "on" } else { "off" }
+        );
+    }
+}
+
+pub fn add_floor_guard(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<FloorGuard>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(FloorGuard::default());
+    }
+}
+
+/// Keeps the altitude setpoint above the floor, raised by the distance the
+/// drone needs to stop if it's coming down. An auto-land is let through.
+#[allow(clippy::type_complexity)]
+pub fn protect_altitude(
+    floor: Res<AltitudeFloor>,
+    mut drone_query: Query<
+        (
+            &Transform,
+            &Velocity,
+            &mut HoverPid,
+            &mut FloorGuard,
+            Option<&AltitudeEstimate>,
+        ),
+        Without<LandingState>,
+    >,
+) {
+    for (tf, velocity, mut ctl_y, mut guard, altitude) in drone_query.iter_mut() {
+        guard.active = false;
+        if !floor.enabled {
+            continue;
+        }
+
+        let y = tf.translation.y;
+        let height = altitude.and_then(|altitude| altitude.range).unwrap_or(y);
+        if height >= floor.clearance {
+            guard.armed = true;
+        }
+        if !guard.armed {
+            continue;
+        }
+
+        let descent = (-velocity.linvel.y).max(0.0);
+        let stopping = descent * descent / (2.0 * BRAKE_ACCEL);
+        let min_target = y - height + floor.clearance + stopping;
+        if ctl_y.target_y < min_target {
+            ctl_y.target_y = min_target.min(ctl_y.max_y);
+            guard.active = true;
+        }
+    }
+}
+
+pub fn disarm_floor_guards(mut drone_query: Query<&mut FloorGuard>) {
+    for mut guard in drone_query.iter_mut() {
+        *guard = FloorGuard::default();
+    }
+}
+
+pub fn update_alt_protect_text(
+    floor: Res<AltitudeFloor>,
+    drone_query: Query<&FloorGuard, With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<AltProtectText>>,
+) {
+    let guard = drone_query.single().ok();
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = match guard {
+            _ if !floor.enabled => ("Floor: off".into(), Color::WHITE),
+            Some(guard) if guard.active => ("ALT PROTECT".into(), Color::srgb(1.0, 0.2, 0.2)),
+            _ => (
+                format!("Floor: {:.1} m", floor.clearance).into(),
+                Color::WHITE,
+            ),
+        };
+    }
+}
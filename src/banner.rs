@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use crate::{
+    AttitudeMode, Drone, DroneId, FailureMode, HoverPid, PitchPid, RollPid, YawPid, arg_value,
+    gust::GustScheduler, has_flag, level::Level, telemetry::TelemetryLogger,
+    timestep::PhysicsTimestep, validate_pid_parameters,
+};
+
+pub fn banner_plugin(app: &mut App) {
+    app.add_systems(PostStartup, compose_banner.after(validate_pid_parameters));
+}
+
+/// Summary of the build and configuration a run was started with, so logs
+/// and recordings can be traced back to it. Printed at startup unless
+/// `--no-banner` is given, and handed to the telemetry logger to write at the
+/// top of every log. Runs after validation so it shows the gains actually flown.
+pub fn compose_banner(
+    level: Res<Level>,
+    timestep: Res<PhysicsTimestep>,
+    gusts: Res<GustScheduler>,
+    attitude: Res<AttitudeMode>,
+    failure: Res<FailureMode>,
+    mut logger: ResMut<TelemetryLogger>,
+    drone_query: Query<(&DroneId, &HoverPid, &PitchPid, &RollPid, &YawPid), With<Drone>>,
+) {
+    let mut lines = vec![format!(
+        "{} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )];
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    lines.push(format!(
+        "args: {}",
+        if args.is_empty() {
+            "(none)".to_string()
+        } else {
+            args.join(" ")
+        }
+    ));
+    lines.push(format!(
+        "level: {} ({} gates, {} waypoints, {} wind layers)",
+        level.path,
+        level.gates.len(),
+        level.waypoints.len(),
+        level.wind.len()
+    ));
+    if let Some(path) = arg_value("--compare") {
+        lines.push(format!("compare gains: {path}"));
+    }
+    lines.push(format!(
+        "physics: {} Hz fixed, interpolation {}",
+        timestep.rate,
+        if timestep.interpolate { "on" } else { "off" }
+    ));
+    lines.push(format!(
+        "attitude error: {:?}, engine failure: {:?}",
+        *attitude, *failure
+    ));
+    lines.push(if gusts.interval > 0.0 {
+        format!(
+            "gusts: every {} s, {} m/s for {} s, seed {}",
+            gusts.interval, gusts.strength, gusts.duration, gusts.seed
+        )
+    } else {
+        "gusts: off".to_string()
+    });
+
+    let mut drones = drone_query.iter().collect::<Vec<_>>();
+    drones.sort_by_key(|(id, ..)| id.0);
+    for (id, ctl_y, ctl_pitch, ctl_roll, ctl_yaw) in drones {
+        lines.push(format!(
+            "drone {}: hover {}/{}/{} pitch {}/{}/{} roll {}/{}/{} yaw {}/{}/{} (kp/ki/kd), anti-windup {:?}",
+            id.0,
+            ctl_y.kp,
+            ctl_y.ki,
+            ctl_y.kd,
+            ctl_pitch.kp,
+            ctl_pitch.ki,
+            ctl_pitch.kd,
+            ctl_roll.kp,
+            ctl_roll.ki,
+            ctl_roll.kd,
+            ctl_yaw.kp,
+            ctl_yaw.ki,
+            ctl_yaw.kd,
+            ctl_y.anti_windup
+        ));
+    }
+
+    if !has_flag("--no-banner") {
+        for line in &lines {
+            info!("{line}");
+        }
+    }
+
+    logger.header = lines;
+}
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ActiveDrone, EngineState, HoverPid, PitchPid, RollPid, YawPid, arg_value, has_flag,
+    reset_feedforward,
+};
+
+/// Spin the drone is dropped with (rad/s), so the catch has to re-level it too.
+const DROP_SPIN: Vec3 = Vec3::new(0.9, 0.3, -0.6);
+/// Below this height before the fall is arrested counts as hitting the ground (m).
+const CRASH_HEIGHT: f32 = 0.3;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchPhase {
+    #[default]
+    Falling,
+    /// Armed, still descending.
+    Arresting,
+    Caught,
+    Crashed,
+}
+
+/// Free-fall recovery scenario: the drone starts high up with the engine off,
+/// tumbling as it drops. Arming the engine (`P`) "catches" it: the controllers
+/// are told to level off and hover where it is, and the HUD reports the
+/// descent rate they had to arrest.
+#[derive(Resource, Default)]
+pub struct CatchScenario {
+    pub enabled: bool,
+    pub height: f32,
+    pub phase: CatchPhase,
+    /// Fastest descent before the fall was arrested (m/s).
+    pub peak_descent: f32,
+    pub catch_altitude: f32,
+    pub lowest: f32,
+}
+
+impl CatchScenario {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: has_flag("--catch"),
+            height: arg_value("--catch-height")
+                .and_then(|height| height.parse::<f32>().ok())
+                .unwrap_or(40.0)
+                .max(1.0),
+            ..Default::default()
+        }
+    }
+
+    /// Where the main drone spawns.
+    pub fn spawn_height(&self, default: f32) -> f32 {
+        if self.enabled { self.height } else { default }
+    }
+}
+
+#[derive(Component)]
+pub struct CatchText;
+
+pub fn catch_plugin(app: &mut App) {
+    let scenario = CatchScenario::from_args();
+    let enabled = scenario.enabled;
+
+    app.insert_resource(scenario)
+        .add_systems(PostStartup, start_free_fall.run_if(move || enabled))
+        .add_systems(
+            OnEnter(EngineState::On),
+            catch_drone
+                .before(reset_feedforward)
+                .run_if(move || enabled),
+        )
+        .add_systems(
+            Update,
+            (track_catch, update_catch_text)
+                .chain()
+                .run_if(move || enabled),
+        );
+}
+
+pub fn start_free_fall(mut drone_query: Query<&mut Velocity, With<ActiveDrone>>) {
+    for mut velocity in drone_query.iter_mut() {
+        velocity.angvel = DROP_SPIN;
+    }
+}
+
+/// Commands a level hover at the altitude the drone was caught at, with the
+/// integrators cleared so nothing from before the drop fights the recovery.
+pub fn catch_drone(
+    mut scenario: ResMut<CatchScenario>,
+    mut drone_query: Query<
+        (
+            &Transform,
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+            &mut YawPid,
+        ),
+        With<ActiveDrone>,
+    >,
+) {
+    if scenario.phase != CatchPhase::Falling {
+        return;
+    }
+
+    for (tf, mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        let (yaw, _, _) = tf.rotation.to_euler(EulerRot::YXZ);
+        ctl_y.target_y = tf.translation.y;
+        ctl_pitch.target_angle = 0.0;
+        ctl_roll.target_angle = 0.0;
+        ctl_yaw.target_angle = yaw;
+        ctl_y.integral_e = 0.0;
+        ctl_pitch.integral_e = 0.0;
+        ctl_roll.integral_e = 0.0;
+        ctl_yaw.integral_e = 0.0;
+
+        scenario.phase = CatchPhase::Arresting;
+        scenario.catch_altitude = tf.translation.y;
+        scenario.lowest = tf.translation.y;
+        info!("Caught at {:.1} m", tf.translation.y);
+    }
+}
+
+pub fn track_catch(
+    mut scenario: ResMut<CatchScenario>,
+    drone_query: Query<(&Transform, &Velocity), With<ActiveDrone>>,
+) {
+    for (tf, velocity) in drone_query.iter() {
+        match scenario.phase {
+            CatchPhase::Falling | CatchPhase::Arresting => {
+                scenario.peak_descent = scenario.peak_descent.max(-velocity.linvel.y);
+                scenario.lowest = scenario.lowest.min(tf.translation.y);
+            }
+            CatchPhase::Caught | CatchPhase::Crashed => continue,
+        }
+
+        if tf.translation.y < CRASH_HEIGHT {
+            scenario.phase = CatchPhase::Crashed;
+            info!("Hit the ground at {:.1} m/s", -velocity.linvel.y);
+        } else if scenario.phase == CatchPhase::Arresting && velocity.linvel.y >= 0.0 {
+            scenario.phase = CatchPhase::Caught;
+            info!(
+                "Fall arrested from {:.1} m/s, {:.1} m below the catch",
+                scenario.peak_descent,
+                scenario.catch_altitude - scenario.lowest
+            );
+        }
+    }
+}
+
+pub fn update_catch_text(
+    scenario: Res<CatchScenario>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<CatchText>>,
+) {
+    for (mut text, mut color) in text_query.iter_mut() {
+        *text = match scenario.phase {
+            CatchPhase::Falling => format!("Falling {:.1} m/s: P!", scenario.peak_descent),
+            CatchPhase::Arresting => format!("Catching {:.1} m/s", scenario.peak_descent),
+            CatchPhase::Caught => format!(
+                "Caught {:.1} m/s -{:.1} m",
+                scenario.peak_descent,
+                scenario.catch_altitude - scenario.lowest
+            ),
+            CatchPhase::Crashed => format!("Crashed {:.1} m/s", scenario.peak_descent),
+        }
+        .into();
+        color.0 = match scenario.phase {
+            CatchPhase::Caught => Color::srgb(0.3, 1.0, 0.4),
+            CatchPhase::Crashed => Color::srgb(1.0, 0.3, 0.3),
+            _ => Color::srgb(1.0, 0.6, 0.0),
+        };
+    }
+}
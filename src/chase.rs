@@ -0,0 +1,119 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    Drone, PitchPid, RollPid,
+    position::{PositionPid, release_tilt},
+};
+
+const CHASE_LEAD_TIME: f32 = 0.5;
+const CHASE_OFFSET: Vec3 = Vec3::new(0.0, 1.0, 4.0);
+const RABBIT_CENTER: Vec3 = Vec3::new(0.0, 6.0, -30.0);
+const RABBIT_RADIUS: f32 = 20.0;
+const RABBIT_SPEED: f32 = 4.0;
+
+/// Makes a drone pursue `entity`, holding `offset` in the target's heading frame
+/// (forward is -Z, so a positive Z offset trails behind it).
+#[derive(Component)]
+pub struct ChaseTarget {
+    pub entity: Entity,
+    pub offset: Vec3,
+}
+
+/// A kinematic target that circles `center` for drones to chase.
+#[derive(Component)]
+pub struct Rabbit {
+    pub center: Vec3,
+    pub radius: f32,
+    pub angular_speed: f32,
+}
+
+pub fn spawn_rabbit(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    drone_query: Query<Entity, With<Drone>>,
+) {
+    let angular_speed = RABBIT_SPEED / RABBIT_RADIUS;
+
+    let rabbit = commands
+        .spawn((
+            Rabbit {
+                center: RABBIT_CENTER,
+                radius: RABBIT_RADIUS,
+                angular_speed,
+            },
+            Name::new("Rabbit"),
+            Mesh3d(meshes.add(Sphere::new(0.4))),
+            MeshMaterial3d(materials.add(Color::srgb_u8(255, 60, 60))),
+            Transform::from_translation(RABBIT_CENTER + Vec3::X * RABBIT_RADIUS)
+                .with_rotation(Quat::from_rotation_y(PI)),
+            RigidBody::KinematicVelocityBased,
+            Velocity::zero(),
+        ))
+        .id();
+
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(ChaseTarget {
+            entity: rabbit,
+            offset: CHASE_OFFSET,
+        });
+    }
+}
+
+pub fn move_rabbit(time: Res<Time>, mut rabbit_query: Query<(&Rabbit, &Transform, &mut Velocity)>) {
+    for (rabbit, tf, mut velocity) in rabbit_query.iter_mut() {
+        let angle = rabbit.angular_speed * time.elapsed_secs();
+        let on_circle = rabbit.center + rabbit.radius * Vec3::new(angle.cos(), 0.0, angle.sin());
+        let tangent =
+            rabbit.radius * rabbit.angular_speed * Vec3::new(-angle.sin(), 0.0, angle.cos());
+
+        // Follow the tangent and pull back onto the circle to cancel integration drift
+        velocity.linvel = tangent + (on_circle - tf.translation) * 2.0;
+        velocity.angvel = Vec3::new(0.0, -rabbit.angular_speed, 0.0);
+    }
+}
+
+pub fn chase_guidance(
+    mut chaser_query: Query<(&ChaseTarget, &mut PositionPid)>,
+    target_query: Query<(&Transform, Option<&Velocity>)>,
+) {
+    for (chase, mut ctl_pos) in chaser_query.iter_mut() {
+        let Ok((target_tf, target_velocity)) = target_query.get(chase.entity) else {
+            continue;
+        };
+
+        let (yaw, _, _) = target_tf.rotation.to_euler(EulerRot::YXZ);
+        let velocity = target_velocity.map_or(Vec3::ZERO, |v| v.linvel);
+
+        // Aim where the target will be shortly so the chaser doesn't lag behind
+        ctl_pos.target = target_tf.translation
+            + Quat::from_rotation_y(yaw) * chase.offset
+            + velocity * CHASE_LEAD_TIME;
+    }
+}
+
+pub fn toggle_chase(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut chaser_query: Query<
+        (&Transform, &mut PositionPid, &mut PitchPid, &mut RollPid),
+        With<ChaseTarget>,
+    >,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyC) {
+        return;
+    }
+
+    for (tf, mut ctl_pos, mut ctl_pitch, mut ctl_roll) in chaser_query.iter_mut() {
+        if ctl_pos.enabled {
+            ctl_pos.disengage();
+            release_tilt(&mut ctl_pitch, &mut ctl_roll);
+        } else {
+            let target = ctl_pos.target;
+            let tilt = Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle);
+            ctl_pos.engage(target, tf.translation, tilt);
+        }
+    }
+}
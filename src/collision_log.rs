@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+};
+
+use bevy::prelude::*;
+use bevy_rapier3d::{prelude::*, rapier::geometry::CollisionEventFlags};
+
+use crate::{Drone, Propeller};
+
+const COALESCE_WINDOW: f32 = 0.25;
+
+/// One logged impact. Contacts with the same collider that keep starting
+/// within the coalesce window (bouncing) are merged into a single entry.
+pub struct Impact {
+    pub drone: Entity,
+    pub drone_name: String,
+    pub other: Entity,
+    pub collider: String,
+    pub time: f32,
+    pub last_time: f32,
+    pub speed: f32,
+    pub point: Option<Vec3>,
+    pub contacts: u32,
+}
+
+#[derive(Resource, Default)]
+pub struct CollisionLog {
+    pub file: Option<BufWriter<File>>,
+    pub window: f32,
+    pub pending: Vec<Impact>,
+    /// Each drone's velocity before the latest physics step.
+    pub last_velocity: HashMap<Entity, Vec3>,
+}
+
+impl CollisionLog {
+    pub fn new(path: Option<&str>) -> Self {
+        let file = path.and_then(|path| {
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            match file {
+                Ok(file) => {
+                    let is_empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+                    let mut writer = BufWriter::new(file);
+                    if is_empty {
+                        let _ = writeln!(writer, "time,drone,collider,impact_speed,x,y,z,contacts");
+                    }
+                    Some(writer)
+                }
+                Err(e) => {
+                    warn!("Failed to open collision log {path}: {e}");
+                    None
+                }
+            }
+        });
+
+        Self {
+            file,
+            window: COALESCE_WINDOW,
+            ..Default::default()
+        }
+    }
+
+    fn record(
+        &mut self,
+        drone: (Entity, String),
+        other: (Entity, String),
+        time: f32,
+        point: Option<Vec3>,
+    ) {
+        let ((drone, drone_name), (other, collider)) = (drone, other);
+        let speed = self.last_velocity.get(&drone).map_or(0.0, |v| v.length());
+
+        if let Some(impact) = self.pending.iter_mut().find(|impact| {
+            impact.drone == drone && impact.other == other && time - impact.last_time < self.window
+        }) {
+            impact.last_time = time;
+            impact.speed = impact.speed.max(speed);
+            impact.contacts += 1;
+            return;
+        }
+
+        self.pending.push(Impact {
+            drone,
+            drone_name,
+            other,
+            collider,
+            time,
+            last_time: time,
+            speed,
+            point,
+            contacts: 1,
+        });
+    }
+
+    fn flush(&mut self, time: f32) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+
+        let window = self.window;
+        self.pending.retain(|impact| {
+            if time - impact.last_time < window {
+                return true;
+            }
+
+            let point = impact
+                .point
+                .map(|p| format!("{:.3},{:.3},{:.3}", p.x, p.y, p.z))
+                .unwrap_or_else(|| ",,".to_string());
+            let _ = writeln!(
+                file,
+                "{:.3},{},{},{:.3},{},{}",
+                impact.time,
+                impact.drone_name,
+                impact.collider,
+                impact.speed,
+                point,
+                impact.contacts
+            );
+            false
+        });
+        let _ = file.flush();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn log_collisions(
+    time: Res<Time>,
+    mut log: ResMut<CollisionLog>,
+    mut collision_events: EventReader<CollisionEvent>,
+    rapier_context: ReadRapierContext,
+    drone_query: Query<(Entity, &Velocity), With<Drone>>,
+    propeller_query: Query<&ImpulseJoint, With<Propeller>>,
+    name_query: Query<&Name>,
+) {
+    if log.file.is_none() {
+        collision_events.clear();
+        return;
+    }
+
+    let now = time.elapsed_secs();
+
+    for event in collision_events.read() {
+        let CollisionEvent::Started(e1, e2, flags) = *event else {
+            continue;
+        };
+        if flags.contains(CollisionEventFlags::SENSOR) {
+            continue;
+        }
+
+        // A propeller strike counts against the drone it's mounted on
+        let drone_of = |entity: Entity| {
+            if drone_query.contains(entity) {
+                Some(entity)
+            } else {
+                propeller_query.get(entity).ok().map(|joint| joint.parent)
+            }
+        };
+        let (drone, other) = match (drone_of(e1), drone_of(e2)) {
+            (Some(drone), None) => (drone, e2),
+            (None, Some(drone)) => (drone, e1),
+            _ => continue,
+        };
+
+        let point = rapier_context.single().ok().and_then(|context| {
+            context.contact_pair(e1, e2).and_then(|pair| {
+                pair.manifolds()
+                    .find_map(|manifold| manifold.solver_contact(0).map(|c| c.point()))
+            })
+        });
+        let name = |entity: Entity| {
+            name_query
+                .get(entity)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|_| entity.to_string())
+        };
+
+        log.record((drone, name(drone)), (other, name(other)), now, point);
+    }
+
+    log.flush(now);
+
+    // Remember the pre-step velocities so the next impact reports the speed it hit at
+    log.last_velocity = drone_query
+        .iter()
+        .map(|(drone, velocity)| (drone, velocity.linvel))
+        .collect();
+}
@@ -0,0 +1,174 @@
+use bevy::{prelude::*, render::camera::Viewport, window::PrimaryWindow};
+use serde::Deserialize;
+
+use crate::{
+    Drone, DroneCamera, FollowDrone, HoverPid, PitchPid, RollPid, YawPid, arg_value, build_drone,
+    spawn_camera, spawn_drone,
+};
+
+/// Sideways spacing between the two compared drones.
+const COMPARE_SPACING: f32 = 3.0;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Gains {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+}
+
+/// Gains for the second drone in comparison mode; axes left out keep the
+/// defaults. The hover `kp` scales the altitude gain schedule.
+#[derive(Resource, Deserialize, Default, Clone, Debug)]
+pub struct GainSet {
+    #[serde(default)]
+    pub hover: Option<Gains>,
+    #[serde(default)]
+    pub pitch: Option<Gains>,
+    #[serde(default)]
+    pub roll: Option<Gains>,
+    #[serde(default)]
+    pub yaw: Option<Gains>,
+}
+
+impl GainSet {
+    pub fn load(path: &str) -> Self {
+        let gains = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| ron::from_str::<GainSet>(&contents).map_err(|e| e.to_string()));
+
+        match gains {
+            Ok(gains) => gains,
+            Err(e) => {
+                warn!("Failed to load gain set {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// The second drone, flying the loaded gain set.
+#[derive(Component)]
+pub struct CompareDrone;
+
+/// Right half of the split screen, following the second drone.
+#[derive(Component)]
+pub struct CompareCamera;
+
+/// A/B tuning: `--compare <gains.ron>` spawns a second drone beside the first
+/// with a different gain set. The compare drone counts as `Piloted`, so both
+/// receive the same input; each gets its own half of the screen.
+pub fn compare_plugin(app: &mut App) {
+    let Some(path) = arg_value("--compare") else {
+        return;
+    };
+
+    app.insert_resource(GainSet::load(&path))
+        .add_systems(
+            Startup,
+            (spawn_compare_drone, apply_compare_gains)
+                .chain()
+                .after(spawn_drone)
+                .after(spawn_camera),
+        )
+        .add_systems(Update, update_compare_viewports);
+}
+
+pub fn spawn_compare_drone(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    drone_query: Query<(), With<Drone>>,
+) {
+    // Numbered after any drones from `--drones`
+    let drone = build_drone(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        drone_query.iter().count(),
+        Vec3::new(COMPARE_SPACING, 3.0, 0.0),
+    );
+    commands.entity(drone).insert(CompareDrone);
+
+    commands.spawn((
+        Name::new("Compare Camera"),
+        DroneCamera,
+        CompareCamera,
+        FollowDrone(drone),
+        Camera3d::default(),
+        Camera {
+            order: 1,
+            ..default()
+        },
+    ));
+
+    // Full-window camera for the HUD so it isn't squeezed into one viewport
+    commands.spawn((
+        Name::new("HUD Camera"),
+        Camera2d,
+        Camera {
+            order: 2,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        IsDefaultUiCamera,
+    ));
+}
+
+pub fn apply_compare_gains(
+    gains: Res<GainSet>,
+    mut drone_query: Query<
+        (&mut HoverPid, &mut PitchPid, &mut RollPid, &mut YawPid),
+        With<CompareDrone>,
+    >,
+) {
+    for (mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        if let Some(g) = gains.hover {
+            let scale = g.kp / ctl_y.kp;
+            ctl_y.min_kp *= scale;
+            ctl_y.max_kp *= scale;
+            (ctl_y.kp, ctl_y.ki, ctl_y.kd) = (g.kp, g.ki, g.kd);
+        }
+        if let Some(g) = gains.pitch {
+            (ctl_pitch.kp, ctl_pitch.ki, ctl_pitch.kd) = (g.kp, g.ki, g.kd);
+        }
+        if let Some(g) = gains.roll {
+            (ctl_roll.kp, ctl_roll.ki, ctl_roll.kd) = (g.kp, g.ki, g.kd);
+        }
+        if let Some(g) = gains.yaw {
+            (ctl_yaw.kp, ctl_yaw.ki, ctl_yaw.kd) = (g.kp, g.ki, g.kd);
+        }
+    }
+}
+
+/// Splits the window between the main camera (left) and the compare camera (right).
+pub fn update_compare_viewports(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&mut Camera, Has<CompareCamera>), With<DroneCamera>>,
+) {
+    let Ok(window) = window_query.single() else {
+        return;
+    };
+    let size = window.physical_size();
+    let half = UVec2::new(size.x / 2, size.y);
+    if half.x == 0 || half.y == 0 {
+        return;
+    }
+
+    for (mut camera, compare) in camera_query.iter_mut() {
+        let position = if compare {
+            UVec2::new(half.x, 0)
+        } else {
+            UVec2::ZERO
+        };
+        let unchanged = camera.viewport.as_ref().is_some_and(|viewport| {
+            viewport.physical_position == position && viewport.physical_size == half
+        });
+        if !unchanged {
+            camera.viewport = Some(Viewport {
+                physical_position: position,
+                physical_size: half,
+                ..default()
+            });
+        }
+    }
+}
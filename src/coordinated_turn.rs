@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ActiveDrone, Drone, GRAVITY, RollPid, YawPid, angle_error, arg_value, clamp_setpoints,
+    has_flag, manual_control, position::PositionPid, position_control,
+};
+
+/// Time constant of the filter on the commanded yaw rate (s). Keyboard yaw
+/// comes in steps, which would otherwise make the bank jitter.
+const YAW_RATE_SMOOTHING: f32 = 0.15;
+/// Bank below which the HUD doesn't call the assist active (rad).
+const ACTIVE_BANK: f32 = 0.01;
+
+/// Coordinated-turn assist: while yawing in forward flight, banks the drone
+/// into the turn like an aircraft. The bank for a balanced turn at speed `v`
+/// and turn rate `ω` is `atan(v·ω / g)`; `gain` scales it.
+#[derive(Resource)]
+pub struct CoordinatedTurn {
+    pub enabled: bool,
+    pub gain: f32,
+}
+
+impl CoordinatedTurn {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: has_flag("--coordinated-turn"),
+            gain: arg_value("--turn-gain")
+                .and_then(|gain| gain.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .max(0.0),
+        }
+    }
+}
+
+/// Bank currently added to a drone's roll setpoint by the assist. It's taken
+/// back out before the pilot's inputs and stick centering run, so those only
+/// ever see the pilot's own setpoint.
+#[derive(Component, Default)]
+pub struct TurnBank {
+    pub bank: f32,
+    /// Filtered commanded yaw rate (rad/s), positive turning left.
+    pub yaw_rate: f32,
+    pub last_yaw_target: Option<f32>,
+}
+
+#[derive(Component)]
+pub struct TurnText;
+
+pub fn coordinated_turn_plugin(app: &mut App) {
+    app.insert_resource(CoordinatedTurn::from_args())
+        .add_systems(
+            Update,
+            (
+                add_turn_bank,
+                remove_turn_bank.before(manual_control),
+                apply_turn_bank
+                    .after(position_control)
+                    .before(clamp_setpoints),
+                update_turn_text,
+            )
+                .chain(),
+        );
+}
+
+pub fn add_turn_bank(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<TurnBank>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(TurnBank::default());
+    }
+}
+
+pub fn remove_turn_bank(mut drone_query: Query<(&TurnBank, &mut RollPid)>) {
+    for (turn, mut ctl_roll) in drone_query.iter_mut() {
+        ctl_roll.target_angle -= turn.bank;
+    }
+}
+
+pub fn apply_turn_bank(
+    time: Res<Time>,
+    assist: Res<CoordinatedTurn>,
+    mut drone_query: Query<(
+        &Transform,
+        &Velocity,
+        &PositionPid,
+        &YawPid,
+        &mut RollPid,
+        &mut TurnBank,
+    )>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+
+    for (tf, velocity, ctl_pos, ctl_yaw, mut ctl_roll, mut turn) in drone_query.iter_mut() {
+        let commanded_rate = turn
+            .last_yaw_target
+            .map_or(0.0, |last| angle_error(ctl_yaw.target_angle, last) / dt);
+        turn.last_yaw_target = Some(ctl_yaw.target_angle);
+        turn.yaw_rate += (commanded_rate - turn.yaw_rate) * (dt / YAW_RATE_SMOOTHING).min(1.0);
+
+        // The autopilots set the tilt themselves
+        if !assist.enabled || ctl_pos.enabled {
+            turn.bank = 0.0;
+            continue;
+        }
+
+        // Speed along the nose (-Z); turning while flying backwards banks the other way
+        let forward_speed = velocity.linvel.dot(tf.rotation * Vec3::NEG_Z);
+        // Turning left (positive yaw rate) banks left, which is positive roll
+        let bank = assist.gain * (forward_speed * turn.yaw_rate / GRAVITY).atan();
+        // Stay inside the roll limits so clamping can't eat into the pilot's setpoint
+        turn.bank = bank.clamp(
+            ctl_roll.min_angle - ctl_roll.target_angle,
+            ctl_roll.max_angle - ctl_roll.target_angle,
+        );
+        ctl_roll.target_angle += turn.bank;
+    }
+}
+
+pub fn update_turn_text(
+    assist: Res<CoordinatedTurn>,
+    drone_query: Query<&TurnBank, With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<TurnText>>,
+) {
+    let Ok(turn) = drone_query.single() else {
+        return;
+    };
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = if !assist.enabled {
+            ("Turn assist: off".into(), Color::WHITE)
+        } else if turn.bank.abs() < ACTIVE_BANK {
+            ("Turn assist: on".into(), Color::WHITE)
+        } else {
+            (
+                format!("Turn assist: bank {:+.1} deg", turn.bank.to_degrees()).into(),
+                Color::srgb(0.4, 0.8, 1.0),
+            )
+        };
+    }
+}
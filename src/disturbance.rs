@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{ActiveDrone, arg_value};
+
+/// Shortest poke: a quick tap still pushes for this long.
+const MIN_POKE_TIME: f32 = 0.2;
+
+/// Keyboard "poke" for testing disturbance rejection. Arrow keys push the
+/// active drone forward/back/left/right in its body frame, with Shift they
+/// twist it in pitch and roll instead. A tap gives a short bump, holding keeps
+/// pushing.
+#[derive(Resource)]
+pub struct Disturbance {
+    /// Push strength as a horizontal acceleration (m/s²).
+    pub accel: f32,
+    /// Twist strength as an angular acceleration (rad/s²).
+    pub angular_accel: f32,
+    pub remaining: f32,
+    /// Body-frame direction of the current push, or axis of the current twist.
+    pub direction: Vec3,
+    pub twist: bool,
+    /// Last applied world-frame force and torque, for the HUD.
+    pub force: Vec3,
+    pub torque: Vec3,
+}
+
+impl Disturbance {
+    pub fn from_args() -> Self {
+        Self {
+            accel: arg_value("--poke-accel")
+                .and_then(|accel| accel.parse::<f32>().ok())
+                .unwrap_or(10.0),
+            angular_accel: arg_value("--poke-angular-accel")
+                .and_then(|accel| accel.parse::<f32>().ok())
+                .unwrap_or(20.0),
+            remaining: 0.0,
+            direction: Vec3::ZERO,
+            twist: false,
+            force: Vec3::ZERO,
+            torque: Vec3::ZERO,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DisturbanceText;
+
+pub fn disturbance_plugin(app: &mut App) {
+    app.insert_resource(Disturbance::from_args())
+        .add_systems(Update, (poke_drone, update_disturbance_text).chain())
+        .add_systems(
+            FixedUpdate,
+            apply_disturbance.before(PhysicsSet::SyncBackend),
+        );
+}
+
+pub fn poke_drone(keyboard: Res<ButtonInput<KeyCode>>, mut disturbance: ResMut<Disturbance>) {
+    let twist = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    // Body frame: forward is -Z, right is +X. Twists use the pitch (X) and
+    // roll (Z) axes with the same sign convention as the attitude targets.
+    let mut direction = Vec3::ZERO;
+    if keyboard.pressed(KeyCode::ArrowUp) {
+        direction += if twist { Vec3::NEG_X } else { Vec3::NEG_Z };
+    }
+    if keyboard.pressed(KeyCode::ArrowDown) {
+        direction += if twist { Vec3::X } else { Vec3::Z };
+    }
+    if keyboard.pressed(KeyCode::ArrowRight) {
+        direction += if twist { Vec3::NEG_Z } else { Vec3::X };
+    }
+    if keyboard.pressed(KeyCode::ArrowLeft) {
+        direction += if twist { Vec3::Z } else { Vec3::NEG_X };
+    }
+
+    if direction != Vec3::ZERO {
+        disturbance.direction = direction.normalize();
+        disturbance.twist = twist;
+        disturbance.remaining = disturbance.remaining.max(MIN_POKE_TIME);
+    }
+}
+
+pub fn apply_disturbance(
+    time: Res<Time<Fixed>>,
+    mut disturbance: ResMut<Disturbance>,
+    mut drone_query: Query<
+        (&Transform, &ReadMassProperties, &mut ExternalImpulse),
+        With<ActiveDrone>,
+    >,
+) {
+    let dt = time.delta_secs();
+    if disturbance.remaining <= 0.0 {
+        disturbance.force = Vec3::ZERO;
+        disturbance.torque = Vec3::ZERO;
+        return;
+    }
+    disturbance.remaining -= dt;
+
+    for (tf, mass_props, mut ext_impulse) in drone_query.iter_mut() {
+        let world_dir = tf.rotation * disturbance.direction;
+        if disturbance.twist {
+            disturbance.force = Vec3::ZERO;
+            disturbance.torque =
+                world_dir * disturbance.angular_accel * mass_props.principal_inertia.max_element();
+        } else {
+            disturbance.force = world_dir * disturbance.accel * mass_props.mass;
+            disturbance.torque = Vec3::ZERO;
+        }
+
+        ext_impulse.impulse += disturbance.force * dt;
+        ext_impulse.torque_impulse += disturbance.torque * dt;
+    }
+}
+
+pub fn update_disturbance_text(
+    disturbance: Res<Disturbance>,
+    mut text_query: Query<&mut Text, With<DisturbanceText>>,
+) {
+    for mut text in text_query.iter_mut() {
+        *text = if disturbance.torque != Vec3::ZERO {
+            format!("Poke: {:.4} Nm", disturbance.torque.length()).into()
+        } else if disturbance.force != Vec3::ZERO {
+            format!("Poke: {:.3} N", disturbance.force.length()).into()
+        } else {
+            "Poke: -".into()
+        };
+    }
+}
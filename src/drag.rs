@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{Drone, EngineState, Wind, apply_wind, arg_value};
+
+/// Drag on top of the quadratic air drag of `Wind::drag`: a linear term
+/// against the airspeed, which dominates at the low speeds of a hover, and
+/// rotational drag against the spin, so a tumbling or yawing drone slows
+/// down on its own instead of turning freely as in a vacuum. Set with
+/// `--linear-drag`, `--angular-drag` and `--angular-drag-quadratic`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DragModel {
+    /// Force per airspeed (N·s/m).
+    pub linear: f32,
+    /// Torque per angular velocity (N·m·s/rad).
+    pub angular: f32,
+    /// Torque per squared angular velocity (N·m·s²/rad²).
+    pub angular_quadratic: f32,
+}
+
+impl DragModel {
+    pub fn from_args() -> Self {
+        let coefficient = |name: &str, default: f32| {
+            arg_value(name)
+                .and_then(|value| value.parse::<f32>().ok())
+                .unwrap_or(default)
+                .max(0.0)
+        };
+        Self {
+            linear: coefficient("--linear-drag", 0.005),
+            angular: coefficient("--angular-drag", 1e-4),
+            angular_quadratic: coefficient("--angular-drag-quadratic", 0.0),
+        }
+    }
+
+    /// Drag force for an air-relative velocity `v_rel` (wind minus drone).
+    pub fn force(&self, v_rel: Vec3) -> Vec3 {
+        self.linear * v_rel
+    }
+
+    /// Drag torque against the angular velocity `angvel`.
+    pub fn torque(&self, angvel: Vec3) -> Vec3 {
+        -(self.angular + self.angular_quadratic * angvel.length()) * angvel
+    }
+}
+
+pub fn drag_plugin(app: &mut App) {
+    app.add_systems(Update, add_drag_model).add_systems(
+        FixedUpdate,
+        apply_drag
+            .after(apply_wind)
+            .before(PhysicsSet::SyncBackend)
+            .run_if(in_state(EngineState::On)),
+    );
+}
+
+pub fn add_drag_model(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<DragModel>)>,
+) {
+    if drone_query.is_empty() {
+        return;
+    }
+    let drag = DragModel::from_args();
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(drag);
+    }
+}
+
+pub fn apply_drag(
+    time: Res<Time<Fixed>>,
+    wind: Res<Wind>,
+    mut drone_query: Query<(&Transform, &Velocity, &DragModel, &mut ExternalImpulse)>,
+) {
+    let dt = time.delta_secs();
+
+    for (tf, velocity, drag, mut ext_impulse) in drone_query.iter_mut() {
+        let v_rel = wind.sample(tf.translation.y) - velocity.linvel;
+        ext_impulse.impulse += drag.force(v_rel) * dt;
+        ext_impulse.torque_impulse += drag.torque(velocity.angvel) * dt;
+    }
+}
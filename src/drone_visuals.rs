@@ -0,0 +1,308 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{Drone, DronePhysics, EngineState, arg_value, drone_collision_groups, has_flag};
+
+/// Strobe period and how long each flash lasts (s).
+const STROBE_PERIOD: f32 = 1.0;
+const STROBE_FLASH: f32 = 0.08;
+/// Brightness of a lit navigation light.
+const NAV_LIGHT_GLOW: f32 = 8.0;
+/// Time for the gear to fully retract or extend (s).
+const GEAR_TIME: f32 = 1.2;
+/// The gear comes back down this far below the retract altitude so it doesn't
+/// flap when hovering right at the threshold.
+const GEAR_HYSTERESIS: f32 = 0.5;
+const GEAR_LENGTH: f32 = 0.15;
+/// How far below the nose the spotlight points.
+const SPOTLIGHT_TILT: f32 = 40.0;
+
+/// Which cosmetic extras the drones get.
+#[derive(Resource)]
+pub struct DroneVisuals {
+    pub nav_lights: bool,
+    /// Retractable skids. These touch the ground, so they're opt-in.
+    pub landing_gear: bool,
+    /// Altitude above which the gear retracts (m).
+    pub gear_altitude: f32,
+    /// Forward/down spotlight for flying in the dark, off unless `--spotlight`.
+    pub spotlight: Option<SpotlightSettings>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SpotlightSettings {
+    /// Luminous power (lm).
+    pub intensity: f32,
+    pub range: f32,
+    /// Half-angle of the cone (rad).
+    pub angle: f32,
+}
+
+impl DroneVisuals {
+    pub fn from_args() -> Self {
+        Self {
+            nav_lights: !has_flag("--no-nav-lights"),
+            landing_gear: has_flag("--landing-gear"),
+            gear_altitude: arg_value("--gear-altitude")
+                .and_then(|altitude| altitude.parse::<f32>().ok())
+                .unwrap_or(2.0)
+                .max(GEAR_HYSTERESIS),
+            spotlight: has_flag("--spotlight").then(|| SpotlightSettings {
+                intensity: arg_value("--spotlight-intensity")
+                    .and_then(|intensity| intensity.parse::<f32>().ok())
+                    .unwrap_or(1_000_000.0)
+                    .max(0.0),
+                range: arg_value("--spotlight-range")
+                    .and_then(|range| range.parse::<f32>().ok())
+                    .unwrap_or(40.0)
+                    .max(1.0),
+                angle: arg_value("--spotlight-angle")
+                    .and_then(|angle| angle.parse::<f32>().ok())
+                    .unwrap_or(30.0)
+                    .clamp(1.0, 89.0)
+                    .to_radians(),
+            }),
+        }
+    }
+}
+
+/// Animated state of a drone's lights and landing gear.
+#[derive(Component, Default)]
+pub struct DroneVisualState {
+    pub lights_on: bool,
+    pub strobe_phase: f32,
+    pub gear_down: bool,
+    /// 1 when the gear is fully down, 0 when fully retracted.
+    pub gear_extension: f32,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum NavLight {
+    /// Red, on the left.
+    Port,
+    /// Green, on the right.
+    Starboard,
+    /// White flashing light at the back.
+    Strobe,
+}
+
+impl NavLight {
+    fn color(self) -> Color {
+        match self {
+            Self::Port => Color::srgb(1.0, 0.05, 0.05),
+            Self::Starboard => Color::srgb(0.05, 1.0, 0.05),
+            Self::Strobe => Color::WHITE,
+        }
+    }
+}
+
+/// Hinge of one landing gear leg; `side` is -1 on the left and 1 on the right.
+#[derive(Component)]
+pub struct GearHinge {
+    pub side: f32,
+}
+
+#[derive(Component)]
+pub struct GearLeg;
+
+/// The drone's spotlight, toggled with `F4`.
+#[derive(Component)]
+pub struct DroneSpotlight;
+
+pub fn drone_visuals_plugin(app: &mut App) {
+    app.insert_resource(DroneVisuals::from_args()).add_systems(
+        Update,
+        (
+            attach_drone_visuals,
+            update_drone_visual_state,
+            update_nav_lights,
+            animate_landing_gear,
+            toggle_spotlight,
+        )
+            .chain(),
+    );
+}
+
+pub fn attach_drone_visuals(
+    mut commands: Commands,
+    visuals: Res<DroneVisuals>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    drone_query: Query<Entity, Added<Drone>>,
+) {
+    if drone_query.is_empty() {
+        return;
+    }
+    let physics = DronePhysics::from_args();
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(DroneVisualState {
+            gear_down: true,
+            gear_extension: 1.0,
+            ..default()
+        });
+
+        if visuals.nav_lights {
+            let bulb = meshes.add(Sphere::new(0.03));
+            for (light, offset) in [
+                (NavLight::Port, Vec3::new(-0.26, 0.0, 0.0)),
+                (NavLight::Starboard, Vec3::new(0.26, 0.0, 0.0)),
+                (NavLight::Strobe, Vec3::new(0.0, 0.06, 0.26)),
+            ] {
+                // Each light gets its own material so it can blink independently
+                let material = materials.add(StandardMaterial {
+                    base_color: light.color(),
+                    ..default()
+                });
+                commands.entity(drone).with_child((
+                    light,
+                    Mesh3d(bulb.clone()),
+                    MeshMaterial3d(material),
+                    Transform::from_translation(offset),
+                ));
+            }
+        }
+
+        if let Some(spotlight) = visuals.spotlight {
+            // Spotlights shine along their local -Z, which is the drone's nose
+            commands.entity(drone).with_child((
+                DroneSpotlight,
+                SpotLight {
+                    intensity: spotlight.intensity,
+                    range: spotlight.range,
+                    outer_angle: spotlight.angle,
+                    inner_angle: spotlight.angle * 0.8,
+                    shadows_enabled: true,
+                    ..default()
+                },
+                Transform::from_xyz(0.0, -0.06, -0.2)
+                    .with_rotation(Quat::from_rotation_x(-SPOTLIGHT_TILT.to_radians())),
+            ));
+        }
+
+        if visuals.landing_gear {
+            let leg = meshes.add(Cuboid::new(0.03, GEAR_LENGTH, 0.4));
+            let material = materials.add(Color::srgb_u8(60, 60, 60));
+            for side in [-1.0, 1.0] {
+                commands.entity(drone).with_children(|parent| {
+                    parent
+                        .spawn((
+                            GearHinge { side },
+                            Transform::from_xyz(side * 0.18, -0.05, 0.0),
+                            Visibility::default(),
+                        ))
+                        .with_child((
+                            GearLeg,
+                            Mesh3d(leg.clone()),
+                            MeshMaterial3d(material.clone()),
+                            Transform::from_xyz(0.0, -GEAR_LENGTH / 2.0, 0.0),
+                            Collider::cuboid(0.015, GEAR_LENGTH / 2.0, 0.2),
+                            drone_collision_groups(),
+                            physics.bundle(),
+                            ColliderMassProperties::Density(0.001),
+                        ));
+                });
+            }
+        }
+    }
+}
+
+pub fn update_drone_visual_state(
+    time: Res<Time>,
+    visuals: Res<DroneVisuals>,
+    engine_state: Res<State<EngineState>>,
+    mut drone_query: Query<(&Transform, &mut DroneVisualState), With<Drone>>,
+) {
+    let dt = time.delta_secs();
+    let engine_on = *engine_state.get() == EngineState::On;
+
+    for (tf, mut state) in drone_query.iter_mut() {
+        state.lights_on = engine_on;
+        state.strobe_phase = (state.strobe_phase + dt) % STROBE_PERIOD;
+
+        let y = tf.translation.y;
+        if state.gear_down && engine_on && y > visuals.gear_altitude {
+            state.gear_down = false;
+        } else if !state.gear_down && (!engine_on || y < visuals.gear_altitude - GEAR_HYSTERESIS) {
+            state.gear_down = true;
+        }
+
+        let target = if state.gear_down { 1.0 } else { 0.0 };
+        let step = dt / GEAR_TIME;
+        state.gear_extension += (target - state.gear_extension).clamp(-step, step);
+    }
+}
+
+pub fn update_nav_lights(
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    drone_query: Query<&DroneVisualState>,
+    light_query: Query<(&NavLight, &ChildOf, &MeshMaterial3d<StandardMaterial>)>,
+) {
+    for (light, child_of, material) in light_query.iter() {
+        let Ok(state) = drone_query.get(child_of.parent()) else {
+            continue;
+        };
+
+        let lit =
+            state.lights_on && (*light != NavLight::Strobe || state.strobe_phase < STROBE_FLASH);
+        let glow = if lit {
+            LinearRgba::from(light.color()) * NAV_LIGHT_GLOW
+        } else {
+            LinearRgba::BLACK
+        };
+
+        let handle = &material.0;
+        if materials.get(handle).is_some_and(|m| m.emissive != glow)
+            && let Some(material) = materials.get_mut(handle)
+        {
+            material.emissive = glow;
+        }
+    }
+}
+
+pub fn animate_landing_gear(
+    mut commands: Commands,
+    drone_query: Query<&DroneVisualState>,
+    mut hinge_query: Query<(&GearHinge, &ChildOf, &Children, &mut Transform)>,
+    leg_query: Query<Has<ColliderDisabled>, With<GearLeg>>,
+) {
+    for (hinge, child_of, children, mut tf) in hinge_query.iter_mut() {
+        let Ok(state) = drone_query.get(child_of.parent()) else {
+            continue;
+        };
+
+        // Fold each leg inwards until it lies flat under the body
+        let fold = (1.0 - state.gear_extension) * FRAC_PI_2;
+        let rotation = Quat::from_rotation_z(-hinge.side * fold);
+        if tf.rotation != rotation {
+            tf.rotation = rotation;
+        }
+
+        // Only a fully deployed leg can carry the drone
+        let deployed = state.gear_extension >= 1.0;
+        for &leg in children {
+            let Ok(disabled) = leg_query.get(leg) else {
+                continue;
+            };
+            if deployed && disabled {
+                commands.entity(leg).remove::<ColliderDisabled>();
+            } else if !deployed && !disabled {
+                commands.entity(leg).insert(ColliderDisabled);
+            }
+        }
+    }
+}
+
+pub fn toggle_spotlight(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut light_query: Query<&mut Visibility, With<DroneSpotlight>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F4) {
+        return;
+    }
+
+    for mut visibility in light_query.iter_mut() {
+        visibility.toggle_inherited_hidden();
+    }
+}
@@ -0,0 +1,261 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{ActiveDrone, Drone, EngineState, GRAVITY, arg_value, is_airborne};
+
+const AIR_DENSITY: f32 = 1.225;
+/// Total disk area of the four 0.2m propellers (m²).
+const ROTOR_DISK_AREA: f32 = 4.0 * std::f32::consts::PI * 0.2 * 0.2;
+/// Ratio of ideal induced power to the power actually drawn.
+const FIGURE_OF_MERIT: f32 = 0.6;
+/// Time constant of the average power used for the endurance estimate (s).
+const AVERAGE_POWER_TAU: f32 = 3.0;
+const JOULES_PER_WH: f32 = 3600.0;
+/// Share of the full-charge thrust still available just before the battery
+/// is flat.
+const EMPTY_THRUST_FACTOR: f32 = 0.3;
+/// Charge below which the battery panel warns (fraction of capacity).
+const LOW_BATTERY: f32 = 0.2;
+
+/// How electrical power is derived from thrust.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum PowerModel {
+    /// Momentum theory: P = T^1.5 / (FM · sqrt(2ρA)).
+    #[default]
+    Momentum,
+    /// P = k · T, with `k` in W/N.
+    Linear(f32),
+}
+
+impl PowerModel {
+    pub fn from_args() -> Self {
+        match arg_value("--power-model").as_deref() {
+            None | Some("momentum") => Self::Momentum,
+            Some("linear") => Self::Linear(
+                arg_value("--power-per-newton")
+                    .and_then(|k| k.parse::<f32>().ok())
+                    .unwrap_or(0.75)
+                    .max(0.0),
+            ),
+            Some(other) => {
+                warn!("Unknown --power-model {other}, expected momentum or linear");
+                Self::Momentum
+            }
+        }
+    }
+
+    pub fn power(self, thrust: f32) -> f32 {
+        match self {
+            Self::Momentum => {
+                thrust.powf(1.5) / (FIGURE_OF_MERIT * (2.0 * AIR_DENSITY * ROTOR_DISK_AREA).sqrt())
+            }
+            Self::Linear(k) => k * thrust,
+        }
+    }
+}
+
+/// Power drawn by the active drone.
+#[derive(Resource)]
+pub struct EnergyBudget {
+    pub model: PowerModel,
+    /// Instantaneous and smoothed power draw (W).
+    pub power: f32,
+    pub average_power: f32,
+}
+
+impl EnergyBudget {
+    pub fn from_args() -> Self {
+        Self {
+            model: PowerModel::from_args(),
+            power: 0.0,
+            average_power: 0.0,
+        }
+    }
+
+    /// Flight time `battery` has left at the recent average power draw, if
+    /// drawing any (s).
+    pub fn endurance(&self, battery: &Battery) -> Option<f32> {
+        (self.average_power > f32::EPSILON).then(|| battery.charge / self.average_power)
+    }
+}
+
+/// A drone's battery. Its voltage sags as it drains, so the thrust the hover
+/// controller can command falls from `thrust_to_weight` times the weight at
+/// full charge to `EMPTY_THRUST_FACTOR` of that near empty, and to nothing
+/// once it's flat: a long flight ends with the drone unable to hold altitude.
+/// Recharges while the drone is on the ground with the engine off.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Battery {
+    /// Usable energy when full (J).
+    pub capacity: f32,
+    pub charge: f32,
+    /// Thrust-to-weight ratio available at full charge.
+    pub thrust_to_weight: f32,
+    /// Time a flat battery takes to charge fully (s).
+    pub recharge_time: f32,
+}
+
+impl Battery {
+    pub fn from_args() -> Self {
+        let capacity = arg_value("--battery-wh")
+            .and_then(|wh| wh.parse::<f32>().ok())
+            .unwrap_or(0.05)
+            .max(0.0)
+            * JOULES_PER_WH;
+        Self {
+            capacity,
+            charge: capacity,
+            thrust_to_weight: arg_value("--battery-thrust-to-weight")
+                .and_then(|ratio| ratio.parse::<f32>().ok())
+                .unwrap_or(3.0)
+                .max(0.0),
+            recharge_time: arg_value("--battery-recharge")
+                .and_then(|secs| secs.parse::<f32>().ok())
+                .unwrap_or(20.0)
+                .max(0.1),
+        }
+    }
+
+    /// Charge left, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.capacity > 0.0 {
+            (self.charge / self.capacity).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.fraction() < LOW_BATTERY
+    }
+
+    /// Largest thrust the battery can deliver for a drone of `weight` (N).
+    pub fn thrust_limit(&self, weight: f32) -> f32 {
+        if self.charge <= 0.0 {
+            return 0.0;
+        }
+        let sag = EMPTY_THRUST_FACTOR + (1.0 - EMPTY_THRUST_FACTOR) * self.fraction();
+        self.thrust_to_weight * weight * sag
+    }
+}
+
+#[derive(Component)]
+pub struct EnergyText;
+
+#[derive(Component)]
+pub struct BatteryText;
+
+pub fn energy_plugin(app: &mut App) {
+    app.insert_resource(EnergyBudget::from_args())
+        .add_systems(
+            Update,
+            (
+                add_battery,
+                accumulate_energy.run_if(in_state(EngineState::On)),
+                recharge_batteries.run_if(in_state(EngineState::Off)),
+            )
+                .chain(),
+        )
+        .add_systems(OnExit(EngineState::On), stop_power_draw)
+        .add_systems(Update, (update_energy_text, update_battery_text));
+}
+
+pub fn add_battery(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<Battery>)>,
+) {
+    if drone_query.is_empty() {
+        return;
+    }
+    let battery = Battery::from_args();
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(battery);
+    }
+}
+
+/// Drains every drone's battery by the power its thrust draws; the active
+/// drone's draw is what the HUD shows.
+pub fn accumulate_energy(
+    time: Res<Time>,
+    mut energy: ResMut<EnergyBudget>,
+    mut drone_query: Query<(&ExternalForce, &mut Battery, Has<ActiveDrone>)>,
+) {
+    let dt = time.delta_secs();
+
+    for (force, mut battery, active) in drone_query.iter_mut() {
+        let power = energy.model.power(force.force.length());
+        battery.charge = (battery.charge - power * dt).max(0.0);
+        if !active {
+            continue;
+        }
+
+        energy.power = power;
+        let alpha = 1.0 - (-dt / AVERAGE_POWER_TAU).exp();
+        energy.average_power += (energy.power - energy.average_power) * alpha;
+    }
+}
+
+/// Batteries charge while their drone sits on the ground.
+pub fn recharge_batteries(time: Res<Time>, mut drone_query: Query<(&Transform, &mut Battery)>) {
+    let dt = time.delta_secs();
+
+    for (tf, mut battery) in drone_query.iter_mut() {
+        if is_airborne(tf) {
+            continue;
+        }
+        let rate = battery.capacity / battery.recharge_time;
+        battery.charge = (battery.charge + rate * dt).min(battery.capacity);
+    }
+}
+
+pub fn stop_power_draw(mut energy: ResMut<EnergyBudget>) {
+    energy.power = 0.0;
+    energy.average_power = 0.0;
+}
+
+pub fn update_energy_text(
+    energy: Res<EnergyBudget>,
+    drone_query: Query<&Battery, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<EnergyText>>,
+) {
+    let endurance = drone_query
+        .single()
+        .ok()
+        .and_then(|battery| energy.endurance(battery))
+        .map_or("-".to_string(), |secs| {
+            let secs = secs.round() as u32;
+            format!("{}:{:02}", secs / 60, secs % 60)
+        });
+
+    for mut text in text_query.iter_mut() {
+        *text = format!("{:.2} W {endurance} left", energy.power).into();
+    }
+}
+
+pub fn update_battery_text(
+    drone_query: Query<(&Battery, &ReadMassProperties), With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<BatteryText>>,
+) {
+    let Ok((battery, mass_props)) = drone_query.single() else {
+        return;
+    };
+    let weight = mass_props.mass * GRAVITY;
+    let max_thrust = if weight > 0.0 {
+        battery.thrust_limit(weight) / weight
+    } else {
+        0.0
+    };
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        *text = format!(
+            "Battery: {:.0}% ({max_thrust:.1}g)",
+            100.0 * battery.fraction()
+        )
+        .into();
+        color.0 = if battery.is_low() {
+            Color::srgb(1.0, 0.2, 0.2)
+        } else {
+            Color::WHITE
+        };
+    }
+}
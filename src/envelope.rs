@@ -0,0 +1,264 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ActiveDrone, Drone, EngineState, HoverPid, PitchPid, RollPid, TumbleDisarm,
+    altitude_floor::{AltitudeFloor, protect_altitude},
+    arg_value, clamp_setpoints,
+    position::Home,
+    position_control,
+    scene::ResetDrones,
+};
+
+/// Cycles through the protection levels.
+const LEVEL_KEY: KeyCode = KeyCode::Backslash;
+
+/// Grouped settings of the safety features, picked with `--protection`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtectionLevel {
+    /// Small tilt, a floor above the ground, gentle descents and a fence.
+    Beginner,
+    /// The stock limits: 30° tilt and tumble disarm.
+    Normal,
+    /// Everything off but a wide tilt range.
+    Acro,
+}
+
+impl ProtectionLevel {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "beginner" => Some(Self::Beginner),
+            "normal" => Some(Self::Normal),
+            "acro" | "off" => Some(Self::Acro),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Beginner => "beginner",
+            Self::Normal => "normal",
+            Self::Acro => "acro",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Beginner => Self::Normal,
+            Self::Normal => Self::Acro,
+            Self::Acro => Self::Beginner,
+        }
+    }
+
+    /// Largest pitch and roll setpoint (rad).
+    fn tilt_limit(self) -> f32 {
+        match self {
+            Self::Beginner => 20_f32.to_radians(),
+            Self::Normal => 30_f32.to_radians(),
+            Self::Acro => 60_f32.to_radians(),
+        }
+    }
+
+    /// Altitude floor clearance (m), if any.
+    fn floor(self) -> Option<f32> {
+        match self {
+            Self::Beginner => Some(1.0),
+            Self::Normal | Self::Acro => None,
+        }
+    }
+
+    fn tumble_disarm(self) -> bool {
+        self != Self::Acro
+    }
+
+    fn max_descent(self) -> Option<f32> {
+        match self {
+            Self::Beginner => Some(1.5),
+            Self::Normal => Some(4.0),
+            Self::Acro => None,
+        }
+    }
+
+    fn runaway_radius(self) -> Option<f32> {
+        match self {
+            Self::Beginner => Some(50.0),
+            Self::Normal => Some(200.0),
+            Self::Acro => None,
+        }
+    }
+}
+
+/// One knob for the safety features: a `ProtectionLevel` sets the tilt limit,
+/// altitude floor, tumble disarm, descent-rate limit and runaway reset
+/// together. Without `--protection` the features keep their own flags
+/// (`custom`), and the descent limit and runaway reset come from
+/// `--max-descent` and `--runaway-radius`; `\` cycles through the levels.
+#[derive(Resource)]
+pub struct EnvelopeProtection {
+    pub level: Option<ProtectionLevel>,
+    /// Fastest the altitude setpoint may take the drone down (m/s).
+    pub max_descent: Option<f32>,
+    /// Distance from its spawn point past which a drone is reset (m).
+    pub runaway_radius: Option<f32>,
+}
+
+impl EnvelopeProtection {
+    pub fn from_args() -> Self {
+        let level = arg_value("--protection").and_then(|name| {
+            let level = ProtectionLevel::from_name(&name);
+            if level.is_none() {
+                warn!("Unknown --protection {name}, expected beginner, normal or acro");
+            }
+            level
+        });
+        let limit = |name: &str| {
+            arg_value(name)
+                .and_then(|value| value.parse::<f32>().ok())
+                .filter(|value| *value > 0.0)
+        };
+
+        Self {
+            level,
+            max_descent: limit("--max-descent"),
+            runaway_radius: limit("--runaway-radius"),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        self.level.map_or("custom", ProtectionLevel::label)
+    }
+}
+
+#[derive(Component)]
+pub struct ProtectionText;
+
+pub fn envelope_plugin(app: &mut App) {
+    app.insert_resource(EnvelopeProtection::from_args())
+        .add_systems(
+            Update,
+            (
+                cycle_protection_level,
+                apply_protection_level.run_if(resource_changed::<EnvelopeProtection>),
+                update_protection_text,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                limit_descent
+                    .after(clamp_setpoints)
+                    .after(position_control)
+                    .after(protect_altitude),
+                reset_runaway,
+            )
+                .run_if(in_state(EngineState::On)),
+        );
+}
+
+pub fn cycle_protection_level(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut protection: ResMut<EnvelopeProtection>,
+) {
+    if !keyboard.just_pressed(LEVEL_KEY) {
+        return;
+    }
+
+    let level = protection
+        .level
+        .map_or(ProtectionLevel::Beginner, ProtectionLevel::next);
+    protection.level = Some(level);
+    info!("Protection level: {}", level.label());
+}
+
+/// Hands the level's settings to the individual features. Runs when the
+/// level changes, so the features' own toggles still work in between.
+pub fn apply_protection_level(
+    mut protection: ResMut<EnvelopeProtection>,
+    mut floor: ResMut<AltitudeFloor>,
+    mut tumble: ResMut<TumbleDisarm>,
+    mut drone_query: Query<(&mut PitchPid, &mut RollPid), With<Drone>>,
+) {
+    let Some(level) = protection.level else {
+        return;
+    };
+
+    let tilt = level.tilt_limit();
+    for (mut ctl_pitch, mut ctl_roll) in drone_query.iter_mut() {
+        (ctl_pitch.min_angle, ctl_pitch.max_angle) = (-tilt, tilt);
+        (ctl_roll.min_angle, ctl_roll.max_angle) = (-tilt, tilt);
+    }
+    floor.enabled = level.floor().is_some();
+    if let Some(clearance) = level.floor() {
+        floor.clearance = clearance;
+    }
+    tumble.enabled = level.tumble_disarm();
+    // Keep this system's own change from retriggering it
+    let protection = protection.bypass_change_detection();
+    protection.max_descent = level.max_descent();
+    protection.runaway_radius = level.runaway_radius();
+}
+
+/// Keeps the altitude setpoint close enough below the drone that the hover
+/// controller settles at no more than `max_descent`, and holds the drone
+/// where it is if it's already coming down faster.
+pub fn limit_descent(
+    protection: Res<EnvelopeProtection>,
+    mut drone_query: Query<(&Transform, &Velocity, &mut HoverPid)>,
+) {
+    let Some(max_descent) = protection.max_descent else {
+        return;
+    };
+
+    for (tf, velocity, mut ctl_y) in drone_query.iter_mut() {
+        let y = tf.translation.y;
+        // The P and D terms balance at a descent rate of kp / kd times the error
+        let lead = if ctl_y.kp > 0.0 {
+            ctl_y.kd / ctl_y.kp
+        } else {
+            1.0
+        };
+        let mut min_target = y - max_descent * lead;
+        if velocity.linvel.y < -max_descent {
+            min_target = y;
+        }
+        if ctl_y.target_y < min_target {
+            ctl_y.target_y = min_target.min(ctl_y.max_y);
+        }
+    }
+}
+
+/// A drone that has got away further than the runaway radius from its spawn
+/// point is put back on it, engine off.
+pub fn reset_runaway(
+    protection: Res<EnvelopeProtection>,
+    drone_query: Query<(&Transform, &Home, &Name), With<Drone>>,
+    mut reset_events: EventWriter<ResetDrones>,
+) {
+    let Some(radius) = protection.runaway_radius else {
+        return;
+    };
+
+    for (tf, home, name) in drone_query.iter() {
+        let distance = tf.translation.distance(home.0);
+        if distance > radius {
+            warn!("{name} ran away ({distance:.0} m from home), resetting");
+            reset_events.write(ResetDrones);
+            return;
+        }
+    }
+}
+
+pub fn update_protection_text(
+    protection: Res<EnvelopeProtection>,
+    drone_query: Query<&PitchPid, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<ProtectionText>>,
+) {
+    let tilt = drone_query
+        .single()
+        .map_or(0.0, |ctl_pitch| ctl_pitch.max_angle.to_degrees());
+
+    for mut text in text_query.iter_mut() {
+        *text = format!("Protect: {} ({tilt:.0}°)", protection.label()).into();
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::Path,
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    Drone, EngineState, GyroSaturation, angle_error, arg_value,
+    stats::{EffortTracker, accumulate_effort},
+    telemetry::{LogFormat, LogWriter, TelemetryData, TelemetryLogger, TelemetrySample},
+};
+
+pub const DEFAULT_TRIGGERS_PATH: &str = "assets/triggers/default.ron";
+/// Window a setpoint step is measured over (s), so a step spread over a few
+/// frames by a rate limit still counts.
+const STEP_WINDOW: f32 = 0.25;
+/// Key for a manual trigger.
+const TRIGGER_KEY: KeyCode = KeyCode::F3;
+
+/// Something worth capturing the data around.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LogTrigger {
+    /// A pitch, roll or yaw setpoint moving by more than this many degrees.
+    SetpointStep(f32),
+    /// The altitude setpoint moving by more than this many meters.
+    AltitudeStep(f32),
+    /// The mixer running out of rotor authority (needs `--thrust-to-weight`).
+    Saturation,
+    /// The gyro clipping (needs `--gyro-range`).
+    GyroSaturation,
+    /// Pressing `F3`.
+    Key,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TriggerConfig {
+    /// Data kept from before the trigger (s).
+    pub pre_trigger: f32,
+    /// How long the capture runs after the trigger (s).
+    pub duration: f32,
+    pub triggers: Vec<LogTrigger>,
+}
+
+impl Default for TriggerConfig {
+    fn default() -> Self {
+        Self {
+            pre_trigger: 2.0,
+            duration: 5.0,
+            triggers: vec![LogTrigger::Key],
+        }
+    }
+}
+
+impl TriggerConfig {
+    pub fn load(path: &str) -> Self {
+        let config = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                ron::from_str::<TriggerConfig>(&contents).map_err(|e| e.to_string())
+            });
+
+        match config {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Failed to load log triggers {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Event-triggered logging: keeps the last `pre_trigger` seconds of telemetry
+/// in a ring buffer, and when one of the configured triggers fires writes
+/// them out to a new file followed by the next `duration` seconds. Another
+/// trigger during a capture extends it. Files are numbered after the
+/// `--event-log` path (`events.csv` → `events_000.csv`) and use the
+/// `--log-format`. Off unless `--event-log` is given.
+#[derive(Resource)]
+pub struct EventLog {
+    pub path: Option<String>,
+    pub format: LogFormat,
+    pub config: TriggerConfig,
+    /// Recent rows of every drone, oldest first.
+    pub history: VecDeque<TelemetrySample>,
+    /// File being captured into, and the time it ends (s).
+    pub capture: Option<(LogWriter, f32)>,
+    pub captures: usize,
+    /// Whether each drone was saturated (mixer, gyro) last frame, so only
+    /// the onset triggers.
+    pub saturated: BTreeMap<usize, (bool, bool)>,
+    pub start: Option<f32>,
+}
+
+impl EventLog {
+    pub fn from_args() -> Self {
+        let path = arg_value("--event-log");
+        let config = if path.is_some() {
+            TriggerConfig::load(
+                &arg_value("--log-triggers").unwrap_or(DEFAULT_TRIGGERS_PATH.to_string()),
+            )
+        } else {
+            TriggerConfig::default()
+        };
+
+        Self {
+            path,
+            format: LogFormat::from_args(),
+            config,
+            history: VecDeque::new(),
+            capture: None,
+            captures: 0,
+            saturated: BTreeMap::new(),
+            start: None,
+        }
+    }
+
+    /// The largest change of the attitude and altitude setpoints over
+    /// `STEP_WINDOW` up to `sample` (deg, m).
+    fn setpoint_steps(&self, sample: &TelemetrySample) -> (f32, f32) {
+        let Some(earlier) = self
+            .history
+            .iter()
+            .find(|old| old.drone == sample.drone && sample.time - old.time <= STEP_WINDOW)
+        else {
+            return (0.0, 0.0);
+        };
+
+        let values = |column: &str| {
+            let now = sample.value(column).unwrap_or_default();
+            let then = earlier.value(column).unwrap_or_default();
+            (now, then)
+        };
+        let angle = ["target_pitch", "target_roll", "target_yaw"]
+            .into_iter()
+            .map(|column| {
+                let (now, then) = values(column);
+                angle_error(now, then).abs().to_degrees()
+            })
+            .fold(0.0, f32::max);
+        let (now, then) = values("target_y");
+        (angle, (now - then).abs())
+    }
+
+    /// Writes the buffered rows to a new file, or extends the capture under way.
+    fn trigger(&mut self, reason: &str, now: f32, header: &[String]) {
+        let end = now + self.config.duration;
+        if let Some((_, capture_end)) = &mut self.capture {
+            *capture_end = end;
+            return;
+        }
+        let Some(path) = self.path.as_deref() else {
+            return;
+        };
+
+        let path = numbered_path(path, self.captures);
+        let mut header = header.to_vec();
+        header.push(format!("trigger: {reason} at {now:.2} s"));
+        let Some(mut writer) = LogWriter::create(path.clone(), self.format, true, &header) else {
+            // Don't retry on every trigger
+            self.path = None;
+            return;
+        };
+        for sample in &self.history {
+            writer.write(self.format, *sample);
+        }
+
+        info!("Log triggered by {reason}, capturing to {path}");
+        self.captures += 1;
+        self.capture = Some((writer, end));
+    }
+
+    pub fn finish(&mut self) {
+        if let Some((writer, _)) = self.capture.take() {
+            writer.finish(self.format);
+        }
+    }
+}
+
+/// `events.csv` → `events_003.csv`.
+fn numbered_path(path: &str, index: usize) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}_{index:03}.{}", extension.to_string_lossy()),
+        None => format!("{stem}_{index:03}"),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+pub fn event_log_plugin(app: &mut App) {
+    let log = EventLog::from_args();
+    let enabled = log.path.is_some();
+
+    app.insert_resource(log)
+        .add_systems(
+            Update,
+            capture_events
+                .after(accumulate_effort)
+                .run_if(in_state(EngineState::On))
+                .run_if(move || enabled),
+        )
+        .add_systems(Last, finish_event_log_on_exit);
+}
+
+#[allow(clippy::type_complexity)]
+pub fn capture_events(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    logger: Res<TelemetryLogger>,
+    mut log: ResMut<EventLog>,
+    drone_query: Query<
+        (
+            TelemetryData,
+            Option<&EffortTracker>,
+            Option<&GyroSaturation>,
+        ),
+        With<Drone>,
+    >,
+) {
+    // Nothing moves while paused
+    if time.delta_secs() <= 0.0 {
+        return;
+    }
+
+    let elapsed = time.elapsed_secs();
+    let now = elapsed - *log.start.get_or_insert(elapsed);
+    let key = keyboard.just_pressed(TRIGGER_KEY);
+
+    for (data, effort, gyro) in drone_query.iter() {
+        let sample = TelemetrySample::capture(now, data);
+        let saturated = effort.is_some_and(|effort| effort.saturated);
+        let gyro_saturated = gyro.is_some_and(|gyro| gyro.saturated);
+        let (was_saturated, was_gyro_saturated) = log
+            .saturated
+            .insert(sample.drone, (saturated, gyro_saturated))
+            .unwrap_or_default();
+        let (angle_step, altitude_step) = log.setpoint_steps(&sample);
+
+        let fired = log
+            .config
+            .triggers
+            .iter()
+            .copied()
+            .find(|trigger| match *trigger {
+                LogTrigger::SetpointStep(degrees) => angle_step > degrees,
+                LogTrigger::AltitudeStep(meters) => altitude_step > meters,
+                LogTrigger::Saturation => saturated && !was_saturated,
+                LogTrigger::GyroSaturation => gyro_saturated && !was_gyro_saturated,
+                LogTrigger::Key => key,
+            });
+
+        log.history.push_back(sample);
+        let format = log.format;
+        if let Some((writer, _)) = &mut log.capture {
+            writer.write(format, sample);
+        }
+        if let Some(trigger) = fired {
+            log.trigger(
+                &format!("{trigger:?} (drone {})", sample.drone),
+                now,
+                &logger.header,
+            );
+        }
+    }
+
+    let keep_from = now - log.config.pre_trigger;
+    while log
+        .history
+        .front()
+        .is_some_and(|sample| sample.time < keep_from)
+    {
+        log.history.pop_front();
+    }
+    if log.capture.as_ref().is_some_and(|(_, end)| now >= *end) {
+        log.finish();
+    }
+}
+
+pub fn finish_event_log_on_exit(mut exit: EventReader<AppExit>, mut log: ResMut<EventLog>) {
+    if exit.read().next().is_some() {
+        log.finish();
+    }
+}
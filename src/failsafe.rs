@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+
+use crate::{
+    Drone, EngineState, HoverPid, PitchPid, RollPid, arg_value, center_setpoints, chase_guidance,
+    gamepad_control,
+    position::{Home, PositionPid, release_tilt},
+};
+
+/// Descent rate of the land failsafe (m/s).
+const LAND_RATE: f32 = 0.8;
+
+/// What the drone does once the control link has gone quiet.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailsafeAction {
+    /// Level out and hold the current altitude.
+    #[default]
+    Hold,
+    /// Level out and fly back above the spawn point.
+    ReturnHome,
+    /// Level out and descend to the ground.
+    Land,
+}
+
+impl FailsafeAction {
+    pub fn from_args() -> Self {
+        match arg_value("--failsafe").as_deref() {
+            None | Some("hold") => Self::Hold,
+            Some("rth") => Self::ReturnHome,
+            Some("land") => Self::Land,
+            Some(other) => {
+                warn!("Unknown --failsafe {other}, expected hold, rth or land");
+                Self::Hold
+            }
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Hold => "hold",
+            Self::ReturnHome => "RTH",
+            Self::Land => "land",
+        }
+    }
+}
+
+/// Last time a remote input source (gamepad, network commands) reported in.
+/// Keyboard input is local and never drops out, so it doesn't count.
+#[derive(Resource, Default)]
+pub struct InputLink {
+    pub last_update: Option<f32>,
+}
+
+impl InputLink {
+    pub fn touch(&mut self, now: f32) {
+        self.last_update = Some(now);
+    }
+
+    /// Seconds since the last update, or `None` if no source has connected yet.
+    pub fn age(&self, now: f32) -> Option<f32> {
+        self.last_update.map(|last| now - last)
+    }
+}
+
+/// RC-style failsafe: once a remote input source has been seen, losing it for
+/// longer than `timeout` levels the drone and runs `action`.
+#[derive(Resource)]
+pub struct Failsafe {
+    pub action: FailsafeAction,
+    pub timeout: f32,
+    pub active: bool,
+}
+
+impl Failsafe {
+    pub fn from_args() -> Self {
+        Self {
+            action: FailsafeAction::from_args(),
+            timeout: arg_value("--failsafe-timeout")
+                .and_then(|timeout| timeout.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .max(0.0),
+            active: false,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct FailsafeText;
+
+pub fn failsafe_plugin(app: &mut App) {
+    app.init_resource::<InputLink>()
+        .insert_resource(Failsafe::from_args())
+        .add_systems(
+            Update,
+            failsafe_control
+                .after(gamepad_control)
+                .before(center_setpoints)
+                .before(chase_guidance)
+                .run_if(in_state(EngineState::On)),
+        )
+        .add_systems(Update, update_failsafe_text)
+        .add_systems(OnExit(EngineState::On), reset_failsafe);
+}
+
+#[allow(clippy::type_complexity)]
+pub fn failsafe_control(
+    time: Res<Time>,
+    link: Res<InputLink>,
+    mut failsafe: ResMut<Failsafe>,
+    mut drone_query: Query<
+        (
+            &Transform,
+            &Home,
+            &mut PositionPid,
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+        ),
+        With<Drone>,
+    >,
+) {
+    let Some(age) = link.age(time.elapsed_secs()) else {
+        return;
+    };
+    let lost = age > failsafe.timeout;
+    let action = failsafe.action;
+
+    if lost && !failsafe.active {
+        warn!("Input link lost for {age:.1}s, failsafe {}", action.label());
+    } else if !lost && failsafe.active {
+        info!("Input link restored, failsafe cleared");
+    }
+
+    for (tf, home, mut ctl_pos, mut ctl_y, mut ctl_pitch, mut ctl_roll) in drone_query.iter_mut() {
+        if !lost {
+            if failsafe.active && action == FailsafeAction::ReturnHome {
+                ctl_pos.disengage();
+                release_tilt(&mut ctl_pitch, &mut ctl_roll);
+            }
+            continue;
+        }
+
+        if !failsafe.active {
+            match action {
+                FailsafeAction::Hold | FailsafeAction::Land => {
+                    ctl_pos.disengage();
+                    ctl_y.target_y = tf.translation.y;
+                }
+                // Come back at the current altitude so nothing in between gets clipped
+                FailsafeAction::ReturnHome => {
+                    let target = Vec3::new(home.0.x, tf.translation.y, home.0.z);
+                    let tilt = Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle);
+                    ctl_pos.engage(target, tf.translation, tilt);
+                }
+            }
+        }
+
+        // Level off at the centering rate rather than snapping flat
+        if action != FailsafeAction::ReturnHome {
+            release_tilt(&mut ctl_pitch, &mut ctl_roll);
+        }
+        if action == FailsafeAction::Land {
+            ctl_y.target_y -= LAND_RATE * time.delta_secs();
+        }
+    }
+
+    failsafe.active = lost;
+}
+
+pub fn reset_failsafe(mut failsafe: ResMut<Failsafe>) {
+    failsafe.active = false;
+}
+
+pub fn update_failsafe_text(
+    time: Res<Time>,
+    link: Res<InputLink>,
+    failsafe: Res<Failsafe>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<FailsafeText>>,
+) {
+    for (mut text, mut color) in text_query.iter_mut() {
+        *text = if failsafe.active {
+            format!("FAILSAFE: {}", failsafe.action.label()).into()
+        } else if link.age(time.elapsed_secs()).is_some() {
+            "Link: OK".into()
+        } else {
+            "Link: -".into()
+        };
+        color.0 = if failsafe.active {
+            Color::srgb(1.0, 0.2, 0.2)
+        } else {
+            Color::WHITE
+        };
+    }
+}
@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{DroneCamera, FollowDrone, arg_value, update_camera_pos};
+
+pub const DEFAULT_CAMERA_PATH: &str = "assets/camera/flythrough.ron";
+
+/// Scripted camera move through `control_points` over `duration` seconds.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CameraPath {
+    pub control_points: Vec<[f32; 3]>,
+    /// Point to keep in view; without it the camera looks along the path.
+    #[serde(default)]
+    pub look_at: Option<[f32; 3]>,
+    pub duration: f32,
+}
+
+impl CameraPath {
+    pub fn load(path: &str) -> Option<Self> {
+        let camera_path = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| ron::from_str::<CameraPath>(&contents).map_err(|e| e.to_string()));
+
+        match camera_path {
+            Ok(camera_path) => Some(camera_path),
+            Err(e) => {
+                warn!("Failed to load camera path {path}: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// The loaded path as a Catmull-Rom spline, and how far into it we are while playing.
+#[derive(Resource, Default)]
+pub struct Flythrough {
+    pub path: Option<CameraPath>,
+    pub curve: Option<CubicCurve<Vec3>>,
+    pub elapsed: Option<f32>,
+}
+
+impl Flythrough {
+    pub fn new(path: Option<CameraPath>) -> Self {
+        let curve = path.as_ref().and_then(|path| {
+            let points = path.control_points.iter().map(|&p| Vec3::from(p));
+            match CubicCardinalSpline::new_catmull_rom(points).to_curve() {
+                Ok(curve) => Some(curve),
+                Err(e) => {
+                    warn!("Camera path needs at least two control points: {e}");
+                    None
+                }
+            }
+        });
+
+        Self {
+            path,
+            curve,
+            elapsed: None,
+        }
+    }
+}
+
+pub fn flythrough_plugin(app: &mut App) {
+    let path =
+        CameraPath::load(&arg_value("--camera-path").unwrap_or(DEFAULT_CAMERA_PATH.to_string()));
+
+    app.insert_resource(Flythrough::new(path)).add_systems(
+        Update,
+        (toggle_flythrough, play_flythrough)
+            .chain()
+            .after(update_camera_pos),
+    );
+}
+
+pub fn toggle_flythrough(keyboard: Res<ButtonInput<KeyCode>>, mut flythrough: ResMut<Flythrough>) {
+    if !keyboard.just_pressed(KeyCode::KeyN) || flythrough.curve.is_none() {
+        return;
+    }
+
+    flythrough.elapsed = match flythrough.elapsed {
+        Some(_) => None,
+        None => Some(0.0),
+    };
+}
+
+/// Moves the main camera along the path, overriding the orbit camera until the
+/// path ends and following picks up again.
+pub fn play_flythrough(
+    time: Res<Time>,
+    mut flythrough: ResMut<Flythrough>,
+    mut cam_query: Query<&mut Transform, (With<DroneCamera>, Without<FollowDrone>)>,
+) {
+    let Some(elapsed) = flythrough.elapsed else {
+        return;
+    };
+    let (Some(path), Some(curve)) = (&flythrough.path, &flythrough.curve) else {
+        return;
+    };
+
+    let elapsed = elapsed + time.delta_secs();
+    let progress = (elapsed / path.duration.max(f32::EPSILON)).min(1.0);
+    // Ease in and out so the shot doesn't start or stop abruptly
+    let eased = progress * progress * (3.0 - 2.0 * progress);
+    let t = eased * curve.segments().len() as f32;
+
+    let position = curve.position(t);
+    let focus = match path.look_at {
+        Some(look_at) => Vec3::from(look_at),
+        None => position + curve.velocity(t).normalize_or(Vec3::NEG_Z),
+    };
+
+    for mut cam_trans in cam_query.iter_mut() {
+        cam_trans.translation = position;
+        cam_trans.look_at(focus, Vec3::Y);
+    }
+
+    flythrough.elapsed = (progress < 1.0).then_some(elapsed);
+}
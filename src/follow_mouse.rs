@@ -0,0 +1,166 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    ActiveDrone, DroneCamera, EngineState, PitchPid, RollPid, arg_value, chase_guidance,
+    landing::LandingState,
+    mission::WaypointMission,
+    orbit::OrbitPoi,
+    position::{PositionPid, release_tilt},
+    position_control,
+    return_home::ReturningHome,
+};
+
+const FOLLOW_KEY: KeyCode = KeyCode::F2;
+
+/// Settings of the follow-the-cursor mode.
+#[derive(Resource)]
+pub struct FollowMouseSettings {
+    /// Altitude the drone flies at (m).
+    pub altitude: f32,
+    /// Furthest the target leads the drone (m), which bounds how hard it
+    /// chases a cursor flicked across the screen.
+    pub max_lead: f32,
+}
+
+impl FollowMouseSettings {
+    pub fn from_args() -> Self {
+        Self {
+            altitude: arg_value("--follow-altitude")
+                .and_then(|altitude| altitude.parse::<f32>().ok())
+                .unwrap_or(5.0),
+            max_lead: arg_value("--follow-lead")
+                .and_then(|lead| lead.parse::<f32>().ok())
+                .unwrap_or(5.0)
+                .max(0.5),
+        }
+    }
+}
+
+/// Flies towards the point on the ground under the mouse cursor, at a fixed
+/// altitude.
+#[derive(Component, Default)]
+pub struct FollowCursor {
+    /// Ground point under the cursor, while it's over the view.
+    pub ground: Option<Vec3>,
+}
+
+pub fn follow_mouse_plugin(app: &mut App) {
+    app.insert_resource(FollowMouseSettings::from_args())
+        .add_systems(
+            Update,
+            (
+                toggle_follow_mouse,
+                follow_cursor
+                    .after(chase_guidance)
+                    .before(position_control)
+                    .run_if(in_state(EngineState::On)),
+            )
+                .chain(),
+        )
+        .add_systems(Update, draw_cursor_target);
+}
+
+#[allow(clippy::type_complexity)]
+pub fn toggle_follow_mouse(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<FollowMouseSettings>,
+    mut drone_query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut PositionPid,
+            &mut PitchPid,
+            &mut RollPid,
+            Has<FollowCursor>,
+        ),
+        With<ActiveDrone>,
+    >,
+) {
+    if !keyboard.just_pressed(FOLLOW_KEY) {
+        return;
+    }
+
+    for (drone, tf, mut ctl_pos, mut ctl_pitch, mut ctl_roll, following) in drone_query.iter_mut() {
+        if following {
+            commands.entity(drone).remove::<FollowCursor>();
+            ctl_pos.disengage();
+            release_tilt(&mut ctl_pitch, &mut ctl_roll);
+            info!("Follow mouse off");
+            continue;
+        }
+
+        // Hold where it is until the cursor is over the view
+        let target = Vec3::new(tf.translation.x, settings.altitude, tf.translation.z);
+        let tilt = Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle);
+        ctl_pos.engage(target, tf.translation, tilt);
+        info!("Follow mouse on");
+        commands
+            .entity(drone)
+            .remove::<(WaypointMission, OrbitPoi, LandingState, ReturningHome)>()
+            .insert(FollowCursor::default());
+    }
+}
+
+/// Ray from the camera through the mouse cursor, while it's over the view.
+/// With a split screen, from the camera of the half the cursor is over.
+pub fn cursor_ray(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<DroneCamera>>,
+) -> Option<Ray3d> {
+    let cursor = window_query.single().ok()?.cursor_position()?;
+    camera_query.iter().find_map(|(camera, camera_tf)| {
+        if !camera
+            .logical_viewport_rect()
+            .is_some_and(|rect| rect.contains(cursor))
+        {
+            return None;
+        }
+        camera.viewport_to_world(camera_tf, cursor).ok()
+    })
+}
+
+/// Casts through the cursor onto the ground plane and moves the position
+/// target towards where it hits.
+pub fn follow_cursor(
+    settings: Res<FollowMouseSettings>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<DroneCamera>>,
+    mut drone_query: Query<(&Transform, &mut FollowCursor, &mut PositionPid)>,
+) {
+    let ground = cursor_ray(&window_query, &camera_query).and_then(|ray| {
+        let distance = ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))?;
+        Some(ray.get_point(distance))
+    });
+
+    for (tf, mut follow, mut ctl_pos) in drone_query.iter_mut() {
+        follow.ground = ground;
+        let Some(ground) = ground else {
+            continue;
+        };
+
+        let offset = Vec2::new(ground.x - tf.translation.x, ground.z - tf.translation.z)
+            .clamp_length_max(settings.max_lead);
+        ctl_pos.target = Vec3::new(
+            tf.translation.x + offset.x,
+            settings.altitude,
+            tf.translation.z + offset.y,
+        );
+        ctl_pos.accel_feedforward = Vec2::ZERO;
+    }
+}
+
+pub fn draw_cursor_target(drone_query: Query<(&FollowCursor, &PositionPid)>, mut gizmos: Gizmos) {
+    let color = Color::srgb(1.0, 0.4, 0.9);
+    for (follow, ctl_pos) in drone_query.iter() {
+        if let Some(ground) = follow.ground {
+            gizmos.circle(
+                Isometry3d::new(ground, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+                0.5,
+                color,
+            );
+            gizmos.line(ground, ctl_pos.target, color);
+        }
+        gizmos.sphere(ctl_pos.target, 0.2, color);
+    }
+}
@@ -0,0 +1,219 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        view::screenshot::{Screenshot, save_to_disk},
+    },
+};
+
+use bevy_rapier3d::prelude::*;
+
+use crate::{ActiveDrone, arg_value, has_flag, spawn_drone};
+
+const DEFAULT_RESOLUTION: (u32, u32) = (1280, 720);
+const DEFAULT_DIR: &str = "fpv";
+/// Downward tilt of the onboard camera, like a typical FPV uptilt mounted on a
+/// drone that flies nose-down.
+const CAMERA_TILT: f32 = -0.2;
+const HORIZON_LOCK_KEY: KeyCode = KeyCode::F12;
+
+/// Onboard camera that renders to an offscreen image instead of the window.
+#[derive(Component)]
+pub struct FpvCamera;
+
+#[derive(Resource)]
+pub struct FpvRecorder {
+    pub target: Handle<Image>,
+    pub width: u32,
+    pub height: u32,
+    pub dir: String,
+    pub recording: bool,
+    pub frame: u32,
+}
+
+/// How the onboard camera rolls. Locked to the drone it banks with every
+/// roll, which some find disorienting; locked to the horizon it keeps the
+/// drone's heading and pitch but stays level, like a roll-stabilized gimbal.
+#[derive(Resource)]
+pub struct FpvCameraRoll {
+    pub horizon_locked: bool,
+}
+
+pub fn fpv_plugin(app: &mut App) {
+    app.insert_resource(FpvCameraRoll {
+        horizon_locked: has_flag("--horizon-lock"),
+    })
+    .add_systems(Startup, spawn_fpv_camera.after(spawn_drone))
+    .add_systems(
+        Update,
+        (
+            (toggle_fpv_recording, capture_fpv_frame).chain(),
+            toggle_horizon_lock,
+        ),
+    )
+    // Once the drone's pose for this frame is known, before it's propagated
+    .add_systems(
+        PostUpdate,
+        level_fpv_camera
+            .after(PhysicsSet::Writeback)
+            .before(TransformSystem::TransformPropagate),
+    );
+}
+
+/// Parses `--fpv-resolution WIDTHxHEIGHT`, falling back to 1280x720.
+fn resolution() -> (u32, u32) {
+    let Some(value) = arg_value("--fpv-resolution") else {
+        return DEFAULT_RESOLUTION;
+    };
+
+    let parsed = value
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+        .filter(|&(w, h): &(u32, u32)| w > 0 && h > 0);
+
+    parsed.unwrap_or_else(|| {
+        warn!("Invalid --fpv-resolution {value}, expected WIDTHxHEIGHT");
+        DEFAULT_RESOLUTION
+    })
+}
+
+pub fn spawn_fpv_camera(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    drone_query: Query<Entity, With<ActiveDrone>>,
+) {
+    let Ok(drone) = drone_query.single() else {
+        return;
+    };
+
+    let (width, height) = resolution();
+    let mut image = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_SRC
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT;
+    let target = images.add(image);
+
+    let camera = commands
+        .spawn((
+            Name::new("FPV Camera"),
+            FpvCamera,
+            Camera3d::default(),
+            Camera {
+                target: RenderTarget::Image(target.clone().into()),
+                order: -1,
+                is_active: false,
+                ..default()
+            },
+            Transform::from_xyz(0.0, 0.05, -0.3).with_rotation(Quat::from_rotation_x(CAMERA_TILT)),
+        ))
+        .id();
+    commands.entity(drone).add_child(camera);
+
+    commands.insert_resource(FpvRecorder {
+        target,
+        width,
+        height,
+        dir: arg_value("--fpv-dir").unwrap_or_else(|| DEFAULT_DIR.to_string()),
+        recording: false,
+        frame: 0,
+    });
+}
+
+pub fn toggle_fpv_recording(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    recorder: Option<ResMut<FpvRecorder>>,
+    mut camera_query: Query<&mut Camera, With<FpvCamera>>,
+) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    if !keyboard.just_pressed(KeyCode::KeyV) {
+        return;
+    }
+
+    if !recorder.recording
+        && let Err(e) = std::fs::create_dir_all(&recorder.dir)
+    {
+        warn!("Failed to create FPV directory {}: {e}", recorder.dir);
+        return;
+    }
+
+    recorder.recording = !recorder.recording;
+    // Only render the onboard view while recording.
+    for mut camera in camera_query.iter_mut() {
+        camera.is_active = recorder.recording;
+    }
+
+    if recorder.recording {
+        info!(
+            "FPV recording to {} at {}x{}",
+            recorder.dir, recorder.width, recorder.height
+        );
+    } else {
+        info!("FPV recording stopped after {} frames", recorder.frame);
+    }
+}
+
+pub fn capture_fpv_frame(mut commands: Commands, recorder: Option<ResMut<FpvRecorder>>) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    if !recorder.recording {
+        return;
+    }
+
+    let path = format!("{}/fpv_{:06}.png", recorder.dir, recorder.frame);
+    commands
+        .spawn(Screenshot::image(recorder.target.clone()))
+        .observe(save_to_disk(path));
+    recorder.frame += 1;
+}
+
+pub fn toggle_horizon_lock(keyboard: Res<ButtonInput<KeyCode>>, mut roll: ResMut<FpvCameraRoll>) {
+    if !keyboard.just_pressed(HORIZON_LOCK_KEY) {
+        return;
+    }
+
+    roll.horizon_locked = !roll.horizon_locked;
+    if roll.horizon_locked {
+        info!("FPV camera locked to the horizon");
+    } else {
+        info!("FPV camera locked to the drone");
+    }
+}
+
+/// Sets the camera's mount rotation: fixed to the drone, or countering the
+/// drone's roll so the camera's up stays in the vertical plane.
+pub fn level_fpv_camera(
+    roll: Res<FpvCameraRoll>,
+    drone_query: Query<&Transform, Without<FpvCamera>>,
+    mut camera_query: Query<(&mut Transform, &ChildOf), With<FpvCamera>>,
+) {
+    let tilt = Quat::from_rotation_x(CAMERA_TILT);
+
+    for (mut tf, child_of) in camera_query.iter_mut() {
+        let Ok(drone_tf) = drone_query.get(child_of.parent()) else {
+            continue;
+        };
+        tf.rotation = if roll.horizon_locked {
+            let (yaw, pitch, _) = drone_tf.rotation.to_euler(EulerRot::YXZ);
+            let level = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+            drone_tf.rotation.inverse() * level * tilt
+        } else {
+            tilt
+        };
+    }
+}
@@ -0,0 +1,285 @@
+use bevy::{
+    prelude::*,
+    render::view::screenshot::{Screenshot, save_to_disk},
+};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{ActiveDrone, DroneCamera, GRAVITY, arg_value};
+
+pub const DEFAULT_ANNOTATIONS_PATH: &str = "assets/annotations/default.ron";
+const DEFAULT_SCREENSHOT_DIR: &str = "screenshots";
+/// Length of a thrust arrow at hover thrust (m).
+const THRUST_ARROW_LENGTH: f32 = 1.5;
+/// Length of a velocity arrow per m/s (m).
+const VELOCITY_ARROW_SCALE: f32 = 0.3;
+/// Size of the marker drawn at each annotated point (m).
+const MARKER_RADIUS: f32 = 0.08;
+const ANNOTATION_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+
+/// Where an annotation is pinned.
+#[derive(Deserialize, Clone, Debug)]
+pub enum AnnotationAnchor {
+    /// Point on the active drone, in its body frame.
+    Drone([f32; 3]),
+    World([f32; 3]),
+}
+
+/// Arrow drawn from an annotation's anchor.
+#[derive(Deserialize, Clone, Debug)]
+pub enum AnnotationArrow {
+    /// Along the applied thrust, `THRUST_ARROW_LENGTH` long at hover thrust.
+    Thrust,
+    Velocity,
+    /// Fixed world-space vector.
+    Vector([f32; 3]),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Annotation {
+    pub label: String,
+    pub at: AnnotationAnchor,
+    #[serde(default)]
+    pub arrow: Option<AnnotationArrow>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct AnnotationSet {
+    pub annotations: Vec<Annotation>,
+}
+
+impl AnnotationSet {
+    pub fn load(path: &str) -> Self {
+        let set = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                ron::from_str::<AnnotationSet>(&contents).map_err(|e| e.to_string())
+            });
+
+        match set {
+            Ok(set) => set,
+            Err(e) => {
+                warn!("Failed to load annotations {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Freeze frame for documentation figures: `F6` pauses the sim and overlays
+/// the annotations from `--annotations` as gizmo arrows with screen-space
+/// labels, and `F8` saves a screenshot of the annotated frame. The file is
+/// re-read on every freeze, so it can be edited between shots.
+#[derive(Resource)]
+pub struct FreezeFrame {
+    pub path: String,
+    pub screenshot_dir: String,
+    pub frozen: bool,
+    pub annotations: Vec<Annotation>,
+    pub screenshots: usize,
+}
+
+impl FreezeFrame {
+    pub fn from_args() -> Self {
+        Self {
+            path: arg_value("--annotations").unwrap_or(DEFAULT_ANNOTATIONS_PATH.to_string()),
+            screenshot_dir: arg_value("--screenshot-dir")
+                .unwrap_or(DEFAULT_SCREENSHOT_DIR.to_string()),
+            frozen: false,
+            annotations: Vec::new(),
+            screenshots: 0,
+        }
+    }
+}
+
+/// Screen-space label of the annotation at this index.
+#[derive(Component)]
+pub struct AnnotationLabel(pub usize);
+
+pub fn freeze_plugin(app: &mut App) {
+    app.insert_resource(FreezeFrame::from_args()).add_systems(
+        Update,
+        (
+            toggle_freeze,
+            (draw_annotations, place_annotation_labels, save_screenshot)
+                .run_if(|freeze: Res<FreezeFrame>| freeze.frozen),
+        )
+            .chain(),
+    );
+}
+
+pub fn toggle_freeze(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut freeze: ResMut<FreezeFrame>,
+    mut time: ResMut<Time<Virtual>>,
+    mut rapier_query: Query<&mut RapierConfiguration>,
+    label_query: Query<Entity, With<AnnotationLabel>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F6) {
+        return;
+    }
+
+    freeze.frozen = !freeze.frozen;
+    for mut config in rapier_query.iter_mut() {
+        config.physics_pipeline_active = !freeze.frozen;
+    }
+    for label in label_query.iter() {
+        commands.entity(label).despawn();
+    }
+
+    if !freeze.frozen {
+        time.unpause();
+        info!("Freeze frame off");
+        return;
+    }
+
+    time.pause();
+    freeze.annotations = AnnotationSet::load(&freeze.path).annotations;
+    info!(
+        "Freeze frame with {} annotations from {}",
+        freeze.annotations.len(),
+        freeze.path
+    );
+
+    let font = asset_server.load("./pixeloid_mono.ttf");
+    for (i, annotation) in freeze.annotations.iter().enumerate() {
+        commands.spawn((
+            AnnotationLabel(i),
+            Text::new(annotation.label.clone()),
+            TextColor(ANNOTATION_COLOR),
+            TextFont {
+                font: font.clone(),
+                font_size: 20.,
+                ..Default::default()
+            },
+            TextShadow::default(),
+            Node {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            Visibility::Hidden,
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+/// World-space anchor and arrow tip of an annotation, or `None` if it's pinned
+/// to the drone and there is no active drone.
+fn annotation_points(
+    annotation: &Annotation,
+    drone: Option<(&Transform, &Velocity, &ExternalForce, &ReadMassProperties)>,
+) -> Option<(Vec3, Option<Vec3>)> {
+    let anchor = match annotation.at {
+        AnnotationAnchor::Drone(offset) => {
+            let (tf, ..) = drone?;
+            tf.transform_point(Vec3::from(offset))
+        }
+        AnnotationAnchor::World(position) => Vec3::from(position),
+    };
+
+    let arrow = match annotation.arrow {
+        None => None,
+        Some(AnnotationArrow::Thrust) => {
+            let (_, _, force, mass_props) = drone?;
+            let weight = (mass_props.mass * GRAVITY).max(f32::EPSILON);
+            Some(force.force / weight * THRUST_ARROW_LENGTH)
+        }
+        Some(AnnotationArrow::Velocity) => {
+            let (_, velocity, ..) = drone?;
+            Some(velocity.linvel * VELOCITY_ARROW_SCALE)
+        }
+        Some(AnnotationArrow::Vector(vector)) => Some(Vec3::from(vector)),
+    };
+
+    Some((anchor, arrow.map(|arrow| anchor + arrow)))
+}
+
+pub fn draw_annotations(
+    freeze: Res<FreezeFrame>,
+    drone_query: Query<
+        (&Transform, &Velocity, &ExternalForce, &ReadMassProperties),
+        With<ActiveDrone>,
+    >,
+    mut gizmos: Gizmos,
+) {
+    let drone = drone_query.single().ok();
+
+    for annotation in &freeze.annotations {
+        let Some((anchor, tip)) = annotation_points(annotation, drone) else {
+            continue;
+        };
+        gizmos.sphere(anchor, MARKER_RADIUS, ANNOTATION_COLOR);
+        if let Some(tip) = tip {
+            gizmos
+                .arrow(anchor, tip, ANNOTATION_COLOR)
+                .with_tip_length(0.25);
+        }
+    }
+}
+
+/// Puts each label next to its arrow tip, or its anchor if it has no arrow,
+/// as seen from the drone camera.
+pub fn place_annotation_labels(
+    freeze: Res<FreezeFrame>,
+    ui_scale: Res<UiScale>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<DroneCamera>>,
+    drone_query: Query<
+        (&Transform, &Velocity, &ExternalForce, &ReadMassProperties),
+        With<ActiveDrone>,
+    >,
+    mut label_query: Query<(&AnnotationLabel, &mut Node, &mut Visibility)>,
+) {
+    let Ok((camera, camera_tf)) = camera_query.single() else {
+        return;
+    };
+    let drone = drone_query.single().ok();
+
+    for (label, mut node, mut visibility) in label_query.iter_mut() {
+        let screen = freeze
+            .annotations
+            .get(label.0)
+            .and_then(|annotation| annotation_points(annotation, drone))
+            .and_then(|(anchor, tip)| {
+                camera
+                    .world_to_viewport(camera_tf, tip.unwrap_or(anchor))
+                    .ok()
+            });
+
+        let Some(screen) = screen else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Visible;
+        node.left = Val::Px(screen.x / ui_scale.0 + 8.0);
+        node.top = Val::Px(screen.y / ui_scale.0 - 10.0);
+    }
+}
+
+/// `F8` while frozen saves the window, annotations included.
+pub fn save_screenshot(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut freeze: ResMut<FreezeFrame>,
+) {
+    if !keyboard.just_pressed(KeyCode::F8) {
+        return;
+    }
+
+    if let Err(e) = std::fs::create_dir_all(&freeze.screenshot_dir) {
+        warn!(
+            "Failed to create screenshot directory {}: {e}",
+            freeze.screenshot_dir
+        );
+        return;
+    }
+    let path = format!(
+        "{}/freeze_{:03}.png",
+        freeze.screenshot_dir, freeze.screenshots
+    );
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(path));
+    freeze.screenshots += 1;
+}
@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+
+use crate::{
+    EngineState, HoverPid, InputLink, Piloted, PitchPid, RollPid, YawPid, arg_value,
+    gamepad_calibration::{GamepadCalibration, StickCalibration, calibration_path},
+    has_flag, manual_control,
+};
+
+/// Deadzone and expo of one stick axis.
+#[derive(Clone, Copy, Debug)]
+pub struct AxisShape {
+    /// Deflection below this is ignored.
+    pub deadzone: f32,
+    /// 0 is linear; towards 1 the center gets softer and the ends steeper.
+    pub expo: f32,
+}
+
+impl AxisShape {
+    /// Removes the deadzone, rescales the rest back to -1..=1 and applies the expo curve.
+    pub fn apply(&self, value: f32) -> f32 {
+        if value.abs() <= self.deadzone {
+            return 0.0;
+        }
+
+        let x = value.signum() * (value.abs() - self.deadzone) / (1.0 - self.deadzone);
+        (1.0 - self.expo) * x + self.expo * x.powi(3)
+    }
+}
+
+/// Analog stick input. The left stick tilts the drone proportionally (full
+/// deflection reaches the pitch/roll limits), the right stick turns and climbs
+/// at a rate. Each axis is shaped separately.
+#[derive(Resource)]
+pub struct GamepadInput {
+    /// Left stick X.
+    pub roll: AxisShape,
+    /// Left stick Y.
+    pub pitch: AxisShape,
+    /// Right stick X.
+    pub yaw: AxisShape,
+    /// Right stick Y, and the analog triggers (right climbs, left descends).
+    pub throttle: AxisShape,
+    /// Yaw rate at full right stick X (rad/s).
+    pub yaw_rate: f32,
+    /// Climb rate at full right stick Y (m/s).
+    pub climb_rate: f32,
+    /// Whether the left stick was outside the deadzone last frame.
+    pub tilting: bool,
+    /// Stick ranges, applied before the shaping.
+    pub calibration: StickCalibration,
+}
+
+impl GamepadInput {
+    pub fn from_args() -> Self {
+        // The flags set every axis; fine-tune them individually in the tuning panel
+        let shape = AxisShape {
+            deadzone: arg_value("--gamepad-deadzone")
+                .and_then(|deadzone| deadzone.parse::<f32>().ok())
+                .unwrap_or(0.1)
+                .clamp(0.0, 0.9),
+            expo: arg_value("--gamepad-expo")
+                .and_then(|expo| expo.parse::<f32>().ok())
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0),
+        };
+
+        Self {
+            roll: shape,
+            pitch: shape,
+            yaw: shape,
+            throttle: shape,
+            yaw_rate: arg_value("--gamepad-yaw-rate")
+                .and_then(|rate| rate.parse::<f32>().ok())
+                .unwrap_or(90.0)
+                .to_radians(),
+            climb_rate: arg_value("--gamepad-climb-rate")
+                .and_then(|rate| rate.parse::<f32>().ok())
+                .unwrap_or(2.0),
+            tilting: false,
+            calibration: StickCalibration::load(&calibration_path()),
+        }
+    }
+
+    /// Calibrated and shaped left and right stick positions of `gamepad`.
+    /// The triggers add to right stick Y, for pads where that's easier to
+    /// hold steady than a self-centering stick.
+    fn sticks(&self, gamepad: &Gamepad) -> (Vec2, Vec2) {
+        let (left, right) = self
+            .calibration
+            .apply(gamepad.left_stick(), gamepad.right_stick());
+        let trigger = |button| gamepad.get(button).unwrap_or(0.0);
+        let triggers = trigger(GamepadButton::RightTrigger2) - trigger(GamepadButton::LeftTrigger2);
+        (
+            Vec2::new(self.roll.apply(left.x), self.pitch.apply(left.y)),
+            Vec2::new(
+                self.yaw.apply(right.x),
+                (self.throttle.apply(right.y) + self.throttle.apply(triggers)).clamp(-1.0, 1.0),
+            ),
+        )
+    }
+}
+
+/// Trainer mode for two gamepads: the first one connected belongs to the
+/// student, the second to the instructor, who takes over by holding the
+/// override button.
+#[derive(Resource)]
+pub struct ControlAuthority {
+    pub enabled: bool,
+    /// Time to hand the sticks over in either direction; 0 switches instantly (s).
+    pub blend_time: f32,
+    pub overriding: bool,
+    /// Share of the instructor's sticks in the command, 0..=1.
+    pub instructor: f32,
+}
+
+impl ControlAuthority {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: has_flag("--instructor"),
+            blend_time: arg_value("--instructor-blend")
+                .and_then(|time| time.parse::<f32>().ok())
+                .unwrap_or(0.0)
+                .max(0.0),
+            overriding: false,
+            instructor: 0.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PilotText;
+
+/// Instructor button that takes over control while held.
+const OVERRIDE_BUTTON: GamepadButton = GamepadButton::LeftTrigger;
+
+pub fn gamepad_plugin(app: &mut App) {
+    app.insert_resource(GamepadInput::from_args())
+        .insert_resource(ControlAuthority::from_args())
+        .add_systems(
+            Update,
+            gamepad_control
+                .after(manual_control)
+                .run_if(in_state(EngineState::On))
+                .run_if(|calibration: Res<GamepadCalibration>| !calibration.is_active()),
+        )
+        .add_systems(Update, update_pilot_text);
+}
+
+/// Maps a stick axis onto a setpoint range. Positive deflection (stick forward
+/// or right) tilts towards the negative limit, matching W/D on the keyboard.
+fn deflect(value: f32, min: f32, max: f32) -> f32 {
+    if value >= 0.0 {
+        value * min
+    } else {
+        -value * max
+    }
+}
+
+pub fn gamepad_control(
+    time: Res<Time>,
+    mut input: ResMut<GamepadInput>,
+    mut authority: ResMut<ControlAuthority>,
+    mut link: ResMut<InputLink>,
+    gamepad_query: Query<(Entity, &Gamepad)>,
+    mut drone_query: Query<(&mut HoverPid, &mut PitchPid, &mut RollPid, &mut YawPid), Piloted>,
+) {
+    // Entities are allocated in connection order, so the student's pad sorts first
+    let mut gamepads = gamepad_query.iter().collect::<Vec<_>>();
+    gamepads.sort_by_key(|(entity, _)| *entity);
+    let Some(&(_, student)) = gamepads.first() else {
+        return;
+    };
+    // A connected pad keeps the link alive even while the sticks rest
+    link.touch(time.elapsed_secs());
+
+    let dt = time.delta_secs();
+    let (mut left, mut right) = input.sticks(student);
+
+    let instructor = gamepads.get(1).filter(|_| authority.enabled);
+    authority.overriding = instructor.is_some_and(|(_, pad)| pad.pressed(OVERRIDE_BUTTON));
+    let target = if authority.overriding { 1.0 } else { 0.0 };
+    authority.instructor = if authority.blend_time > 0.0 {
+        let step = dt / authority.blend_time;
+        authority.instructor + (target - authority.instructor).clamp(-step, step)
+    } else {
+        target
+    };
+    if let Some(&(_, instructor)) = instructor {
+        let (instructor_left, instructor_right) = input.sticks(instructor);
+        left = left.lerp(instructor_left, authority.instructor);
+        right = right.lerp(instructor_right, authority.instructor);
+    }
+
+    let tilting = left != Vec2::ZERO;
+
+    for (mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        // Only take over pitch/roll while the stick is deflected so the
+        // keyboard keeps working; letting go eases back to level like a key release
+        if tilting {
+            ctl_pitch.centering = false;
+            ctl_roll.centering = false;
+            ctl_pitch.target_angle = deflect(left.y, ctl_pitch.min_angle, ctl_pitch.max_angle);
+            ctl_roll.target_angle = deflect(left.x, ctl_roll.min_angle, ctl_roll.max_angle);
+        } else if input.tilting {
+            ctl_pitch.centering = true;
+            ctl_roll.centering = true;
+        }
+
+        // Stick right turns right, like E
+        ctl_yaw.target_angle += right.x * input.yaw_rate * dt;
+        ctl_y.target_y += right.y * input.climb_rate * dt;
+    }
+
+    input.tilting = tilting;
+}
+
+pub fn update_pilot_text(
+    authority: Res<ControlAuthority>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<PilotText>>,
+) {
+    for (mut text, mut color) in text_query.iter_mut() {
+        *text = if !authority.enabled {
+            "Pilot: -".into()
+        } else if authority.instructor >= 1.0 {
+            "INSTRUCTOR".into()
+        } else if authority.instructor > 0.0 {
+            format!("INSTRUCTOR {:.0}%", authority.instructor * 100.0).into()
+        } else {
+            "Pilot: student".into()
+        };
+        color.0 = if authority.instructor > 0.0 {
+            Color::srgb(1.0, 0.6, 0.0)
+        } else {
+            Color::WHITE
+        };
+    }
+}
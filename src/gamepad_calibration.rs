@@ -0,0 +1,289 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{GamepadInput, arg_value};
+
+pub const DEFAULT_CALIBRATION_PATH: &str = "gamepad_calibration.ron";
+/// Smallest travel either side of center that counts as a calibrated axis.
+const MIN_TRAVEL: f32 = 0.2;
+
+/// Measured range of one raw stick axis.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AxisCalibration {
+    pub min: f32,
+    pub center: f32,
+    pub max: f32,
+}
+
+impl Default for AxisCalibration {
+    fn default() -> Self {
+        Self {
+            min: -1.0,
+            center: 0.0,
+            max: 1.0,
+        }
+    }
+}
+
+impl AxisCalibration {
+    /// Maps a raw reading onto -1..=1, with `center` at 0 and each side
+    /// scaled separately so both extremes reach full deflection.
+    pub fn apply(&self, raw: f32) -> f32 {
+        let value = if raw >= self.center {
+            (raw - self.center) / (self.max - self.center)
+        } else {
+            (raw - self.center) / (self.center - self.min)
+        };
+        value.clamp(-1.0, 1.0)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.max - self.center >= MIN_TRAVEL && self.center - self.min >= MIN_TRAVEL
+    }
+}
+
+/// Stick ranges of the pad, applied before the deadzone and expo.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct StickCalibration {
+    pub left_x: AxisCalibration,
+    pub left_y: AxisCalibration,
+    pub right_x: AxisCalibration,
+    pub right_y: AxisCalibration,
+}
+
+impl StickCalibration {
+    /// The saved calibration, or the identity if there is none yet.
+    pub fn load(path: &str) -> Self {
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match ron::from_str::<StickCalibration>(&contents) {
+            Ok(calibration) => {
+                info!("Loaded gamepad calibration from {path}");
+                calibration
+            }
+            Err(e) => {
+                warn!("Failed to load gamepad calibration {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    /// Calibrated left and right stick positions.
+    pub fn apply(&self, left: Vec2, right: Vec2) -> (Vec2, Vec2) {
+        (
+            Vec2::new(self.left_x.apply(left.x), self.left_y.apply(left.y)),
+            Vec2::new(self.right_x.apply(right.x), self.right_y.apply(right.y)),
+        )
+    }
+
+    fn axes_mut(&mut self) -> [&mut AxisCalibration; 4] {
+        [
+            &mut self.left_x,
+            &mut self.left_y,
+            &mut self.right_x,
+            &mut self.right_y,
+        ]
+    }
+}
+
+/// File the calibration is saved to and loaded from.
+pub fn calibration_path() -> String {
+    arg_value("--gamepad-calibration").unwrap_or(DEFAULT_CALIBRATION_PATH.to_string())
+}
+
+/// Where the calibration routine is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalibrationStep {
+    /// Sticks at rest; the center is read when `F10` is pressed.
+    Center,
+    /// Sticks swept around their full range; the extremes are recorded until
+    /// `F10` is pressed.
+    Extremes,
+}
+
+impl CalibrationStep {
+    fn prompt(self) -> &'static str {
+        match self {
+            Self::Center => "Gamepad calibration: let go of both sticks, then press F10",
+            Self::Extremes => {
+                "Gamepad calibration: move both sticks to their extremes in every direction, then press F10"
+            }
+        }
+    }
+}
+
+/// `F10` walks through calibrating the first gamepad's sticks: first the
+/// resting center, then the extremes. The result is saved to
+/// `--gamepad-calibration` (default `gamepad_calibration.ron`) and loaded from
+/// there at startup. `Shift+F10` cancels and keeps the old calibration.
+#[derive(Resource)]
+pub struct GamepadCalibration {
+    pub path: String,
+    pub step: Option<CalibrationStep>,
+    pub recording: StickCalibration,
+}
+
+impl GamepadCalibration {
+    pub fn from_args() -> Self {
+        Self {
+            path: calibration_path(),
+            step: None,
+            recording: StickCalibration::default(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.step.is_some()
+    }
+}
+
+/// On-screen prompt shown while calibrating.
+#[derive(Component)]
+pub struct CalibrationPrompt;
+
+pub fn gamepad_calibration_plugin(app: &mut App) {
+    app.insert_resource(GamepadCalibration::from_args())
+        .add_systems(
+            Update,
+            (
+                step_calibration,
+                record_extremes.run_if(|calibration: Res<GamepadCalibration>| {
+                    calibration.step == Some(CalibrationStep::Extremes)
+                }),
+            )
+                .chain(),
+        );
+}
+
+pub fn step_calibration(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut calibration: ResMut<GamepadCalibration>,
+    mut input: ResMut<GamepadInput>,
+    gamepad_query: Query<(Entity, &Gamepad)>,
+    mut prompt_query: Query<(Entity, &mut Text), With<CalibrationPrompt>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F10) {
+        return;
+    }
+
+    if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        if calibration.is_active() {
+            calibration.step = None;
+            info!("Gamepad calibration cancelled");
+        }
+    } else {
+        // The student's pad, which connected first
+        let Some((_, gamepad)) = gamepad_query.iter().min_by_key(|(entity, _)| *entity) else {
+            warn!("Connect a gamepad to calibrate it");
+            return;
+        };
+        let (left, right) = (gamepad.left_stick(), gamepad.right_stick());
+
+        calibration.step = match calibration.step {
+            None => Some(CalibrationStep::Center),
+            Some(CalibrationStep::Center) => {
+                for (axis, raw) in calibration
+                    .recording
+                    .axes_mut()
+                    .into_iter()
+                    .zip([left.x, left.y, right.x, right.y])
+                {
+                    *axis = AxisCalibration {
+                        min: raw,
+                        center: raw,
+                        max: raw,
+                    };
+                }
+                Some(CalibrationStep::Extremes)
+            }
+            Some(CalibrationStep::Extremes) => {
+                finish_calibration(&mut calibration, &mut input);
+                None
+            }
+        };
+    }
+
+    match (calibration.step, prompt_query.single_mut()) {
+        (Some(step), Ok((_, mut text))) => *text = step.prompt().into(),
+        (Some(step), Err(_)) => {
+            commands.spawn((
+                CalibrationPrompt,
+                Text::new(step.prompt()),
+                TextFont {
+                    font: asset_server.load("./pixeloid_mono.ttf"),
+                    font_size: 26.,
+                    ..Default::default()
+                },
+                TextColor(Color::srgb(1.0, 0.8, 0.2)),
+                BackgroundColor(Color::BLACK.with_alpha(0.8)),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(40.),
+                    left: Val::Percent(10.),
+                    right: Val::Percent(10.),
+                    padding: UiRect::all(Val::Px(16.)),
+                    ..Default::default()
+                },
+            ));
+        }
+        (None, Ok((prompt, _))) => commands.entity(prompt).despawn(),
+        (None, Err(_)) => {}
+    }
+}
+
+pub fn record_extremes(
+    mut calibration: ResMut<GamepadCalibration>,
+    gamepad_query: Query<(Entity, &Gamepad)>,
+) {
+    let Some((_, gamepad)) = gamepad_query.iter().min_by_key(|(entity, _)| *entity) else {
+        return;
+    };
+    let (left, right) = (gamepad.left_stick(), gamepad.right_stick());
+
+    for (axis, raw) in calibration
+        .recording
+        .axes_mut()
+        .into_iter()
+        .zip([left.x, left.y, right.x, right.y])
+    {
+        axis.min = axis.min.min(raw);
+        axis.max = axis.max.max(raw);
+    }
+}
+
+/// Applies and saves the recorded ranges. Axes that barely moved keep their
+/// previous calibration.
+fn finish_calibration(calibration: &mut GamepadCalibration, input: &mut GamepadInput) {
+    let mut result = input.calibration;
+    for (name, (recorded, axis)) in ["left X", "left Y", "right X", "right Y"].into_iter().zip(
+        calibration
+            .recording
+            .axes_mut()
+            .into_iter()
+            .zip(result.axes_mut()),
+    ) {
+        if recorded.is_valid() {
+            *axis = *recorded;
+        } else {
+            warn!("Gamepad {name} axis didn't move far enough, keeping its old calibration");
+        }
+    }
+
+    input.calibration = result;
+    match result.save(&calibration.path) {
+        Ok(()) => info!("Gamepad calibration saved to {}", calibration.path),
+        Err(e) => warn!(
+            "Failed to save gamepad calibration {}: {e}",
+            calibration.path
+        ),
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{Drone, SceneEntity, level::Level, race::RaceState};
+
+const GATE_THICKNESS: f32 = 0.2;
+const GATE_DEPTH: f32 = 0.5;
+
+#[derive(Component)]
+pub struct Gate {
+    pub index: usize,
+}
+
+#[derive(Event)]
+pub struct GatePassed {
+    pub index: usize,
+}
+
+pub fn spawn_gates(
+    mut commands: Commands,
+    level: Res<Level>,
+    mut race: ResMut<RaceState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = materials.add(Color::srgb_u8(255, 140, 0));
+
+    for (index, def) in level.gates.iter().enumerate() {
+        let post = meshes.add(Cuboid::new(GATE_THICKNESS, def.height, GATE_THICKNESS));
+        let beam = meshes.add(Cuboid::new(
+            def.width + GATE_THICKNESS,
+            GATE_THICKNESS,
+            GATE_THICKNESS,
+        ));
+
+        commands
+            .spawn((
+                Gate { index },
+                SceneEntity,
+                Name::new(format!("Gate {index}")),
+                Transform::from_translation(Vec3::from_array(def.position))
+                    .with_rotation(Quat::from_rotation_y(def.yaw.to_radians())),
+                Visibility::default(),
+                Collider::cuboid(def.width / 2.0, def.height / 2.0, GATE_DEPTH / 2.0),
+                Sensor,
+                ActiveEvents::COLLISION_EVENTS,
+            ))
+            .with_children(|parent| {
+                for x in [-def.width / 2.0, def.width / 2.0] {
+                    parent.spawn((
+                        Name::new(format!("Gate {index} post")),
+                        Mesh3d(post.clone()),
+                        MeshMaterial3d(material.clone()),
+                        Transform::from_xyz(x, 0.0, 0.0),
+                        Collider::cuboid(
+                            GATE_THICKNESS / 2.0,
+                            def.height / 2.0,
+                            GATE_THICKNESS / 2.0,
+                        ),
+                    ));
+                }
+                for y in [-def.height / 2.0, def.height / 2.0] {
+                    parent.spawn((
+                        Name::new(format!("Gate {index} beam")),
+                        Mesh3d(beam.clone()),
+                        MeshMaterial3d(material.clone()),
+                        Transform::from_xyz(0.0, y, 0.0),
+                        Collider::cuboid(
+                            (def.width + GATE_THICKNESS) / 2.0,
+                            GATE_THICKNESS / 2.0,
+                            GATE_THICKNESS / 2.0,
+                        ),
+                    ));
+                }
+            });
+    }
+
+    *race = RaceState::new((0..level.gates.len()).collect());
+}
+
+pub fn detect_gates(
+    mut collision_events: EventReader<CollisionEvent>,
+    gate_query: Query<&Gate>,
+    drone_query: Query<(), With<Drone>>,
+    mut gate_passed: EventWriter<GatePassed>,
+) {
+    for event in collision_events.read() {
+        let CollisionEvent::Started(e1, e2, _) = event else {
+            continue;
+        };
+
+        for (gate_entity, other) in [(*e1, *e2), (*e2, *e1)] {
+            if let Ok(gate) = gate_query.get(gate_entity)
+                && drone_query.contains(other)
+            {
+                gate_passed.write(GatePassed { index: gate.index });
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+
+use crate::{
+    ActiveDrone, arg_value, has_flag,
+    race::RaceState,
+    replay::{FlightRecording, PoseSample},
+    track_race,
+};
+
+/// How far ahead of the last match the ghost's path is searched for the
+/// drone's position (s). Keeps the match from jumping to a later part of the
+/// lap where the path crosses itself.
+const MATCH_WINDOW: f32 = 2.0;
+
+/// A translucent drone that flies the best lap so far alongside the current
+/// one. The best lap is recorded in memory as it is flown; with
+/// `--ghost <file.csv>` it is loaded from and saved back to a replay file, so
+/// it carries over between sessions.
+#[derive(Resource)]
+pub struct GhostRace {
+    pub enabled: bool,
+    pub path: Option<String>,
+    pub best: Option<FlightRecording>,
+    /// Lap time of `best`, excluding penalties (s).
+    pub best_time: Option<f32>,
+    pub current: Vec<PoseSample>,
+    /// Laps completed when last checked, to notice the race finishing one.
+    pub laps: usize,
+    /// Index into `best` of the sample last matched to the drone's position.
+    pub cursor: usize,
+    /// Current lap time minus the ghost's time at the same point; positive is behind.
+    pub delta: Option<f32>,
+}
+
+impl GhostRace {
+    pub fn from_args() -> Self {
+        let path = arg_value("--ghost");
+        let best = path
+            .as_deref()
+            .and_then(|path| match FlightRecording::load(path) {
+                Ok(recording) => Some(recording),
+                Err(e) => {
+                    info!("No ghost lap loaded from {path}: {e}");
+                    None
+                }
+            })
+            .filter(|recording| !recording.samples.is_empty());
+
+        Self {
+            enabled: !has_flag("--no-ghost"),
+            path,
+            best_time: best.as_ref().map(FlightRecording::duration),
+            best,
+            current: Vec::new(),
+            laps: 0,
+            cursor: 0,
+            delta: None,
+        }
+    }
+
+    /// Ghost lap time at the point of its path nearest `position`.
+    fn match_time(&mut self, position: Vec3) -> Option<f32> {
+        let best = self.best.as_ref()?;
+        let start = self.cursor.min(best.samples.len().saturating_sub(1));
+        let window_end = best.samples.get(start)?.time + MATCH_WINDOW;
+
+        let (nearest, _) = best.samples[start..]
+            .iter()
+            .take_while(|sample| sample.time <= window_end)
+            .enumerate()
+            .map(|(i, sample)| (start + i, sample.translation.distance_squared(position)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+        self.cursor = nearest;
+        Some(best.samples[nearest].time)
+    }
+}
+
+#[derive(Component)]
+pub struct Ghost;
+
+#[derive(Component)]
+pub struct GhostText;
+
+pub fn ghost_plugin(app: &mut App) {
+    let ghost = GhostRace::from_args();
+    let enabled = ghost.enabled;
+
+    app.insert_resource(ghost)
+        .add_systems(Startup, spawn_ghost.run_if(move || enabled))
+        .add_systems(
+            Update,
+            (record_ghost_lap, move_ghost, update_ghost_text)
+                .chain()
+                .after(track_race)
+                .run_if(move || enabled),
+        );
+}
+
+pub fn spawn_ghost(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        Ghost,
+        Name::new("Ghost"),
+        Mesh3d(meshes.add(Cuboid::new(0.5, 0.1, 0.5))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(0.6, 0.9, 1.0, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..Default::default()
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+}
+
+/// Records the lap being flown and keeps it as the ghost if it beats the best.
+pub fn record_ghost_lap(
+    race: Res<RaceState>,
+    mut ghost: ResMut<GhostRace>,
+    drone_query: Query<&Transform, With<ActiveDrone>>,
+) {
+    let laps = race.lap_times.len();
+    if !race.started || laps < ghost.laps {
+        // Race reset
+        ghost.current.clear();
+        ghost.laps = laps;
+        ghost.cursor = 0;
+        return;
+    }
+
+    if laps > ghost.laps {
+        ghost.laps = laps;
+        ghost.cursor = 0;
+        // The race has already restarted the lap clock, so time it from the samples
+        let lap_time = ghost.current.last().map_or(0.0, |sample| sample.time);
+        let samples = std::mem::take(&mut ghost.current);
+        if ghost.best_time.is_none_or(|best| lap_time < best) {
+            let recording = FlightRecording { samples };
+            if let Some(path) = &ghost.path
+                && let Err(e) = recording.save(path)
+            {
+                warn!("Failed to save ghost lap to {path}: {e}");
+            }
+            info!("New ghost lap: {lap_time:.2} s");
+            ghost.best_time = Some(lap_time);
+            ghost.best = Some(recording);
+        }
+    }
+
+    for tf in drone_query.iter() {
+        ghost.current.push(PoseSample {
+            time: race.lap.elapsed_secs(),
+            translation: tf.translation,
+            rotation: tf.rotation,
+        });
+    }
+}
+
+pub fn move_ghost(
+    race: Res<RaceState>,
+    mut ghost: ResMut<GhostRace>,
+    drone_query: Query<&Transform, (With<ActiveDrone>, Without<Ghost>)>,
+    mut ghost_query: Query<(&mut Transform, &mut Visibility), With<Ghost>>,
+) {
+    let sample = ghost
+        .best
+        .as_ref()
+        .filter(|_| race.started)
+        .and_then(|best| best.sample(race.lap.elapsed_secs()))
+        .map(|sample| (sample.translation, sample.rotation));
+
+    for (mut tf, mut visibility) in ghost_query.iter_mut() {
+        match sample {
+            Some((translation, rotation)) => {
+                tf.translation = translation;
+                tf.rotation = rotation;
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    ghost.delta = None;
+    if race.started {
+        for drone_tf in drone_query.iter() {
+            ghost.delta = ghost
+                .match_time(drone_tf.translation)
+                .map(|ghost_time| race.lap.elapsed_secs() - ghost_time);
+        }
+    }
+}
+
+pub fn update_ghost_text(
+    ghost: Res<GhostRace>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<GhostText>>,
+) {
+    for (mut text, mut color) in text_query.iter_mut() {
+        match ghost.delta {
+            Some(delta) => {
+                *text = format!("Ghost: {delta:+.2} s").into();
+                color.0 = if delta > 0.0 {
+                    Color::srgb(1.0, 0.4, 0.4)
+                } else {
+                    Color::srgb(0.4, 1.0, 0.5)
+                };
+            }
+            None => {
+                *text = "Ghost: -".into();
+                color.0 = Color::WHITE;
+            }
+        }
+    }
+}
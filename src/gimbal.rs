@@ -0,0 +1,82 @@
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::{ActiveDrone, Drone, OutputPitchText, arg_value, has_flag};
+
+/// Flags attitudes close to the YXZ euler singularity. Near ±90° pitch the yaw
+/// and roll angles describe (almost) the same rotation, so their readouts
+/// (and, with `--attitude euler`, the controllers' errors) become unreliable.
+#[derive(Resource)]
+pub struct GimbalLockWarning {
+    /// |pitch| above which the attitude counts as near gimbal lock (rad).
+    pub threshold: f32,
+    /// Also log a warning each time a drone gets close.
+    pub log: bool,
+}
+
+impl GimbalLockWarning {
+    pub fn from_args() -> Self {
+        Self {
+            threshold: arg_value("--gimbal-warn-angle")
+                .and_then(|angle| angle.parse::<f32>().ok())
+                .unwrap_or(80.0)
+                .clamp(0.0, 90.0)
+                .to_radians(),
+            log: !has_flag("--no-gimbal-log"),
+        }
+    }
+
+    pub fn is_near(&self, rotation: Quat) -> bool {
+        let (_, pitch, _) = rotation.to_euler(EulerRot::YXZ);
+        pitch.abs() > self.threshold
+    }
+}
+
+pub fn gimbal_plugin(app: &mut App) {
+    app.insert_resource(GimbalLockWarning::from_args())
+        .add_systems(Update, (log_gimbal_lock, highlight_gimbal_lock));
+}
+
+pub fn log_gimbal_lock(
+    warning: Res<GimbalLockWarning>,
+    drone_query: Query<(Entity, &Name, &Transform), With<Drone>>,
+    mut near: Local<HashSet<Entity>>,
+) {
+    for (entity, name, tf) in drone_query.iter() {
+        if !warning.is_near(tf.rotation) {
+            near.remove(&entity);
+            continue;
+        }
+
+        // Only once per approach, not every frame spent there
+        if near.insert(entity) && warning.log {
+            let (_, pitch, _) = tf.rotation.to_euler(EulerRot::YXZ);
+            warn!(
+                "{name} near gimbal lock at {:.1} deg pitch, yaw/roll readings are unreliable",
+                pitch.to_degrees()
+            );
+        }
+    }
+}
+
+/// Turns the pitch readout orange while the active drone is near gimbal lock.
+pub fn highlight_gimbal_lock(
+    warning: Res<GimbalLockWarning>,
+    drone_query: Query<&Transform, With<ActiveDrone>>,
+    text_query: Query<&ChildOf, With<OutputPitchText>>,
+    mut panel_query: Query<&mut BackgroundColor>,
+) {
+    let near = drone_query.iter().any(|tf| warning.is_near(tf.rotation));
+    let background = if near {
+        Color::srgb(0.9, 0.45, 0.0)
+    } else {
+        Color::BLACK
+    };
+
+    for child_of in text_query.iter() {
+        if let Ok(mut panel) = panel_query.get_mut(child_of.parent())
+            && panel.0 != background
+        {
+            panel.0 = background;
+        }
+    }
+}
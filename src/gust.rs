@@ -0,0 +1,207 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{ActiveDrone, EngineState, Wind, arg_value};
+
+/// How long the screen edge flashes when a gust hits (s).
+const FLASH_TIME: f32 = 0.4;
+/// Thickness of the screen-edge flash (px).
+const FLASH_WIDTH: f32 = 12.0;
+/// Length of the gust arrow per m/s of gust (m).
+const ARROW_SCALE: f32 = 0.3;
+
+/// Discrete wind gusts for testing transient disturbance rejection. Every
+/// `interval` seconds a gust of peak speed `strength` blows for `duration`
+/// seconds with a 1-cosine profile, on top of the layered wind. Directions
+/// cycle through `--gust-directions` if given, otherwise they are random
+/// (reproducible with `--gust-seed`). Off unless `--gust-interval` is set.
+#[derive(Resource)]
+pub struct GustScheduler {
+    pub interval: f32,
+    /// Peak gust speed (m/s).
+    pub strength: f32,
+    pub duration: f32,
+    /// Headings to blow towards in turn (deg, 0 = forward, 90 = right).
+    pub directions: Vec<f32>,
+    pub until_next: f32,
+    /// Time into the current gust, or `None` between gusts.
+    pub elapsed: Option<f32>,
+    pub direction: f32,
+    pub count: usize,
+    /// Seed of the direction sequence, for reproducing a run.
+    pub seed: u32,
+    rng: u32,
+}
+
+impl GustScheduler {
+    pub fn from_args() -> Self {
+        let interval = arg_value("--gust-interval")
+            .and_then(|interval| interval.parse::<f32>().ok())
+            .unwrap_or(0.0)
+            .max(0.0);
+        let directions = arg_value("--gust-directions")
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter_map(|direction| direction.trim().parse::<f32>().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let seed = arg_value("--gust-seed")
+            .and_then(|seed| seed.parse::<u32>().ok())
+            .unwrap_or(1)
+            .max(1);
+
+        Self {
+            interval,
+            strength: arg_value("--gust-strength")
+                .and_then(|strength| strength.parse::<f32>().ok())
+                .unwrap_or(8.0),
+            duration: arg_value("--gust-duration")
+                .and_then(|duration| duration.parse::<f32>().ok())
+                .unwrap_or(0.5)
+                .max(0.05),
+            directions,
+            until_next: interval,
+            elapsed: None,
+            direction: 0.0,
+            count: 0,
+            seed,
+            rng: seed,
+        }
+    }
+
+    /// Xorshift; good enough to scatter gust directions.
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng as f32 / u32::MAX as f32
+    }
+
+    fn next_direction(&mut self) -> f32 {
+        if self.directions.is_empty() {
+            self.random() * 360.0
+        } else {
+            self.directions[self.count % self.directions.len()]
+        }
+    }
+
+    /// Gust wind velocity at the current point of the gust.
+    pub fn velocity(&self) -> Vec3 {
+        let Some(elapsed) = self.elapsed else {
+            return Vec3::ZERO;
+        };
+        let speed = self.strength * 0.5 * (1.0 - (TAU * elapsed / self.duration).cos());
+        let direction = self.direction.to_radians();
+        Vec3::new(direction.sin(), 0.0, -direction.cos()) * speed
+    }
+}
+
+/// Screen-edge frame that flashes when a gust hits.
+#[derive(Component)]
+pub struct GustFlash;
+
+pub fn gust_plugin(app: &mut App) {
+    let scheduler = GustScheduler::from_args();
+    let enabled = scheduler.interval > 0.0;
+
+    app.insert_resource(scheduler)
+        .add_systems(Startup, spawn_gust_flash.run_if(move || enabled))
+        .add_systems(
+            Update,
+            schedule_gusts
+                .run_if(in_state(EngineState::On))
+                .run_if(move || enabled),
+        )
+        .add_systems(
+            Update,
+            (draw_gust, update_gust_flash).run_if(move || enabled),
+        )
+        .add_systems(OnExit(EngineState::On), stop_gust);
+}
+
+pub fn spawn_gust_flash(mut commands: Commands) {
+    commands.spawn((
+        GustFlash,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            border: UiRect::all(Val::Px(FLASH_WIDTH)),
+            ..Default::default()
+        },
+        BorderColor(Color::NONE),
+        Pickable::IGNORE,
+    ));
+}
+
+pub fn schedule_gusts(
+    time: Res<Time>,
+    mut scheduler: ResMut<GustScheduler>,
+    mut wind: ResMut<Wind>,
+) {
+    let dt = time.delta_secs();
+
+    if let Some(elapsed) = scheduler.elapsed {
+        let elapsed = elapsed + dt;
+        scheduler.elapsed = (elapsed < scheduler.duration).then_some(elapsed);
+    } else {
+        scheduler.until_next -= dt;
+        if scheduler.until_next <= 0.0 {
+            scheduler.until_next += scheduler.interval;
+            scheduler.direction = scheduler.next_direction();
+            scheduler.count += 1;
+            scheduler.elapsed = Some(0.0);
+            info!(
+                "Gust {}: {:.1} m/s towards {:.0} deg",
+                scheduler.count, scheduler.strength, scheduler.direction
+            );
+        }
+    }
+
+    wind.gust = scheduler.velocity();
+}
+
+pub fn stop_gust(mut scheduler: ResMut<GustScheduler>, mut wind: ResMut<Wind>) {
+    scheduler.elapsed = None;
+    wind.gust = Vec3::ZERO;
+}
+
+/// Arrow through the active drone along the gust, scaled by its speed.
+pub fn draw_gust(
+    wind: Res<Wind>,
+    drone_query: Query<&Transform, With<ActiveDrone>>,
+    mut gizmos: Gizmos,
+) {
+    if wind.gust == Vec3::ZERO {
+        return;
+    }
+
+    for tf in drone_query.iter() {
+        let half = wind.gust * ARROW_SCALE * 0.5;
+        gizmos
+            .arrow(
+                tf.translation - half,
+                tf.translation + half,
+                Color::srgb(0.3, 0.8, 1.0),
+            )
+            .with_tip_length(0.4);
+    }
+}
+
+/// Flashes the screen edge at the start of each gust, fading over `FLASH_TIME`.
+pub fn update_gust_flash(
+    scheduler: Res<GustScheduler>,
+    mut flash_query: Query<&mut BorderColor, With<GustFlash>>,
+) {
+    let alpha = scheduler
+        .elapsed
+        .map_or(0.0, |elapsed| (1.0 - elapsed / FLASH_TIME).max(0.0) * 0.8);
+
+    for mut border in flash_query.iter_mut() {
+        border.0 = Color::srgba(0.3, 0.8, 1.0, alpha);
+    }
+}
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+
+use crate::{ActiveDrone, Drone, arg_value};
+
+/// How long the HUD keeps warning after the last saturated reading (s), so a
+/// brief spike is still readable.
+const WARNING_HOLD: f32 = 0.5;
+
+/// Gyro measurement range. The attitude D terms act on the measured body
+/// rate, which a real gyro can't report past its full-scale range: beyond it
+/// the reading sticks at the limit, so a fast tumble looks slower than it is
+/// and the damping falls short. Off unless `--gyro-range <deg/s>` is given.
+#[derive(Resource)]
+pub struct GyroRange {
+    /// Full-scale rate (rad/s).
+    pub limit: Option<f32>,
+}
+
+impl GyroRange {
+    pub fn from_args() -> Self {
+        Self {
+            limit: arg_value("--gyro-range")
+                .and_then(|range| range.parse::<f32>().ok())
+                .filter(|range| *range > 0.0)
+                .map(f32::to_radians),
+        }
+    }
+
+    /// The reading for a true `rate` (rad/s), and whether it's clipped.
+    pub fn measure(&self, rate: f32) -> (f32, bool) {
+        match self.limit {
+            Some(limit) if rate.abs() > limit => (rate.clamp(-limit, limit), true),
+            _ => (rate, false),
+        }
+    }
+}
+
+/// Per-drone gyro saturation, set by `update_drone_forces`.
+#[derive(Component, Default)]
+pub struct GyroSaturation {
+    /// Time since the last saturated reading (s).
+    pub since: f32,
+    pub saturated: bool,
+    /// Readings clipped so far.
+    pub events: u32,
+}
+
+impl GyroSaturation {
+    pub fn record(&mut self, saturated: bool, dt: f32) {
+        if saturated {
+            if !self.saturated {
+                self.events += 1;
+            }
+            self.since = 0.0;
+        } else {
+            self.since += dt;
+        }
+        self.saturated = saturated;
+    }
+}
+
+#[derive(Component)]
+pub struct GyroText;
+
+pub fn gyro_plugin(app: &mut App) {
+    let range = GyroRange::from_args();
+    let enabled = range.limit.is_some();
+
+    app.insert_resource(range).add_systems(
+        Update,
+        (
+            add_gyro_saturation.run_if(move || enabled),
+            update_gyro_text,
+        ),
+    );
+}
+
+pub fn add_gyro_saturation(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<GyroSaturation>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(GyroSaturation {
+            since: WARNING_HOLD,
+            ..Default::default()
+        });
+    }
+}
+
+pub fn update_gyro_text(
+    range: Res<GyroRange>,
+    drone_query: Query<&GyroSaturation, With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<GyroText>>,
+) {
+    let saturation = drone_query.single().ok();
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = match (range.limit, saturation) {
+            (None, _) => ("Gyro: unlimited".into(), Color::WHITE),
+            (Some(_), Some(saturation)) if saturation.since < WARNING_HOLD => (
+                format!("GYRO SAT ({})", saturation.events).into(),
+                Color::srgb(1.0, 0.2, 0.2),
+            ),
+            (Some(limit), _) => (
+                format!("Gyro: ±{:.0}°/s", limit.to_degrees()).into(),
+                Color::WHITE,
+            ),
+        };
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ActiveDrone, Disarmed, EngineState, GamepadInput, HoverPid, PitchPid, RollPid, arg_value,
+    center_setpoints, chase_guidance, clamp_setpoints,
+    follow_mouse::FollowCursor,
+    gamepad_control, manual_control,
+    mission::WaypointMission,
+    orbit::OrbitPoi,
+    position::{PositionPid, release_tilt},
+    return_home::ReturningHome,
+};
+
+const LAND_KEY: KeyCode = KeyCode::KeyL;
+/// Keys that take the drone back from the autopilot.
+const MANUAL_KEYS: [KeyCode; 8] = [
+    KeyCode::Space,
+    KeyCode::ControlLeft,
+    KeyCode::KeyW,
+    KeyCode::KeyA,
+    KeyCode::KeyS,
+    KeyCode::KeyD,
+    KeyCode::KeyQ,
+    KeyCode::KeyE,
+];
+/// Height below which the drone counts as down (m); it rests at about 0.1 m.
+const TOUCHDOWN_Y: f32 = 0.2;
+/// Fastest the drone may still be moving to be switched off (m/s).
+const TOUCHDOWN_SPEED: f32 = 0.1;
+/// Furthest the altitude setpoint runs ahead of the drone (m), so a drone
+/// that lags behind the ramp isn't pulled down harder and harder.
+const MAX_LEAD: f32 = 0.5;
+
+/// Settings of the auto-land started with `L`.
+#[derive(Resource)]
+pub struct LandingSettings {
+    /// Descent rate of the altitude setpoint (m/s).
+    pub descent_rate: f32,
+    /// Height from which it slows down for touchdown (m).
+    pub flare_height: f32,
+    /// Descent rate below `flare_height` (m/s).
+    pub flare_rate: f32,
+}
+
+impl LandingSettings {
+    pub fn from_args() -> Self {
+        let descent_rate = arg_value("--land-rate")
+            .and_then(|rate| rate.parse::<f32>().ok())
+            .unwrap_or(1.0)
+            .max(0.05);
+        Self {
+            descent_rate,
+            flare_height: arg_value("--land-flare")
+                .and_then(|height| height.parse::<f32>().ok())
+                .unwrap_or(1.5)
+                .max(0.0),
+            flare_rate: arg_value("--land-flare-rate")
+                .and_then(|rate| rate.parse::<f32>().ok())
+                .unwrap_or(0.3)
+                .clamp(0.05, descent_rate),
+        }
+    }
+}
+
+/// A drone on its way down. Landing levels the drone, ramps its altitude
+/// setpoint to the ground and disarms the drone once it's resting there.
+#[derive(Component, Default)]
+pub struct LandingState {
+    /// Time since the landing started (s).
+    pub elapsed: f32,
+}
+
+#[derive(Component)]
+pub struct LandingText;
+
+pub fn landing_plugin(app: &mut App) {
+    app.insert_resource(LandingSettings::from_args())
+        .add_systems(
+            Update,
+            (toggle_landing, auto_land)
+                .chain()
+                .after(manual_control)
+                .after(gamepad_control)
+                .before(center_setpoints)
+                .before(clamp_setpoints)
+                .before(chase_guidance)
+                .run_if(in_state(EngineState::On)),
+        )
+        .add_systems(Update, update_landing_text)
+        .add_systems(OnExit(EngineState::On), cancel_landing);
+}
+
+#[allow(clippy::type_complexity)]
+pub fn toggle_landing(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut drone_query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut PositionPid,
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+            Has<LandingState>,
+        ),
+        With<ActiveDrone>,
+    >,
+) {
+    if !keyboard.just_pressed(LAND_KEY) {
+        return;
+    }
+
+    for (drone, tf, mut ctl_pos, mut ctl_y, mut ctl_pitch, mut ctl_roll, landing) in
+        drone_query.iter_mut()
+    {
+        if landing {
+            commands.entity(drone).remove::<LandingState>();
+            ctl_y.target_y = tf.translation.y;
+            info!("Landing cancelled");
+            continue;
+        }
+
+        // Start from where the drone is rather than a setpoint it hasn't reached
+        ctl_pos.disengage();
+        release_tilt(&mut ctl_pitch, &mut ctl_roll);
+        ctl_y.target_y = ctl_y.target_y.min(tf.translation.y);
+        info!("Landing from {:.1} m", tf.translation.y);
+        commands
+            .entity(drone)
+            .remove::<(WaypointMission, OrbitPoi, FollowCursor, ReturningHome)>()
+            .insert(LandingState::default());
+    }
+}
+
+/// Ramps the altitude setpoint down, slower below the flare height, and
+/// disarms the drone once it has settled on the ground. Any
+/// manual input hands control straight back to the pilot.
+#[allow(clippy::type_complexity)]
+pub fn auto_land(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<LandingSettings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepad: Res<GamepadInput>,
+    mut drone_query: Query<(
+        Entity,
+        &Transform,
+        &Velocity,
+        &mut HoverPid,
+        &mut LandingState,
+    )>,
+) {
+    let manual = keyboard.any_pressed(MANUAL_KEYS) || gamepad.tilting;
+    let dt = time.delta_secs();
+
+    for (drone, tf, velocity, mut ctl_y, mut landing) in drone_query.iter_mut() {
+        if manual {
+            commands.entity(drone).remove::<LandingState>();
+            info!("Landing aborted by manual input");
+            continue;
+        }
+
+        let y = tf.translation.y;
+        if y < TOUCHDOWN_Y && velocity.linvel.length() < TOUCHDOWN_SPEED {
+            commands
+                .entity(drone)
+                .remove::<LandingState>()
+                .insert(Disarmed);
+            info!("Landed after {:.1} s", landing.elapsed);
+            continue;
+        }
+
+        landing.elapsed += dt;
+        let rate = if y < settings.flare_height {
+            settings.flare_rate
+        } else {
+            settings.descent_rate
+        };
+        ctl_y.target_y = (ctl_y.target_y - rate * dt).max(y - MAX_LEAD).max(0.0);
+    }
+}
+
+pub fn cancel_landing(mut commands: Commands, drone_query: Query<Entity, With<LandingState>>) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).remove::<LandingState>();
+    }
+}
+
+pub fn update_landing_text(
+    drone_query: Query<(&Transform, Option<&LandingState>), With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<LandingText>>,
+) {
+    let Ok((tf, landing)) = drone_query.single() else {
+        return;
+    };
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = match landing {
+            Some(_) => (
+                format!("LANDING {:.1} m", tf.translation.y).into(),
+                Color::srgb(1.0, 0.8, 0.2),
+            ),
+            None => ("Land: L".into(), Color::WHITE),
+        };
+    }
+}
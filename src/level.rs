@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, de};
+
+use crate::wind::WindLayer;
+
+pub const DEFAULT_LEVEL_PATH: &str = "assets/levels/default.ron";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GateDef {
+    pub position: [f32; 3],
+    pub yaw: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// What a mission does on reaching a waypoint before flying on.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum WaypointAction {
+    #[default]
+    FlyThrough,
+    /// Hold position over the waypoint for this long (s).
+    Loiter(f32),
+    /// Circle the waypoint at the `--orbit-radius` and `--orbit-speed` for
+    /// this long (s).
+    Orbit(f32),
+    /// Fly back over the spawn point at the waypoint's altitude and end the
+    /// mission there.
+    ReturnHome,
+}
+
+/// A mission waypoint, written either as a bare `(x, y, z)` or as
+/// `(position: (x, y, z), action: Loiter(5.0))`.
+#[derive(Clone, Copy, Debug)]
+pub struct Waypoint {
+    pub position: Vec3,
+    pub action: WaypointAction,
+}
+
+impl<'de> Deserialize<'de> for Waypoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "snake_case")]
+        enum Field {
+            Position,
+            Action,
+        }
+
+        struct WaypointVisitor;
+
+        impl<'de> de::Visitor<'de> for WaypointVisitor {
+            type Value = Waypoint;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("(x, y, z) or (position: (x, y, z), action: ...)")
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<Waypoint, A::Error> {
+                let position = <[f32; 3]>::deserialize(de::value::SeqAccessDeserializer::new(seq))?;
+                Ok(Waypoint {
+                    position: Vec3::from(position),
+                    action: WaypointAction::default(),
+                })
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Waypoint, A::Error> {
+                let mut position: Option<[f32; 3]> = None;
+                let mut action = WaypointAction::default();
+                while let Some(field) = map.next_key::<Field>()? {
+                    match field {
+                        Field::Position => position = Some(map.next_value()?),
+                        Field::Action => action = map.next_value()?,
+                    }
+                }
+                let position = position.ok_or_else(|| de::Error::missing_field("position"))?;
+                Ok(Waypoint {
+                    position: Vec3::from(position),
+                    action,
+                })
+            }
+        }
+
+        // Bare points and points with an action can be mixed, so the shape
+        // decides which one this is
+        deserializer.deserialize_any(WaypointVisitor)
+    }
+}
+
+#[derive(Resource, Deserialize, Default, Clone, Debug)]
+pub struct Level {
+    #[serde(default)]
+    pub gates: Vec<GateDef>,
+    #[serde(default)]
+    pub wind: Vec<WindLayer>,
+    /// Points a mission (`M`) flies through, in order.
+    #[serde(default)]
+    pub waypoints: Vec<Waypoint>,
+    /// File the level was loaded from, for reloading.
+    #[serde(skip)]
+    pub path: String,
+}
+
+impl Level {
+    pub fn load(path: &str) -> Self {
+        let level = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| ron::from_str::<Level>(&contents).map_err(|e| e.to_string()));
+
+        let level = match level {
+            Ok(level) => level,
+            Err(e) => {
+                warn!("Failed to load level {path}: {e}");
+                Self::default()
+            }
+        };
+        Self {
+            path: path.to_string(),
+            ..level
+        }
+    }
+}
@@ -0,0 +1,2804 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    input::{InputPlugin, mouse::MouseMotion},
+    prelude::*,
+    scene::ScenePlugin,
+    state::app::StatesPlugin,
+    window::WindowMode,
+};
+use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
+use bevy_rapier3d::prelude::*;
+
+mod altitude;
+mod altitude_floor;
+mod banner;
+mod catch;
+mod chase;
+mod collision_log;
+mod compare;
+mod coordinated_turn;
+mod disturbance;
+mod drag;
+mod drone_visuals;
+mod energy;
+mod envelope;
+mod event_log;
+mod failsafe;
+mod flythrough;
+mod follow_mouse;
+mod fpv;
+mod freeze;
+mod gamepad;
+mod gamepad_calibration;
+mod gate;
+mod ghost;
+mod gimbal;
+mod gust;
+mod gyro;
+mod landing;
+mod level;
+mod measure;
+mod mission;
+mod mixer;
+mod orbit;
+mod perch;
+mod pid_debug;
+mod position;
+mod prediction;
+mod profile;
+mod race;
+mod replay;
+mod return_home;
+mod scene;
+mod stats;
+mod sticks;
+mod sysid;
+mod telemetry;
+mod telemetry_server;
+mod tether;
+mod tilt_rotor;
+mod timestep;
+mod trajectory;
+mod trim;
+mod tuning;
+mod validation;
+mod vibration;
+mod vortex_ring;
+mod wheel;
+mod wind;
+mod wind_estimate;
+mod wind_hold_demo;
+mod windup_demo;
+mod yaw_to_velocity;
+
+use altitude::*;
+use altitude_floor::*;
+use banner::*;
+use catch::*;
+use chase::*;
+use collision_log::*;
+use compare::*;
+use coordinated_turn::*;
+use disturbance::*;
+use drag::*;
+use drone_visuals::*;
+use energy::*;
+use envelope::*;
+use event_log::*;
+use failsafe::*;
+use flythrough::*;
+use follow_mouse::*;
+use fpv::*;
+use freeze::*;
+use gamepad::*;
+use gamepad_calibration::*;
+use gate::*;
+use ghost::*;
+use gimbal::*;
+use gust::*;
+use gyro::*;
+use landing::*;
+use level::{DEFAULT_LEVEL_PATH, Level};
+use measure::*;
+use mission::*;
+use mixer::*;
+use orbit::*;
+use perch::*;
+use pid_debug::*;
+use position::*;
+use prediction::*;
+use profile::*;
+use race::*;
+use replay::*;
+use return_home::*;
+use scene::*;
+use stats::*;
+use sticks::*;
+use sysid::*;
+use telemetry::*;
+pub use telemetry_server::serve_replay;
+use telemetry_server::*;
+use tether::*;
+use tilt_rotor::*;
+use timestep::*;
+use trim::*;
+use tuning::*;
+use validation::*;
+use vibration::*;
+use vortex_ring::*;
+use wheel::*;
+use wind::*;
+use wind_estimate::*;
+use wind_hold_demo::*;
+use windup_demo::*;
+use yaw_to_velocity::*;
+
+const FOLLOW_DIST: f32 = 15.0;
+const FOLLOW_PITCH: f32 = 10.0;
+const SENSITIVITY: f32 = 0.005;
+/// Default time constant of the orbit camera's mouse smoothing (s).
+const MOUSE_SMOOTHING: f32 = 0.04;
+const MIN_CAMERA_PITCH: f32 = -40.0;
+const MAX_CAMERA_PITCH: f32 = 89.0;
+/// How quickly the orbit eases towards a selected preset (1/s).
+const CAMERA_PRESET_RATE: f32 = 6.0;
+/// Toggles the orbit following the drone's heading.
+const HEADING_LOCK_KEY: KeyCode = KeyCode::Digit4;
+/// Default time constant the camera turns after the drone's heading with (s).
+const HEADING_SMOOTHING: f32 = 0.3;
+const GRAVITY: f32 = 9.81;
+/// Sideways spacing between the drones spawned with `--drones` (m).
+const DRONE_SPACING: f32 = 3.0;
+const MAX_DRONES: usize = 16;
+const DEBUG_PRINT_INTERVAL: f32 = 0.5;
+const AIRBORNE_MIN_Y: f32 = 0.5;
+const UI_SCALE_STEP: f32 = 0.1;
+/// Motor/propeller mounting points in the drone's body frame.
+const PROPELLER_OFFSETS: [Vec3; 4] = [
+    Vec3::new(-0.5, 0.0, -0.5), // front-left
+    Vec3::new(0.5, 0.0, -0.5),  // front-right
+    Vec3::new(-0.5, 0.0, 0.5),  // back-left
+    Vec3::new(0.5, 0.0, 0.5),   // back-right
+];
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 3.0;
+
+#[derive(Component)]
+pub struct Drone;
+
+/// Stable number of a drone, matching its name ("Drone 2" is 2), used to tell
+/// drones apart in logs.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DroneId(pub usize);
+
+#[derive(Component)]
+pub struct Propeller(pub usize);
+
+#[derive(Component)]
+pub struct DroneCamera;
+
+/// Makes a `DroneCamera` orbit this drone instead of the active one.
+#[derive(Component)]
+pub struct FollowDrone(pub Entity);
+
+#[derive(Component)]
+pub struct HoverPid {
+    pub kp: f32,
+    pub min_kp: f32,
+    pub max_kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub prev_e: f32,
+    pub integral_e: f32,
+    pub anti_windup: AntiWindup,
+    pub integral_max: f32,
+    pub integral_max_frac: f32,
+    /// Gain on the setpoint's rate of change, added straight to the output (1/s).
+    pub feedforward_gain: f32,
+    pub prev_target: f32,
+    pub target_y: f32,
+    pub v_rate: f32,
+    pub min_y: f32,
+    pub max_y: f32,
+    /// Depth of the soft ceiling below `max_y` (m); 0 keeps the hard clamp.
+    pub ceiling_softness: f32,
+    /// Scale the thrust by `1/cos(tilt)`, so a tilted drone still holds up
+    /// its weight instead of sagging until the feedback catches up.
+    pub tilt_compensation: bool,
+    /// Largest factor the tilt compensation may scale the thrust by.
+    pub max_tilt_factor: f32,
+    pub smoothing: bool,
+    pub smoothing_tau: f32,
+    /// +1, or -1 to flip the controller's contribution when hunting down a
+    /// sign-convention mismatch.
+    pub output_sign: f32,
+    pub output: f32,
+}
+
+impl HoverPid {
+    /// Altitude setpoint the controller flies. Within `ceiling_softness` of
+    /// `max_y` the target is pushed down more and more the higher it goes,
+    /// approaching the ceiling smoothly instead of running into the clamp.
+    pub fn soft_target(&self) -> f32 {
+        self.soften(self.target_y)
+    }
+
+    /// Highest altitude the controller will actually hold.
+    pub fn effective_ceiling(&self) -> f32 {
+        self.soften(self.max_y)
+    }
+
+    fn soften(&self, y: f32) -> f32 {
+        let knee = self.max_y - self.ceiling_softness;
+        if self.ceiling_softness <= 0.0 || y <= knee {
+            return y;
+        }
+        knee + self.ceiling_softness * ((y - knee) / self.ceiling_softness).tanh()
+    }
+}
+
+#[derive(Component)]
+pub struct PitchPid {
+    pub kp: f32,
+    pub min_kp: f32,
+    pub max_kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub prev_e: f32,
+    pub integral_e: f32,
+    pub anti_windup: AntiWindup,
+    pub integral_max: f32,
+    pub integral_max_frac: f32,
+    /// Gain on the setpoint's rate of change, added straight to the output (1/s).
+    pub feedforward_gain: f32,
+    pub prev_target: f32,
+    pub target_angle: f32,
+    pub angle_rate: f32,
+    pub min_angle: f32,
+    pub max_angle: f32,
+    /// How fast the setpoint eases back to level after the key is released (rad/s).
+    pub center_rate: f32,
+    pub centering: bool,
+    pub smoothing: bool,
+    pub smoothing_tau: f32,
+    /// +1, or -1 to flip the controller's contribution when hunting down a
+    /// sign-convention mismatch.
+    pub output_sign: f32,
+    pub output: f32,
+}
+
+#[derive(Component)]
+pub struct RollPid {
+    pub kp: f32,
+    pub min_kp: f32,
+    pub max_kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub prev_e: f32,
+    pub integral_e: f32,
+    pub anti_windup: AntiWindup,
+    pub integral_max: f32,
+    pub integral_max_frac: f32,
+    /// Gain on the setpoint's rate of change, added straight to the output (1/s).
+    pub feedforward_gain: f32,
+    pub prev_target: f32,
+    pub target_angle: f32,
+    pub angle_rate: f32,
+    pub min_angle: f32,
+    pub max_angle: f32,
+    /// How fast the setpoint eases back to level after the key is released (rad/s).
+    pub center_rate: f32,
+    pub centering: bool,
+    pub smoothing: bool,
+    pub smoothing_tau: f32,
+    /// +1, or -1 to flip the controller's contribution when hunting down a
+    /// sign-convention mismatch.
+    pub output_sign: f32,
+    pub output: f32,
+}
+
+#[derive(Component)]
+pub struct YawPid {
+    pub kp: f32,
+    pub min_kp: f32,
+    pub max_kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub prev_e: f32,
+    pub integral_e: f32,
+    pub anti_windup: AntiWindup,
+    pub integral_max: f32,
+    pub integral_max_frac: f32,
+    /// Gain on the setpoint's rate of change, added straight to the output (1/s).
+    pub feedforward_gain: f32,
+    pub prev_target: f32,
+    pub target_angle: f32,
+    pub angle_rate: f32,
+    pub min_angle: f32,
+    pub max_angle: f32,
+    pub smoothing: bool,
+    pub smoothing_tau: f32,
+    /// +1, or -1 to flip the controller's contribution when hunting down a
+    /// sign-convention mismatch.
+    pub output_sign: f32,
+    pub output: f32,
+}
+
+/// First-order spin-up lag of the rotors. Yaw comes from differential rotor
+/// torque, so it follows the commanded torque more slowly than pitch/roll.
+#[derive(Component)]
+pub struct RotorLag {
+    pub yaw_tau: f32,
+    pub yaw_torque: f32,
+}
+
+#[derive(Component)]
+pub struct OutputYText;
+
+#[derive(Component)]
+pub struct TargetYText;
+
+#[derive(Component)]
+pub struct OutputPitchText;
+
+#[derive(Component)]
+pub struct TargetPitchText;
+
+#[derive(Component)]
+pub struct OutputRollText;
+
+#[derive(Component)]
+pub struct TargetRollText;
+
+#[derive(Component)]
+pub struct OutputYawText;
+
+#[derive(Component)]
+pub struct TargetYawText;
+
+#[derive(Component)]
+pub struct EngineText;
+
+#[derive(Component)]
+pub struct DroneText;
+
+#[derive(Component)]
+pub struct EngineUI;
+
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EngineState {
+    On,
+    #[default]
+    Off,
+}
+
+/// Distinct body colors handed out to drones in spawn order.
+pub const DRONE_PALETTE: [(&str, Color); 6] = [
+    ("Blue", Color::srgb(124. / 255., 144. / 255., 1.0)),
+    ("Red", Color::srgb(1.0, 0.35, 0.3)),
+    ("Green", Color::srgb(0.35, 0.9, 0.4)),
+    ("Yellow", Color::srgb(1.0, 0.85, 0.25)),
+    ("Purple", Color::srgb(0.7, 0.4, 1.0)),
+    ("Orange", Color::srgb(1.0, 0.6, 0.2)),
+];
+/// Emissive tint added to the camera-followed drone so it stands out.
+const ACTIVE_DRONE_GLOW: f32 = 0.4;
+
+#[derive(Component)]
+pub struct DroneColor {
+    pub name: &'static str,
+    pub color: Color,
+}
+
+impl DroneColor {
+    /// Color from `--drone-color <name>` for the first drone, otherwise the
+    /// palette entry for the drone's spawn index.
+    pub fn for_index(index: usize) -> Self {
+        let configured = arg_value("--drone-color")
+            .filter(|_| index == 0)
+            .and_then(|name| {
+                let entry = DRONE_PALETTE
+                    .iter()
+                    .find(|(palette_name, _)| palette_name.eq_ignore_ascii_case(&name));
+                if entry.is_none() {
+                    warn!("Unknown --drone-color {name}");
+                }
+                entry
+            });
+        let (name, color) = *configured.unwrap_or(&DRONE_PALETTE[index % DRONE_PALETTE.len()]);
+        Self { name, color }
+    }
+}
+
+/// The drone the camera follows and the HUD describes; `Tab` moves it on
+/// to the next drone.
+#[derive(Component)]
+pub struct ActiveDrone;
+
+/// A drone whose motors were cut on their own (it landed, perched or
+/// tumbled) while the rest keep flying. The engine goes off once every drone
+/// is disarmed, and arming again clears it.
+#[derive(Component)]
+pub struct Disarmed;
+
+/// Drones flown by the pilot's input: the active one and, in comparison
+/// mode, the compare drone mirroring it.
+pub type Piloted = Or<(With<ActiveDrone>, With<CompareDrone>)>;
+
+/// What happens to the controllers when the engine is cut.
+///
+/// `Cut` drops all force and torque. `Deadstick` kills thrust but keeps the
+/// attitude controllers running while airborne so the pilot can glide down.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureMode {
+    #[default]
+    Cut,
+    Deadstick,
+}
+
+/// Disarms the engine when the drone tilts past `threshold` (radians from
+/// level), since spinning motors upside down only drive it into the ground.
+#[derive(Resource)]
+pub struct TumbleDisarm {
+    pub enabled: bool,
+    pub threshold: f32,
+    pub tripped: bool,
+}
+
+impl TumbleDisarm {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: !has_flag("--no-tumble-disarm"),
+            threshold: arg_value("--tumble-angle")
+                .and_then(|angle| angle.parse::<f32>().ok())
+                .unwrap_or(100.0)
+                .to_radians(),
+            tripped: false,
+        }
+    }
+}
+
+/// Caps how fast the attitude setpoints may change, per axis (rad/s), so
+/// sudden stick or autopilot commands turn into smooth, bounded attitude
+/// changes. Unset axes are unlimited.
+#[derive(Resource, Default)]
+pub struct AttitudeRateLimit {
+    pub pitch: Option<f32>,
+    pub roll: Option<f32>,
+    pub yaw: Option<f32>,
+}
+
+impl AttitudeRateLimit {
+    pub fn from_args() -> Self {
+        let rate = |name: &str| {
+            arg_value(name)
+                .and_then(|rate| rate.parse::<f32>().ok())
+                .filter(|rate| *rate > 0.0)
+                .map(f32::to_radians)
+        };
+        Self {
+            pitch: rate("--max-pitch-rate"),
+            roll: rate("--max-roll-rate"),
+            yaw: rate("--max-yaw-rate"),
+        }
+    }
+}
+
+/// Attitude setpoints (pitch, roll, yaw) the rate limit let through last
+/// frame, which the next frame's setpoints are stepped from.
+#[derive(Component, Default)]
+pub struct LimitedAttitude(pub Option<Vec3>);
+
+/// HUD suffix with an axis's rate limit, if it has one.
+fn rate_limit_text(limit: Option<f32>) -> String {
+    limit.map_or(String::new(), |rate| {
+        format!(" (max {:.0} deg/s)", rate.to_degrees())
+    })
+}
+
+/// How the integral term is kept from winding up.
+///
+/// `Clamp`, the default, bounds `integral_e` itself to `±integral_max`.
+/// `OutputFraction` lets the error accumulate but limits the integral term to
+/// `±integral_max_frac` of the proportional + derivative output.
+/// `Conditional` clamps like `Clamp` and also stops integrating while the
+/// mixer or the battery can't deliver the axis's output and the error would
+/// push it further into the limit.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntiWindup {
+    Off,
+    #[default]
+    Clamp,
+    OutputFraction,
+    Conditional,
+}
+
+impl AntiWindup {
+    fn from_args() -> Self {
+        match arg_value("--anti-windup").as_deref() {
+            Some("off") => Self::Off,
+            None | Some("clamp") => Self::Clamp,
+            Some("fraction") => Self::OutputFraction,
+            Some("conditional") => Self::Conditional,
+            Some(other) => {
+                warn!(
+                    "Unknown --anti-windup mode {other}, expected off, clamp, fraction or conditional"
+                );
+                Self::Clamp
+            }
+        }
+    }
+}
+
+/// How the attitude error fed to the pitch/roll/yaw PIDs is computed.
+///
+/// `Euler` takes each axis's error from the YXZ euler angles on its own, which
+/// breaks down near ±90° pitch. `Quaternion` builds the desired orientation
+/// from the three setpoints and splits the rotation from the current
+/// orientation into it (as a world-frame rotation vector) over the axes.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttitudeMode {
+    Euler,
+    #[default]
+    Quaternion,
+}
+
+impl AttitudeMode {
+    pub fn from_args() -> Self {
+        match arg_value("--attitude").as_deref() {
+            None | Some("quaternion") => Self::Quaternion,
+            Some("euler") => Self::Euler,
+            Some(other) => {
+                warn!("Unknown --attitude mode {other}, expected quaternion or euler");
+                Self::Quaternion
+            }
+        }
+    }
+
+    /// Pitch, roll and yaw errors (rad) towards the target angles.
+    pub fn errors(self, rotation: Quat, pitch: f32, roll: f32, yaw: f32) -> (f32, f32, f32) {
+        match self {
+            Self::Euler => {
+                let (cur_yaw, cur_pitch, cur_roll) = rotation.to_euler(EulerRot::YXZ);
+                (
+                    angle_error(pitch, cur_pitch),
+                    angle_error(roll, cur_roll),
+                    angle_error(yaw, cur_yaw),
+                )
+            }
+            Self::Quaternion => {
+                let target = Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll);
+                let mut error = target * rotation.inverse();
+                // q and -q are the same rotation; take the short way round
+                if error.w < 0.0 {
+                    error = -error;
+                }
+                let e = error.to_scaled_axis();
+                (e.x, e.z, e.y)
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Delay {
+    pub timer: Timer,
+}
+
+impl Delay {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct DebugPrint {
+    pub enabled: bool,
+    pub timer: Timer,
+}
+
+impl DebugPrint {
+    pub fn new(enabled: bool, interval: f32) -> Self {
+        Self {
+            enabled,
+            timer: Timer::from_seconds(interval, TimerMode::Repeating),
+        }
+    }
+}
+
+/// A saved orbit angle the camera can ease to with a number key.
+pub struct CameraPreset {
+    pub name: &'static str,
+    pub key: KeyCode,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+#[derive(Resource)]
+pub struct DroneCameraParams {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub radius: f32,
+    pub sensitivity: f32,
+    /// Time constant the mouse deltas are eased in with; 0 applies them raw.
+    pub mouse_smoothing: f32,
+    /// Mouse motion (yaw, pitch) not yet applied by the smoothing.
+    pub pending_motion: Vec2,
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    pub min_yaw: f32,
+    pub max_yaw: f32,
+    pub presets: Vec<CameraPreset>,
+    /// Orbit angles (yaw, pitch) the camera is easing towards, if any.
+    pub preset_target: Option<(f32, f32)>,
+    /// Turns the orbit with the drone so it stays behind it; `yaw` is then
+    /// relative to the drone's heading instead of the world.
+    pub yaw_follows_drone: bool,
+    /// Time constant of the turn after the heading; 0 snaps to it.
+    pub heading_smoothing: f32,
+}
+
+impl Default for DroneCameraParams {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: FOLLOW_PITCH.to_radians(),
+            radius: FOLLOW_DIST,
+            sensitivity: SENSITIVITY,
+            mouse_smoothing: MOUSE_SMOOTHING,
+            pending_motion: Vec2::ZERO,
+            min_pitch: MIN_CAMERA_PITCH.to_radians(),
+            max_pitch: MAX_CAMERA_PITCH.to_radians(),
+            min_yaw: f32::NEG_INFINITY,
+            max_yaw: f32::INFINITY,
+            presets: vec![
+                CameraPreset {
+                    name: "Top-down",
+                    key: KeyCode::Digit1,
+                    yaw: 0.0,
+                    pitch: MAX_CAMERA_PITCH.to_radians(),
+                },
+                CameraPreset {
+                    name: "Behind",
+                    key: KeyCode::Digit2,
+                    yaw: 0.0,
+                    pitch: FOLLOW_PITCH.to_radians(),
+                },
+                CameraPreset {
+                    name: "Side",
+                    key: KeyCode::Digit3,
+                    yaw: 90_f32.to_radians(),
+                    pitch: 0.0,
+                },
+            ],
+            preset_target: None,
+            yaw_follows_drone: false,
+            heading_smoothing: HEADING_SMOOTHING,
+        }
+    }
+}
+
+impl DroneCameraParams {
+    /// Defaults with orbit limits overridden by `--camera-pitch-limits MIN,MAX`
+    /// and `--camera-yaw-limits MIN,MAX` (degrees).
+    pub fn from_args() -> Self {
+        let mut params = Self::default();
+        if let Some(tau) = arg_value("--mouse-smoothing").and_then(|tau| tau.parse::<f32>().ok()) {
+            params.mouse_smoothing = tau.max(0.0);
+        }
+        params.yaw_follows_drone = has_flag("--camera-heading-lock");
+        if let Some(tau) =
+            arg_value("--camera-heading-smoothing").and_then(|tau| tau.parse::<f32>().ok())
+        {
+            params.heading_smoothing = tau.max(0.0);
+        }
+        if let Some((min, max)) = degree_limits("--camera-pitch-limits") {
+            params.min_pitch = min;
+            params.max_pitch = max;
+        }
+        if let Some((min, max)) = degree_limits("--camera-yaw-limits") {
+            params.min_yaw = min;
+            params.max_yaw = max;
+        }
+        params.clamp_orbit();
+        params
+    }
+
+    fn clamp_orbit(&mut self) {
+        self.pitch = self.pitch.clamp(self.min_pitch, self.max_pitch);
+        self.yaw = self.yaw.clamp(self.min_yaw, self.max_yaw);
+    }
+}
+
+fn degree_limits(name: &str) -> Option<(f32, f32)> {
+    let value = arg_value(name)?;
+    let limits = value.split_once(',').and_then(|(min, max)| {
+        let min = min.trim().parse::<f32>().ok()?;
+        let max = max.trim().parse::<f32>().ok()?;
+        (min <= max).then(|| (min.to_radians(), max.to_radians()))
+    });
+    if limits.is_none() {
+        warn!("Invalid {name} {value}, expected MIN,MAX in degrees");
+    }
+    limits
+}
+
+/// Builds the sim. A `headless` app has no window, renderer, HUD or egui,
+/// just the physics, the drones and the flight controllers, so the control
+/// loop can be stepped from tests and scripts. The drones still get their
+/// meshes and materials, which are only stored there.
+pub fn build_app(headless: bool) -> App {
+    let level =
+        Level::load(&arg_value("--level").unwrap_or_else(|| DEFAULT_LEVEL_PATH.to_string()));
+
+    let mut app = App::new();
+    if headless {
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            StatesPlugin,
+            InputPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>();
+    } else {
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                title: "Drone Sim".into(),
+                resizable: true,
+                mode: WindowMode::BorderlessFullscreen(MonitorSelection::Primary),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }));
+    }
+
+    // Before the physics, which wants its fixed timestep in place
+    app.add_plugins(timestep_plugin)
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
+        .insert_resource(Delay::new(0.05))
+        .insert_resource(if has_flag("--deadstick") {
+            FailureMode::Deadstick
+        } else {
+            FailureMode::Cut
+        })
+        .insert_resource(TumbleDisarm::from_args())
+        .insert_resource(AttitudeRateLimit::from_args())
+        .insert_resource(AttitudeMode::from_args())
+        .insert_resource(Wind::new(level.wind.clone()))
+        .insert_resource(WindSwell::from_args())
+        .insert_resource(DebugPrint::new(has_flag("--verbose"), DEBUG_PRINT_INTERVAL))
+        .insert_resource(level)
+        .init_state::<EngineState>()
+        .add_systems(Startup, spawn_floor)
+        .add_systems(Startup, spawn_drone)
+        .add_systems(Update, manual_control)
+        .add_systems(
+            FixedUpdate,
+            update_drone_forces
+                .before(PhysicsSet::SyncBackend)
+                .run_if(in_state(EngineState::On).or(resource_equals(FailureMode::Deadstick))),
+        )
+        .add_systems(
+            FixedUpdate,
+            (swell_wind, apply_wind)
+                .chain()
+                .before(PhysicsSet::SyncBackend)
+                .run_if(in_state(EngineState::On)),
+        )
+        .add_systems(
+            Update,
+            (chase_guidance, position_control)
+                .chain()
+                .after(manual_control)
+                .run_if(in_state(EngineState::On)),
+        )
+        .add_systems(
+            Update,
+            center_setpoints
+                .after(manual_control)
+                .before(chase_guidance)
+                .before(clamp_setpoints),
+        )
+        .add_systems(
+            Update,
+            clamp_setpoints
+                .after(manual_control)
+                .after(position_control),
+        )
+        .add_systems(Update, limit_attitude_rate.after(clamp_setpoints))
+        .add_systems(Update, tumble_disarm.run_if(in_state(EngineState::On)))
+        .add_systems(Update, debug_print)
+        .add_systems(
+            PostUpdate,
+            engine_off_when_disarmed.run_if(in_state(EngineState::On)),
+        )
+        .add_systems(
+            OnEnter(EngineState::On),
+            (rearm_tumble_disarm, rearm_drones),
+        )
+        .add_systems(OnEnter(EngineState::On), reset_feedforward)
+        .add_systems(OnExit(EngineState::On), (engine_off, stop_swell))
+        .add_plugins(catch_plugin)
+        .add_plugins(mixer_plugin)
+        .add_plugins(vortex_ring_plugin)
+        .add_plugins(gyro_plugin)
+        .add_plugins(drag_plugin);
+    if headless {
+        return app;
+    }
+
+    app.add_plugins(RapierDebugRenderPlugin::default())
+        .add_plugins(EguiPlugin::default())
+        .insert_resource(DroneCameraParams::from_args())
+        .insert_resource(UiScale(
+            arg_value("--ui-scale")
+                .and_then(|scale| scale.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(MIN_UI_SCALE, MAX_UI_SCALE),
+        ))
+        .insert_resource(CollisionLog::new(arg_value("--collision-log").as_deref()))
+        .init_resource::<RaceState>()
+        .init_resource::<TuningPanel>()
+        .add_event::<GatePassed>()
+        .add_systems(Startup, spawn_light)
+        .add_systems(Startup, spawn_camera)
+        .add_systems(Startup, spawn_ui)
+        .add_systems(Startup, spawn_gates)
+        .add_systems(
+            Startup,
+            spawn_rabbit
+                .after(spawn_drone)
+                .run_if(|| has_flag("--chase")),
+        )
+        .add_systems(
+            Update,
+            (
+                exit_on_escape,
+                control_camera_mouse,
+                (
+                    toggle_camera_heading_lock,
+                    select_camera_preset,
+                    ease_camera_to_preset,
+                )
+                    .chain(),
+                update_engine_ui,
+                adjust_ui_scale,
+                (cycle_active_drone, highlight_active_drone).chain(),
+                update_output_y_text,
+                update_target_y_text,
+                update_output_pitch_text,
+                update_target_pitch_text,
+                update_output_roll_text,
+                update_target_roll_text,
+                update_output_yaw_text,
+                update_target_yaw_text,
+                update_camera_pos,
+                update_wind_text,
+                log_collisions,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                detect_gates,
+                track_race,
+                reset_race,
+                update_next_gate_text,
+                update_lap_time_text,
+                update_best_lap_text,
+                update_penalty_text,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                move_rabbit,
+                toggle_chase,
+                toggle_tuning_panel,
+                tilt_rotor_control,
+                update_tilt_text,
+            ),
+        )
+        .add_systems(EguiPrimaryContextPass, tuning_ui)
+        .add_plugins(replay_plugin)
+        .add_plugins(fpv_plugin)
+        .add_plugins(sticks_plugin)
+        .add_plugins(stats_plugin)
+        .add_plugins(windup_demo_plugin)
+        .add_plugins(disturbance_plugin)
+        .add_plugins(telemetry_plugin)
+        .add_plugins(compare_plugin)
+        .add_plugins(prediction_plugin)
+        .add_plugins(gamepad_plugin)
+        .add_plugins(failsafe_plugin)
+        .add_plugins(drone_visuals_plugin)
+        .add_plugins(altitude_plugin)
+        .add_plugins(flythrough_plugin)
+        .add_plugins(validation_plugin)
+        .add_plugins(energy_plugin)
+        .add_plugins(wheel_plugin)
+        .add_plugins(gimbal_plugin)
+        .add_plugins(vibration_plugin)
+        .add_plugins(orbit_plugin)
+        .add_plugins(wind_hold_demo_plugin)
+        .add_plugins(sysid_plugin)
+        .add_plugins(scene_plugin)
+        .add_plugins(ghost_plugin)
+        .add_plugins(mission_plugin)
+        .add_plugins(gust_plugin)
+        .add_plugins(telemetry_server_plugin)
+        .add_plugins(perch_plugin)
+        .add_plugins(coordinated_turn_plugin)
+        .add_plugins(banner_plugin)
+        .add_plugins(freeze_plugin)
+        .add_plugins(gamepad_calibration_plugin)
+        .add_plugins(tether_plugin)
+        .add_plugins(event_log_plugin)
+        .add_plugins(follow_mouse_plugin)
+        .add_plugins(profile_plugin)
+        .add_plugins(landing_plugin)
+        .add_plugins(altitude_floor_plugin)
+        .add_plugins(return_home_plugin)
+        .add_plugins(trim_plugin)
+        .add_plugins(pid_debug_plugin)
+        .add_plugins(wind_estimate_plugin)
+        .add_plugins(envelope_plugin)
+        .add_plugins(measure_plugin)
+        .add_plugins(yaw_to_velocity_plugin);
+    app
+}
+
+pub fn has_flag(name: &str) -> bool {
+    std::env::args().any(|arg| arg == name)
+}
+
+pub fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args();
+    args.find(|arg| arg == name)?;
+    args.next()
+}
+
+pub fn spawn_floor(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn((
+            Mesh3d(meshes.add(Cuboid::new(500., 0.1, 500.))),
+            MeshMaterial3d(materials.add(Color::WHITE)),
+            Transform::from_xyz(0.0, 0.0, 0.0),
+            Name::new("Floor"),
+        ))
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(500. / 2., 0.1 / 2.0, 500. / 2.));
+}
+
+/// Spawns `--drones <n>` drones (1 by default) in a row to the left of the
+/// first one, each with its own controllers. The first starts out active.
+pub fn spawn_drone(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    catch: Res<CatchScenario>,
+) {
+    let count = arg_value("--drones")
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_DRONES);
+
+    for index in 0..count {
+        let drone_entity = build_drone(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            index,
+            Vec3::new(-DRONE_SPACING * index as f32, catch.spawn_height(3.0), 0.0),
+        );
+        if index == 0 {
+            commands.entity(drone_entity).insert(ActiveDrone);
+        }
+    }
+}
+
+/// Collision group of every collider belonging to a drone.
+pub const DRONE_GROUP: Group = Group::GROUP_2;
+
+/// Drones always collide with the world, but with each other only when run
+/// with `--drone-collisions`, so formations can be flown without the drones
+/// knocking each other about.
+pub fn drone_collision_groups() -> CollisionGroups {
+    let filter = if has_flag("--drone-collisions") {
+        Group::ALL
+    } else {
+        Group::ALL.difference(DRONE_GROUP)
+    };
+    CollisionGroups::new(DRONE_GROUP, filter)
+}
+
+/// Surface properties of every drone collider, so a drone scraping along a
+/// wall is dragged round by the friction at the contact instead of sliding
+/// off, and bounces as much as `restitution` says. The contact forces act on
+/// the body like any other, for the controllers to fight. Set with
+/// `--drone-friction` (0.5), `--drone-restitution` (0) and
+/// `--drone-friction-combine` (`average`, `min`, `max` or `multiply`, how the
+/// drone's friction is combined with the surface it touches).
+#[derive(Debug, Clone, Copy)]
+pub struct DronePhysics {
+    pub friction: Friction,
+    pub restitution: Restitution,
+}
+
+impl DronePhysics {
+    pub fn from_args() -> Self {
+        let coefficient = |name: &str, default: f32| {
+            arg_value(name)
+                .and_then(|value| value.parse::<f32>().ok())
+                .unwrap_or(default)
+                .max(0.0)
+        };
+        let combine_rule = match arg_value("--drone-friction-combine").as_deref() {
+            None | Some("average") => CoefficientCombineRule::Average,
+            Some("min") => CoefficientCombineRule::Min,
+            Some("max") => CoefficientCombineRule::Max,
+            Some("multiply") => CoefficientCombineRule::Multiply,
+            Some(other) => {
+                warn!(
+                    "Unknown --drone-friction-combine {other}, expected average, min, max or multiply"
+                );
+                CoefficientCombineRule::Average
+            }
+        };
+
+        Self {
+            friction: Friction {
+                coefficient: coefficient("--drone-friction", 0.5),
+                combine_rule,
+            },
+            restitution: Restitution::coefficient(coefficient("--drone-restitution", 0.0).min(1.0)),
+        }
+    }
+
+    pub fn bundle(self) -> impl Bundle {
+        (self.friction, self.restitution)
+    }
+}
+
+/// Feedforward gains (altitude, pitch, roll, yaw) from `--feedforward`,
+/// either one gain for every axis or four separated by commas.
+fn feedforward_gains() -> [f32; 4] {
+    let Some(value) = arg_value("--feedforward") else {
+        return [0.0; 4];
+    };
+    let gains = value
+        .split(',')
+        .map(|gain| gain.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<_>>>();
+    match gains.as_deref() {
+        Some(&[gain]) => [gain; 4],
+        Some(&[y, pitch, roll, yaw]) => [y, pitch, roll, yaw],
+        _ => {
+            warn!("Unknown --feedforward {value}, expected one gain or four as y,pitch,roll,yaw");
+            [0.0; 4]
+        }
+    }
+}
+
+/// Spawns a drone with its controllers and propellers. `index` picks its
+/// palette color and name, so additional drones stay distinguishable.
+pub fn build_drone(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    index: usize,
+    translation: Vec3,
+) -> Entity {
+    let anti_windup = AntiWindup::from_args();
+    let physics = DronePhysics::from_args();
+    let [ff_y, ff_pitch, ff_roll, ff_yaw] = feedforward_gains();
+    let inverted = arg_value("--invert-output").unwrap_or_default();
+    let output_sign = |axis: &str| {
+        if inverted.split(',').any(|name| name.trim() == axis) {
+            -1.0
+        } else {
+            1.0
+        }
+    };
+    let ceiling_softness = arg_value("--ceiling-softness")
+        .and_then(|softness| softness.parse::<f32>().ok())
+        .unwrap_or(0.0)
+        .max(0.0);
+    let max_tilt_factor = arg_value("--tilt-comp-max")
+        .and_then(|factor| factor.parse::<f32>().ok())
+        .unwrap_or(1.5)
+        .max(1.0);
+    let center_rate = arg_value("--center-rate")
+        .and_then(|rate| rate.parse::<f32>().ok())
+        .unwrap_or(600.0)
+        .max(0.0)
+        .to_radians();
+    let drone_color = DroneColor::for_index(index);
+    let name = match index {
+        0 => "Drone".to_string(),
+        _ => format!("Drone {}", index + 1),
+    };
+
+    let drone_entity = commands
+        .spawn((
+            Drone,
+            DroneId(index + 1),
+            Name::new(name),
+            Mesh3d(meshes.add(Cuboid::new(0.5, 0.1, 0.5))),
+            MeshMaterial3d(materials.add(drone_color.color)),
+            Transform::from_translation(translation),
+            RigidBody::Dynamic,
+            Collider::cuboid(0.5 / 2.0, 0.1 / 2.0, 0.5 / 2.0),
+            GravityScale(1.0),
+            ExternalForce::default(),
+            ExternalImpulse::default(),
+            ColliderMassProperties::Density(1.0),
+            ActiveEvents::COLLISION_EVENTS,
+            ReadMassProperties::default(),
+            Velocity::zero(),
+        ))
+        .insert(drone_collision_groups())
+        .insert(physics.bundle())
+        .insert(drone_color)
+        .insert(HoverPid {
+            kp: 3.0,
+            min_kp: 0.6,
+            max_kp: 6.0,
+            ki: 0.23,
+            kd: 1.09,
+            prev_e: 0.0,
+            integral_e: 0.0,
+            anti_windup,
+            integral_max: 5.0,
+            integral_max_frac: 0.5,
+            feedforward_gain: ff_y,
+            prev_target: 0.0,
+            target_y: 0.0,
+            v_rate: 2.0,
+            min_y: 0.0,
+            max_y: 120.0,
+            ceiling_softness,
+            tilt_compensation: has_flag("--tilt-comp"),
+            max_tilt_factor,
+            smoothing: false,
+            smoothing_tau: 0.1,
+            output_sign: output_sign("hover"),
+            output: 0.0,
+        })
+        .insert(PitchPid {
+            kp: 5.0,
+            min_kp: 2.0,
+            max_kp: 6.0,
+            ki: 0.1,
+            kd: 1.2,
+            prev_e: 0.0,
+            integral_e: 0.0,
+            anti_windup,
+            integral_max: 0.5,
+            integral_max_frac: 0.5,
+            feedforward_gain: ff_pitch,
+            prev_target: 0.0,
+            target_angle: 0.0 * PI / 180.0,
+            angle_rate: 5.0 * PI / 180.0,
+            min_angle: -30.0 * PI / 180.0,
+            max_angle: 30.0 * PI / 180.0,
+            center_rate,
+            centering: false,
+            smoothing: false,
+            smoothing_tau: 0.1,
+            output_sign: output_sign("pitch"),
+            output: 0.0,
+        })
+        .insert(RollPid {
+            kp: 5.0,
+            min_kp: 2.0,
+            max_kp: 6.0,
+            ki: 0.1,
+            kd: 1.2,
+            prev_e: 0.0,
+            integral_e: 0.0,
+            anti_windup,
+            integral_max: 0.5,
+            integral_max_frac: 0.5,
+            feedforward_gain: ff_roll,
+            prev_target: 0.0,
+            target_angle: 0.0 * PI / 180.0,
+            angle_rate: 5.0 * PI / 180.0,
+            min_angle: -30.0 * PI / 180.0,
+            max_angle: 30.0 * PI / 180.0,
+            center_rate,
+            centering: false,
+            smoothing: false,
+            smoothing_tau: 0.1,
+            output_sign: output_sign("roll"),
+            output: 0.0,
+        })
+        .insert(YawPid {
+            kp: 5.0,
+            min_kp: 2.0,
+            max_kp: 6.0,
+            ki: 0.1,
+            kd: 1.2,
+            prev_e: 0.0,
+            integral_e: 0.0,
+            anti_windup,
+            integral_max: 0.5,
+            integral_max_frac: 0.5,
+            feedforward_gain: ff_yaw,
+            prev_target: 0.0,
+            target_angle: 0.0 * PI / 180.0,
+            angle_rate: 5.0 * PI / 180.0,
+            min_angle: -PI * 2.0,
+            max_angle: PI * 2.0,
+            smoothing: false,
+            smoothing_tau: 0.1,
+            output_sign: output_sign("yaw"),
+            output: 0.0,
+        })
+        .insert(PositionPid {
+            kp: 1.0,
+            ki: 0.05,
+            kd: 1.5,
+            prev_e: Vec2::ZERO,
+            integral_e: Vec2::ZERO,
+            target: Vec3::new(0.0, 3.0, 0.0),
+            enabled: false,
+            handover: Vec2::ZERO,
+            handover_left: 0.0,
+            accel_feedforward: Vec2::ZERO,
+        })
+        .insert(Home(translation))
+        .insert(RotorLag {
+            yaw_tau: arg_value("--yaw-lag")
+                .and_then(|tau| tau.parse::<f32>().ok())
+                .unwrap_or(0.1)
+                .max(0.0),
+            yaw_torque: 0.0,
+        })
+        .insert(LimitedAttitude::default())
+        .id();
+
+    if has_flag("--tilt-rotor") {
+        commands.entity(drone_entity).insert(TiltRotor {
+            tilt_angle: 0.0,
+            tilt_rate: 20.0 * PI / 180.0,
+            min_tilt: -45.0 * PI / 180.0,
+            max_tilt: 45.0 * PI / 180.0,
+        });
+    }
+
+    // One-DOF thrust stand for tuning the hover controller in isolation
+    match arg_value("--rig").as_deref() {
+        None => {}
+        Some("vertical") => {
+            commands.entity(drone_entity).insert(
+                LockedAxes::TRANSLATION_LOCKED_X
+                    | LockedAxes::TRANSLATION_LOCKED_Z
+                    | LockedAxes::ROTATION_LOCKED,
+            );
+        }
+        Some(other) => warn!("Unknown --rig {other}, expected vertical"),
+    }
+
+    for (i, offset) in PROPELLER_OFFSETS.iter().enumerate() {
+        let joint = FixedJointBuilder::new()
+            .local_anchor1(*offset)
+            .local_anchor2(Vec3::ZERO);
+
+        commands.spawn((
+            Propeller(i),
+            Name::new(format!("Propeller {i}")),
+            Mesh3d(meshes.add(Cylinder::new(0.2, 0.01))),
+            MeshMaterial3d(materials.add(Color::srgb_u8(200, 200, 200))),
+            Transform::from_translation(translation + *offset),
+            RigidBody::Dynamic,
+            Collider::cylinder(0.01 / 2.0, 0.2),
+            drone_collision_groups(),
+            physics.bundle(),
+            ColliderMassProperties::Density(0.001),
+            ActiveEvents::COLLISION_EVENTS,
+            ImpulseJoint::new(drone_entity, joint),
+        ));
+    }
+
+    drone_entity
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn update_drone_forces(
+    time: Res<Time<Fixed>>,
+    engine_state: Res<State<EngineState>>,
+    failure_mode: Res<FailureMode>,
+    attitude_mode: Res<AttitudeMode>,
+    mixer: Res<Mixer>,
+    vortex_ring_model: Res<VortexRingModel>,
+    gyro: Res<GyroRange>,
+    mut drone_query: Query<
+        (
+            (&Transform, &Velocity),
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+            &mut YawPid,
+            &ReadMassProperties,
+            &mut ExternalForce,
+            Option<&TiltRotor>,
+            Option<&mut RotorLag>,
+            Option<&AltitudeEstimate>,
+            Option<&VortexRing>,
+            Option<&mut GyroSaturation>,
+            Option<&mut PidTerms>,
+            Option<&Battery>,
+            Has<Disarmed>,
+        ),
+        With<Drone>,
+    >,
+) {
+    let dt = time.delta_secs();
+    // Paused (freeze frame); the derivative terms would divide by zero
+    if dt <= 0.0 {
+        return;
+    }
+
+    for (
+        (tf, velocity),
+        mut ctl_y,
+        mut ctl_pitch,
+        mut ctl_roll,
+        mut ctl_yaw,
+        mass_props,
+        mut ext_force,
+        tilt_rotor,
+        mut rotor_lag,
+        altitude,
+        vortex_ring,
+        gyro_saturation,
+        pid_terms,
+        battery,
+        disarmed,
+    ) in drone_query.iter_mut()
+    {
+        // Setpoint velocities for feedforward
+        let target_y = ctl_y.soft_target();
+        let v_y = (target_y - ctl_y.prev_target) / dt;
+        ctl_y.prev_target = target_y;
+        let v_pitch = angle_error(ctl_pitch.target_angle, ctl_pitch.prev_target) / dt;
+        ctl_pitch.prev_target = ctl_pitch.target_angle;
+        let v_roll = angle_error(ctl_roll.target_angle, ctl_roll.prev_target) / dt;
+        ctl_roll.prev_target = ctl_roll.target_angle;
+        let v_yaw = angle_error(ctl_yaw.target_angle, ctl_yaw.prev_target) / dt;
+        ctl_yaw.prev_target = ctl_yaw.target_angle;
+
+        let engine_on = *engine_state.get() == EngineState::On && !disarmed;
+
+        // Deadstick: with the engine out only the attitude controllers keep running
+        if !engine_on && (*failure_mode == FailureMode::Cut || !is_airborne(tf)) {
+            ext_force.force = Vec3::ZERO;
+            ext_force.torque = Vec3::ZERO;
+            if let Some(lag) = rotor_lag.as_mut() {
+                lag.yaw_torque = 0.0;
+            }
+            continue;
+        }
+
+        // Tilt-rotors vector the thrust in the body frame instead of along body up
+        let thrust_dir = tilt_rotor.map_or(Vec3::Y, TiltRotor::thrust_dir);
+
+        // === Hover PID ===
+        let (thrust_hover, hover_terms) = if engine_on {
+            let y = altitude.map_or(tf.translation.y, |altitude| altitude.fused);
+            let e_y = target_y - y;
+            ctl_y.integral_e += e_y * dt;
+
+            let norm_y = (y / ctl_y.max_y).clamp(0.0, 1.0);
+            ctl_y.kp = ctl_y.min_kp + (ctl_y.max_kp - ctl_y.min_kp) * norm_y;
+
+            let p_y = ctl_y.kp * e_y;
+            let d_y = ctl_y.kd * (e_y - ctl_y.prev_e) / dt;
+            let ff_y = ctl_y.feedforward_gain * v_y;
+            let i_y = integral_term(
+                ctl_y.anti_windup,
+                ctl_y.ki,
+                ctl_y.integral_max,
+                ctl_y.integral_max_frac,
+                p_y + d_y,
+                &mut ctl_y.integral_e,
+            );
+            let a_y = p_y + i_y + d_y + ff_y;
+            ctl_y.prev_e = e_y;
+
+            // Only the vertical part of the thrust holds the drone up. Upside
+            // down more thrust would only push it down, so leave it alone.
+            let lift = (tf.rotation * thrust_dir).y;
+            let tilt_factor = if ctl_y.tilt_compensation && lift > 0.0 {
+                (1.0 / lift).min(ctl_y.max_tilt_factor)
+            } else {
+                1.0
+            };
+
+            (
+                mass_props.mass * (ctl_y.output_sign * a_y + GRAVITY) * tilt_factor,
+                AxisTerms::new(y, target_y, e_y, p_y, i_y, d_y, ff_y),
+            )
+        } else {
+            (0.0, AxisTerms::default())
+        };
+
+        // === Orientation PID ===
+        let (e_pitch, e_roll, e_yaw) = attitude_mode.errors(
+            tf.rotation,
+            ctl_pitch.target_angle,
+            ctl_roll.target_angle,
+            ctl_yaw.target_angle,
+        );
+
+        // The D terms act on the rate of change of the error: the setpoint's
+        // rate less the body rate the gyro reads, clipped at its range
+        let body_rate = tf.rotation.inverse() * velocity.angvel;
+        let (rate_pitch, sat_pitch) = gyro.measure(body_rate.x);
+        let (rate_roll, sat_roll) = gyro.measure(body_rate.z);
+        let (rate_yaw, sat_yaw) = gyro.measure(body_rate.y);
+
+        ctl_pitch.integral_e += e_pitch * dt;
+
+        let p_pitch = ctl_pitch.kp * e_pitch;
+        let d_pitch = ctl_pitch.kd * (v_pitch - rate_pitch);
+        let ff_pitch = ctl_pitch.feedforward_gain * v_pitch;
+        let i_pitch = integral_term(
+            ctl_pitch.anti_windup,
+            ctl_pitch.ki,
+            ctl_pitch.integral_max,
+            ctl_pitch.integral_max_frac,
+            p_pitch + d_pitch,
+            &mut ctl_pitch.integral_e,
+        );
+        let alpha_pitch = p_pitch + i_pitch + d_pitch + ff_pitch;
+        ctl_pitch.prev_e = e_pitch;
+
+        let torque_x = mass_props.principal_inertia.x * ctl_pitch.output_sign * alpha_pitch;
+
+        ctl_roll.integral_e += e_roll * dt;
+
+        let p_roll = ctl_roll.kp * e_roll;
+        let d_roll = ctl_roll.kd * (v_roll - rate_roll);
+        let ff_roll = ctl_roll.feedforward_gain * v_roll;
+        let i_roll = integral_term(
+            ctl_roll.anti_windup,
+            ctl_roll.ki,
+            ctl_roll.integral_max,
+            ctl_roll.integral_max_frac,
+            p_roll + d_roll,
+            &mut ctl_roll.integral_e,
+        );
+        let alpha_roll = p_roll + i_roll + d_roll + ff_roll;
+        ctl_roll.prev_e = e_roll;
+
+        let torque_z = mass_props.principal_inertia.z * ctl_roll.output_sign * alpha_roll;
+
+        ctl_yaw.integral_e += e_yaw * dt;
+
+        let p_yaw = ctl_yaw.kp * e_yaw;
+        let d_yaw = ctl_yaw.kd * (v_yaw - rate_yaw);
+        let ff_yaw = ctl_yaw.feedforward_gain * v_yaw;
+        let i_yaw = integral_term(
+            ctl_yaw.anti_windup,
+            ctl_yaw.ki,
+            ctl_yaw.integral_max,
+            ctl_yaw.integral_max_frac,
+            p_yaw + d_yaw,
+            &mut ctl_yaw.integral_e,
+        );
+        let alpha_yaw = p_yaw + i_yaw + d_yaw + ff_yaw;
+        ctl_yaw.prev_e = e_yaw;
+
+        let torque_y = mass_props.principal_inertia.y * ctl_yaw.output_sign * alpha_yaw;
+
+        if let Some(mut saturation) = gyro_saturation {
+            saturation.record(sat_pitch || sat_roll || sat_yaw, dt);
+        }
+        if let Some(mut terms) = pid_terms {
+            let (yaw, pitch, roll) = tf.rotation.to_euler(EulerRot::YXZ);
+            *terms = PidTerms {
+                hover: hover_terms,
+                pitch: AxisTerms::new(
+                    pitch,
+                    ctl_pitch.target_angle,
+                    e_pitch,
+                    p_pitch,
+                    i_pitch,
+                    d_pitch,
+                    ff_pitch,
+                ),
+                roll: AxisTerms::new(
+                    roll,
+                    ctl_roll.target_angle,
+                    e_roll,
+                    p_roll,
+                    i_roll,
+                    d_roll,
+                    ff_roll,
+                ),
+                yaw: AxisTerms::new(
+                    yaw,
+                    ctl_yaw.target_angle,
+                    e_yaw,
+                    p_yaw,
+                    i_yaw,
+                    d_yaw,
+                    ff_yaw,
+                ),
+            };
+        }
+
+        // === Output smoothing ===
+        ctl_y.output = if engine_on {
+            smooth_output(
+                ctl_y.smoothing,
+                ctl_y.output,
+                thrust_hover,
+                ctl_y.smoothing_tau,
+                dt,
+            )
+        } else {
+            0.0
+        };
+        ctl_pitch.output = smooth_output(
+            ctl_pitch.smoothing,
+            ctl_pitch.output,
+            torque_x,
+            ctl_pitch.smoothing_tau,
+            dt,
+        );
+        ctl_roll.output = smooth_output(
+            ctl_roll.smoothing,
+            ctl_roll.output,
+            torque_z,
+            ctl_roll.smoothing_tau,
+            dt,
+        );
+        ctl_yaw.output = smooth_output(
+            ctl_yaw.smoothing,
+            ctl_yaw.output,
+            torque_y,
+            ctl_yaw.smoothing_tau,
+            dt,
+        );
+
+        // === Rotor spin-up lag ===
+        let yaw_torque = match rotor_lag.as_mut() {
+            Some(lag) => {
+                lag.yaw_torque =
+                    smooth_output(true, lag.yaw_torque, ctl_yaw.output, lag.yaw_tau, dt);
+                lag.yaw_torque
+            }
+            None => ctl_yaw.output,
+        };
+
+        // === Mixer ===
+        let torque = Vec3::new(ctl_pitch.output, yaw_torque, ctl_roll.output);
+        let (thrust, torque) = if engine_on {
+            let weight = mass_props.mass * GRAVITY;
+            // A sagging battery can't deliver what the hover controller asks for
+            let hover = battery.map_or(ctl_y.output, |battery| {
+                ctl_y.output.min(battery.thrust_limit(weight))
+            });
+            let (thrust, mixed) = mixer.mix_world(weight, tf.rotation, hover, torque);
+
+            // Conditional integration: take back this step's error on the
+            // axes the mixer clipped, if it winds them further into the limit.
+            // The rotors clip in the body frame.
+            let tolerance = 1e-4 * weight;
+            let clipped = tf.rotation.inverse() * (mixed - torque);
+            unwind_clipped(
+                ctl_y.anti_windup,
+                (thrust - ctl_y.output) * ctl_y.output_sign,
+                tolerance,
+                hover_terms.error * dt,
+                &mut ctl_y.integral_e,
+            );
+            unwind_clipped(
+                ctl_pitch.anti_windup,
+                clipped.x * ctl_pitch.output_sign,
+                tolerance,
+                e_pitch * dt,
+                &mut ctl_pitch.integral_e,
+            );
+            unwind_clipped(
+                ctl_yaw.anti_windup,
+                clipped.y * ctl_yaw.output_sign,
+                tolerance,
+                e_yaw * dt,
+                &mut ctl_yaw.integral_e,
+            );
+            unwind_clipped(
+                ctl_roll.anti_windup,
+                clipped.z * ctl_roll.output_sign,
+                tolerance,
+                e_roll * dt,
+                &mut ctl_roll.integral_e,
+            );
+            (thrust, mixed)
+        } else {
+            (ctl_y.output, torque)
+        };
+        // Lift lost to the rotors' own downwash in a fast descent
+        let thrust =
+            thrust * vortex_ring.map_or(1.0, |ring| ring.thrust_factor(&vortex_ring_model));
+
+        ext_force.force = tf.rotation * thrust_dir * thrust;
+        ext_force.torque = torque;
+    }
+}
+
+/// First-order low-pass on a controller output, or a pass-through when disabled.
+#[inline]
+fn smooth_output(enabled: bool, prev: f32, raw: f32, tau: f32, dt: f32) -> f32 {
+    if !enabled || tau <= 0.0 {
+        return raw;
+    }
+
+    prev + (raw - prev) * (1.0 - (-dt / tau).exp())
+}
+
+/// Integral contribution `ki * integral_e`, limited according to `mode`.
+/// `pd` is the proportional + derivative output the fraction is taken of.
+fn integral_term(
+    mode: AntiWindup,
+    ki: f32,
+    integral_max: f32,
+    integral_max_frac: f32,
+    pd: f32,
+    integral_e: &mut f32,
+) -> f32 {
+    match mode {
+        AntiWindup::Off => ki * *integral_e,
+        AntiWindup::Clamp | AntiWindup::Conditional => {
+            *integral_e = integral_e.clamp(-integral_max, integral_max);
+            ki * *integral_e
+        }
+        AntiWindup::OutputFraction => {
+            let limit = integral_max_frac * pd.abs();
+            (ki * *integral_e).clamp(-limit, limit)
+        }
+    }
+}
+
+/// Undoes `step` (the error just added to `integral_e`) in `Conditional`
+/// mode when the output was `clipped` by more than `tolerance` against the
+/// direction the step pushes it. `clipped` is in the error's sign convention,
+/// i.e. already multiplied by the controller's `output_sign`.
+fn unwind_clipped(mode: AntiWindup, clipped: f32, tolerance: f32, step: f32, integral_e: &mut f32) {
+    if mode == AntiWindup::Conditional && clipped.abs() > tolerance && clipped * step < 0.0 {
+        *integral_e -= step;
+    }
+}
+
+#[inline]
+pub fn is_airborne(tf: &Transform) -> bool {
+    tf.translation.y > AIRBORNE_MIN_Y
+}
+
+#[inline]
+fn angle_error(target: f32, current: f32) -> f32 {
+    let angle = target - current;
+
+    let y = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if y == -PI { PI } else { y }
+}
+
+pub fn manual_control(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut drone_query: Query<(&mut HoverPid, &mut PitchPid, &mut RollPid, &mut YawPid), Piloted>,
+    mut next_engine_state: ResMut<NextState<EngineState>>,
+    engine_state: Res<State<EngineState>>,
+    failure_mode: Res<FailureMode>,
+    mut delay: ResMut<Delay>,
+    time: Res<Time>,
+) {
+    // Attitude stays steerable with the engine out so deadstick glides can be flown
+    let attitude_enabled =
+        *engine_state.get() == EngineState::On || *failure_mode == FailureMode::Deadstick;
+
+    for (mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        if keyboard.just_pressed(KeyCode::KeyP) {
+            if *engine_state.get() == EngineState::On {
+                next_engine_state.set(EngineState::Off);
+            } else {
+                next_engine_state.set(EngineState::On);
+            }
+        }
+
+        if keyboard.pressed(KeyCode::Space) {
+            delay.timer.tick(time.delta());
+
+            if delay.timer.just_finished() && *engine_state.get() == EngineState::On {
+                ctl_y.target_y += ctl_y.v_rate;
+            }
+        }
+        if keyboard.pressed(KeyCode::ControlLeft) {
+            delay.timer.tick(time.delta());
+
+            if delay.timer.just_finished() && *engine_state.get() == EngineState::On {
+                ctl_y.target_y -= ctl_y.v_rate;
+            }
+        }
+
+        if keyboard.pressed(KeyCode::KeyW) {
+            ctl_pitch.centering = false;
+            delay.timer.tick(time.delta());
+
+            if delay.timer.just_finished() && attitude_enabled {
+                ctl_pitch.target_angle -= ctl_pitch.angle_rate;
+            }
+        }
+        if keyboard.just_released(KeyCode::KeyW) {
+            ctl_pitch.centering = true;
+        }
+
+        if keyboard.pressed(KeyCode::KeyS) {
+            ctl_pitch.centering = false;
+            delay.timer.tick(time.delta());
+
+            if delay.timer.just_finished() && attitude_enabled {
+                ctl_pitch.target_angle += ctl_pitch.angle_rate;
+            }
+        }
+        if keyboard.just_released(KeyCode::KeyS) {
+            ctl_pitch.centering = true;
+        }
+
+        if keyboard.pressed(KeyCode::KeyD) {
+            ctl_roll.centering = false;
+            delay.timer.tick(time.delta());
+
+            if delay.timer.just_finished() && attitude_enabled {
+                ctl_roll.target_angle -= ctl_roll.angle_rate;
+            }
+        }
+        if keyboard.just_released(KeyCode::KeyD) {
+            ctl_roll.centering = true;
+        }
+
+        if keyboard.pressed(KeyCode::KeyA) {
+            ctl_roll.centering = false;
+            delay.timer.tick(time.delta());
+
+            if delay.timer.just_finished() && attitude_enabled {
+                ctl_roll.target_angle += ctl_roll.angle_rate;
+            }
+        }
+        if keyboard.just_released(KeyCode::KeyA) {
+            ctl_roll.centering = true;
+        }
+
+        if keyboard.pressed(KeyCode::KeyQ) {
+            delay.timer.tick(time.delta());
+
+            if delay.timer.just_finished() && attitude_enabled {
+                ctl_yaw.target_angle -= ctl_yaw.angle_rate;
+            }
+        }
+
+        if keyboard.pressed(KeyCode::KeyE) {
+            delay.timer.tick(time.delta());
+
+            if delay.timer.just_finished() && attitude_enabled {
+                ctl_yaw.target_angle += ctl_yaw.angle_rate;
+            }
+        }
+
+        if keyboard.just_pressed(KeyCode::KeyR) && *engine_state.get() == EngineState::Off {
+            ctl_y.target_y = 0.0;
+            ctl_pitch.target_angle = 0.0;
+            ctl_roll.target_angle = 0.0;
+            ctl_yaw.target_angle = 0.0;
+        }
+    }
+}
+
+/// Eases released pitch/roll setpoints back to level at their center rate.
+pub fn center_setpoints(
+    time: Res<Time>,
+    mut drone_query: Query<(&mut PitchPid, &mut RollPid), With<Drone>>,
+) {
+    let dt = time.delta_secs();
+
+    for (mut ctl_pitch, mut ctl_roll) in drone_query.iter_mut() {
+        if ctl_pitch.centering {
+            let step = ctl_pitch.center_rate * dt;
+            ctl_pitch.target_angle -= ctl_pitch.target_angle.clamp(-step, step);
+            ctl_pitch.centering = ctl_pitch.target_angle != 0.0;
+        }
+        if ctl_roll.centering {
+            let step = ctl_roll.center_rate * dt;
+            ctl_roll.target_angle -= ctl_roll.target_angle.clamp(-step, step);
+            ctl_roll.centering = ctl_roll.target_angle != 0.0;
+        }
+    }
+}
+
+/// Enforces the setpoint limits stored on the controllers after every input
+/// source (keyboard, position hold, chase, scripted demos) has had its say.
+pub fn clamp_setpoints(
+    mut drone_query: Query<(&mut HoverPid, &mut PitchPid, &mut RollPid, &mut YawPid), With<Drone>>,
+) {
+    for (mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        // Prevent going below ground level or exceeding the maximum height
+        ctl_y.target_y = ctl_y.target_y.clamp(ctl_y.min_y, ctl_y.max_y);
+        ctl_pitch.target_angle = ctl_pitch
+            .target_angle
+            .clamp(ctl_pitch.min_angle, ctl_pitch.max_angle);
+        ctl_roll.target_angle = ctl_roll
+            .target_angle
+            .clamp(ctl_roll.min_angle, ctl_roll.max_angle);
+
+        // Yaw is a heading, so wrap it back into range instead of stopping the turn
+        if !(ctl_yaw.min_angle..=ctl_yaw.max_angle).contains(&ctl_yaw.target_angle) {
+            ctl_yaw.target_angle = angle_error(ctl_yaw.target_angle, 0.0);
+        }
+    }
+}
+
+/// Moves each attitude setpoint towards its commanded value no faster than
+/// the axis's rate limit, stepping from the setpoint it let through last frame.
+#[allow(clippy::type_complexity)]
+pub fn limit_attitude_rate(
+    time: Res<Time>,
+    limit: Res<AttitudeRateLimit>,
+    mut drone_query: Query<
+        (
+            &mut PitchPid,
+            &mut RollPid,
+            &mut YawPid,
+            &mut LimitedAttitude,
+        ),
+        With<Drone>,
+    >,
+) {
+    let dt = time.delta_secs();
+    let step = |rate: Option<f32>, target: f32, prev: f32| match rate {
+        Some(rate) => prev + angle_error(target, prev).clamp(-rate * dt, rate * dt),
+        None => target,
+    };
+
+    for (mut ctl_pitch, mut ctl_roll, mut ctl_yaw, mut limited) in drone_query.iter_mut() {
+        if let Some(prev) = limited.0 {
+            ctl_pitch.target_angle = step(limit.pitch, ctl_pitch.target_angle, prev.x);
+            ctl_roll.target_angle = step(limit.roll, ctl_roll.target_angle, prev.y);
+            if limit.yaw.is_some() {
+                let yaw = step(limit.yaw, ctl_yaw.target_angle, prev.z);
+                ctl_yaw.target_angle = angle_error(yaw, 0.0);
+            }
+        }
+        limited.0 = Some(Vec3::new(
+            ctl_pitch.target_angle,
+            ctl_roll.target_angle,
+            ctl_yaw.target_angle,
+        ));
+    }
+}
+
+pub fn exit_on_escape(keyboard: Res<ButtonInput<KeyCode>>, mut exit: EventWriter<AppExit>) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        // Exit through AppExit so exit hooks (e.g. tracking stats) still run
+        exit.write(AppExit::Success);
+    }
+}
+
+pub fn debug_print(
+    mut debug: ResMut<DebugPrint>,
+    time: Res<Time>,
+    drone_query: Query<(&HoverPid, &ExternalForce), With<Drone>>,
+) {
+    if !debug.enabled {
+        return;
+    }
+
+    debug.timer.tick(time.delta());
+    if !debug.timer.just_finished() {
+        return;
+    }
+
+    for (ctl_y, ext_force) in drone_query.iter() {
+        // prev_e holds the altitude error from the latest controller update
+        println!(
+            "[{:>8.2}s] e_y: {:>7.3} m | thrust: {:>8.3} N | torque: ({:>7.3}, {:>7.3}, {:>7.3}) N·m",
+            time.elapsed_secs(),
+            ctl_y.prev_e,
+            ext_force.force.length(),
+            ext_force.torque.x,
+            ext_force.torque.y,
+            ext_force.torque.z,
+        );
+    }
+}
+
+pub fn engine_off(
+    mut drone_query: Query<&mut ExternalForce, With<Drone>>,
+    engine_state: Res<State<EngineState>>,
+    failure_mode: Res<FailureMode>,
+) {
+    for mut force in drone_query.iter_mut() {
+        if *engine_state.get() == EngineState::Off {
+            force.force = Vec3::ZERO; // Stop the drone when engine is off
+            if *failure_mode == FailureMode::Cut {
+                force.torque = Vec3::ZERO;
+            }
+        }
+    }
+}
+
+/// Setpoints can move while the controllers aren't running (e.g. `R`), so
+/// start from the current ones instead of feeding forward the jump.
+pub fn reset_feedforward(
+    mut drone_query: Query<(&mut HoverPid, &mut PitchPid, &mut RollPid, &mut YawPid), With<Drone>>,
+) {
+    for (mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        ctl_y.prev_target = ctl_y.soft_target();
+        ctl_pitch.prev_target = ctl_pitch.target_angle;
+        ctl_roll.prev_target = ctl_roll.target_angle;
+        ctl_yaw.prev_target = ctl_yaw.target_angle;
+    }
+}
+
+/// Disarms each drone that tumbles, leaving the rest of the swarm flying.
+#[allow(clippy::type_complexity)]
+pub fn tumble_disarm(
+    mut commands: Commands,
+    mut tumble: ResMut<TumbleDisarm>,
+    drone_query: Query<(Entity, &Name, &Transform), (With<Drone>, Without<Disarmed>)>,
+) {
+    if !tumble.enabled {
+        return;
+    }
+
+    for (drone, name, tf) in drone_query.iter() {
+        // Body up is R_y(yaw) R_x(pitch) R_z(roll) * Y, whose vertical part is cos(pitch) cos(roll)
+        let (_, pitch, roll) = tf.rotation.to_euler(EulerRot::YXZ);
+        let tilt = (pitch.cos() * roll.cos()).clamp(-1.0, 1.0).acos();
+
+        if tilt > tumble.threshold {
+            warn!(
+                "{name}: tumble detected at {:.0} deg, disarming",
+                tilt.to_degrees()
+            );
+            tumble.tripped = true;
+            commands.entity(drone).insert(Disarmed);
+        }
+    }
+}
+
+pub fn rearm_tumble_disarm(mut tumble: ResMut<TumbleDisarm>) {
+    tumble.tripped = false;
+}
+
+pub fn rearm_drones(mut commands: Commands, drone_query: Query<Entity, With<Disarmed>>) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).remove::<Disarmed>();
+    }
+}
+
+/// Switches the engine off once no drone is left armed.
+pub fn engine_off_when_disarmed(
+    mut next_engine_state: ResMut<NextState<EngineState>>,
+    drone_query: Query<Has<Disarmed>, With<Drone>>,
+) {
+    if !drone_query.is_empty() && drone_query.iter().all(|disarmed| disarmed) {
+        next_engine_state.set(EngineState::Off);
+    }
+}
+
+pub fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        DroneCamera,
+        Camera3d::default(),
+        Transform::from_xyz(0.0, FOLLOW_DIST, FOLLOW_DIST).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_camera_pos(
+    time: Res<Time>,
+    drone_cam_params: Res<DroneCameraParams>,
+    rapier_context: ReadRapierContext,
+    drone_query: Query<(&Transform, Has<ActiveDrone>), (With<Drone>, Without<DroneCamera>)>,
+    mut cam_query: Query<
+        (&mut Transform, Option<&FollowDrone>),
+        (With<DroneCamera>, Without<Drone>),
+    >,
+) {
+    for (mut cam_trans, follow) in cam_query.iter_mut() {
+        let followed = match follow {
+            Some(follow) => drone_query.get(follow.0).ok(),
+            None => drone_query.iter().find(|(_, active)| *active),
+        };
+
+        if let Some((drone_trans, _)) = followed {
+            let Ok(context) = rapier_context.single() else {
+                continue;
+            };
+
+            let yaw = if drone_cam_params.yaw_follows_drone {
+                // Turn from where the camera is now, so each camera keeps
+                // its own lag behind the drone it follows
+                let (heading, _, _) = drone_trans.rotation.to_euler(EulerRot::YXZ);
+                let target = heading + drone_cam_params.yaw;
+                let offset = cam_trans.translation - drone_trans.translation;
+                let current = offset.x.atan2(offset.z);
+                let blend = match drone_cam_params.heading_smoothing {
+                    tau if tau > 0.0 => 1.0 - (-time.delta_secs() / tau).exp(),
+                    _ => 1.0,
+                };
+                current + angle_error(target, current) * blend
+            } else {
+                drone_cam_params.yaw
+            };
+            let pitch = drone_cam_params.pitch;
+            let radius = drone_cam_params.radius;
+
+            let x = radius * yaw.sin() * pitch.cos();
+            let y = radius * pitch.sin();
+            let z = radius * yaw.cos() * pitch.cos();
+
+            let offset = Vec3::new(x, y, z);
+
+            let drone_view_pos = drone_trans.translation + Vec3::Y;
+
+            let ideal_camera_pos = drone_view_pos + offset;
+            let dir = (ideal_camera_pos - drone_view_pos).normalize();
+            let max_dist = offset.length();
+            let mut final_dist = max_dist;
+
+            if let Some((_entity, toi)) = context.cast_ray(
+                drone_view_pos,
+                dir,
+                max_dist,
+                true,
+                QueryFilter::default().exclude_sensors(),
+            ) {
+                final_dist = toi - 0.1;
+            }
+
+            let actual_camera_pos = drone_view_pos + dir * final_dist;
+            cam_trans.translation = actual_camera_pos;
+            cam_trans.look_at(drone_view_pos, Vec3::Y);
+        }
+    }
+}
+
+pub fn control_camera_mouse(
+    time: Res<Time>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut mouse_events: EventReader<MouseMotion>,
+    mut cam_params: ResMut<DroneCameraParams>,
+) {
+    if mouse_input.pressed(MouseButton::Right) {
+        for event in mouse_events.read() {
+            // Dragging takes over from any preset transition
+            cam_params.preset_target = None;
+            let delta = -event.delta * cam_params.sensitivity;
+            cam_params.pending_motion += delta;
+        }
+    }
+
+    if cam_params.pending_motion == Vec2::ZERO {
+        return;
+    }
+
+    // Ease the accumulated motion in so fast flicks pan fluidly
+    let step = if cam_params.mouse_smoothing > 0.0 {
+        cam_params.pending_motion * (1.0 - (-time.delta_secs() / cam_params.mouse_smoothing).exp())
+    } else {
+        cam_params.pending_motion
+    };
+    cam_params.pending_motion -= step;
+    if cam_params.pending_motion.length_squared() < 1e-10 {
+        cam_params.pending_motion = Vec2::ZERO;
+    }
+
+    cam_params.yaw += step.x;
+    cam_params.pitch += step.y;
+
+    // Limit pitch so it doesn’t flip, and yaw if configured
+    cam_params.clamp_orbit();
+}
+
+pub fn toggle_camera_heading_lock(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut cam_params: ResMut<DroneCameraParams>,
+) {
+    if !keyboard.just_pressed(HEADING_LOCK_KEY) {
+        return;
+    }
+
+    cam_params.yaw_follows_drone = !cam_params.yaw_follows_drone;
+    // Start from straight behind either way; the old yaw meant something else
+    cam_params.yaw = 0.0_f32.clamp(cam_params.min_yaw, cam_params.max_yaw);
+    cam_params.preset_target = None;
+    info!(
+        "Camera heading lock {}",
+        if cam_params.yaw_follows_drone {
+            "on"
+        } else {
+            "off"
+        }
+    );
+}
+
+pub fn select_camera_preset(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut cam_params: ResMut<DroneCameraParams>,
+) {
+    let Some(preset) = cam_params
+        .presets
+        .iter()
+        .find(|preset| keyboard.just_pressed(preset.key))
+    else {
+        return;
+    };
+
+    info!("Camera preset: {}", preset.name);
+    let target = (
+        preset.yaw.clamp(cam_params.min_yaw, cam_params.max_yaw),
+        preset
+            .pitch
+            .clamp(cam_params.min_pitch, cam_params.max_pitch),
+    );
+    cam_params.preset_target = Some(target);
+    cam_params.pending_motion = Vec2::ZERO;
+}
+
+pub fn ease_camera_to_preset(time: Res<Time>, mut cam_params: ResMut<DroneCameraParams>) {
+    let Some((target_yaw, target_pitch)) = cam_params.preset_target else {
+        return;
+    };
+
+    // Unbounded yaw takes the short way round, bounded yaw must stay in range
+    let e_yaw = if cam_params.min_yaw.is_finite() || cam_params.max_yaw.is_finite() {
+        target_yaw - cam_params.yaw
+    } else {
+        angle_error(target_yaw, cam_params.yaw)
+    };
+    let e_pitch = target_pitch - cam_params.pitch;
+
+    let blend = 1.0 - (-CAMERA_PRESET_RATE * time.delta_secs()).exp();
+    cam_params.yaw += e_yaw * blend;
+    cam_params.pitch += e_pitch * blend;
+    cam_params.clamp_orbit();
+
+    if e_yaw.abs() < 1e-3 && e_pitch.abs() < 1e-3 {
+        cam_params.preset_target = None;
+    }
+}
+
+pub fn spawn_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./pixeloid_mono.ttf");
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::SpaceBetween,
+                ..Default::default()
+            },
+            BackgroundColor(Color::NONE),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(hud_column(AlignItems::FlexStart))
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            EngineUI,
+                            text_panel(Color::srgba(0. / 255., 210. / 255., 0. / 255., 1.)),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((EngineText, panel_text(&font, "Engine: On")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((DroneText, panel_text(&font, "Drone: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((OutputYText, panel_text(&font, "Output Y: 0.00 m")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((TargetYText, panel_text(&font, "Target Y: 0.00 m")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((AltitudeText, panel_text(&font, "Fusion: off")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                OutputPitchText,
+                                panel_text(&font, "Output Pitch: 0.00 deg"),
+                            ));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TargetPitchText,
+                                panel_text(&font, "Target Pitch: 0.00 deg"),
+                            ));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                OutputRollText,
+                                panel_text(&font, "Output Roll: 0.00 deg"),
+                            ));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TargetRollText,
+                                panel_text(&font, "Target Roll: 0.00 deg"),
+                            ));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent
+                                .spawn((OutputYawText, panel_text(&font, "Output Yaw: 0.00 deg")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent
+                                .spawn((TargetYawText, panel_text(&font, "Target Yaw: 0.00 deg")));
+                        });
+                });
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(hud_column(AlignItems::FlexEnd))
+                .with_children(|parent| {
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((NextGateText, panel_text(&font, "Next Gate: 1/1")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((LapTimeText, panel_text(&font, "Lap 1: 0.00 s")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((BestLapText, panel_text(&font, "Best Lap: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((PenaltyText, panel_text(&font, "Missed: 0 (+0.0 s)")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((GhostText, panel_text(&font, "Ghost: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((WindText, panel_text(&font, "Wind: 0.0 m/s   0 deg")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((WindEstimateText, panel_text(&font, "Wind est: off")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((TiltText, panel_text(&font, "Tilt: off")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((WindupText, panel_text(&font, "Windup demo: press B")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((DisturbanceText, panel_text(&font, "Poke: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((FailsafeText, panel_text(&font, "Link: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((PilotText, panel_text(&font, "Pilot: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((EnergyText, panel_text(&font, "0.00 W - left")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((BatteryText, panel_text(&font, "Battery: 100%")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((WindHoldText, panel_text(&font, "Wind hold: press Z")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((CatchText, panel_text(&font, "Catch: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((MissionText, panel_text(&font, "Mission: press M")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((PerchText, panel_text(&font, "Perch: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((TurnText, panel_text(&font, "Turn assist: off")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((AutoYawText, panel_text(&font, "Auto yaw: off")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((VrsText, panel_text(&font, "VRS: off")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((GyroText, panel_text(&font, "Gyro: unlimited")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((ProfileText, panel_text(&font, "Profile: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((LandingText, panel_text(&font, "Land: L")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((AltProtectText, panel_text(&font, "Floor: off")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((ProtectionText, panel_text(&font, "Protect: custom")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((HomeText, panel_text(&font, "Home: -")));
+                        });
+                    parent
+                        .spawn(text_panel(Color::BLACK))
+                        .with_children(|parent| {
+                            parent.spawn((TrimText, panel_text(&font, "Trim: -")));
+                        });
+                });
+        });
+}
+
+fn hud_column(align_items: AlignItems) -> impl Bundle {
+    Node {
+        flex_direction: FlexDirection::Column,
+        align_items,
+        justify_content: JustifyContent::FlexStart,
+        ..Default::default()
+    }
+}
+
+fn text_panel(background: Color) -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(380.),
+            display: Display::Flex,
+            justify_content: JustifyContent::Start,
+            align_items: AlignItems::Center,
+            position_type: PositionType::Relative,
+            padding: UiRect::all(Val::Px(8.)),
+            border: UiRect::all(Val::Px(2.)),
+            ..Default::default()
+        },
+        BorderColor(Color::WHITE),
+        BackgroundColor(background),
+    )
+}
+
+fn panel_text(font: &Handle<Font>, value: &str) -> impl Bundle {
+    (
+        Text::new(value),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Left),
+        TextFont {
+            font: font.clone(),
+            font_size: 22.,
+            ..Default::default()
+        },
+    )
+}
+
+/// `Tab` makes the next drone (by `DroneId`) the active one, wrapping around.
+pub fn cycle_active_drone(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    drone_query: Query<(Entity, &DroneId, &Name, Has<ActiveDrone>), With<Drone>>,
+) {
+    if !keyboard.just_pressed(KeyCode::Tab) {
+        return;
+    }
+
+    let mut drones = drone_query.iter().collect::<Vec<_>>();
+    if drones.len() < 2 {
+        return;
+    }
+    drones.sort_by_key(|(_, id, _, _)| id.0);
+    let current = drones
+        .iter()
+        .position(|(_, _, _, active)| *active)
+        .unwrap_or(0);
+    let (next, _, name, _) = drones[(current + 1) % drones.len()];
+
+    for (drone, _, _, active) in &drones {
+        if *active {
+            commands.entity(*drone).remove::<ActiveDrone>();
+        }
+    }
+    commands.entity(next).insert(ActiveDrone);
+    info!("Active drone: {name}");
+}
+
+/// Tints the active drone and shows its color on the HUD.
+#[allow(clippy::type_complexity)]
+pub fn highlight_active_drone(
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    drone_query: Query<
+        (
+            &DroneColor,
+            &MeshMaterial3d<StandardMaterial>,
+            Has<ActiveDrone>,
+        ),
+        With<Drone>,
+    >,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<DroneText>>,
+) {
+    for (drone_color, material, active) in drone_query.iter() {
+        let glow = if active {
+            LinearRgba::from(drone_color.color) * ACTIVE_DRONE_GLOW
+        } else {
+            LinearRgba::BLACK
+        };
+        let handle = &material.0;
+        if materials.get(handle).is_some_and(|m| m.emissive != glow)
+            && let Some(material) = materials.get_mut(handle)
+        {
+            material.emissive = glow;
+        }
+
+        if active {
+            for (mut text, mut color) in text_query.iter_mut() {
+                *text = format!("Drone: {}", drone_color.name).into();
+                color.0 = drone_color.color;
+            }
+        }
+    }
+}
+
+pub fn adjust_ui_scale(keyboard: Res<ButtonInput<KeyCode>>, mut ui_scale: ResMut<UiScale>) {
+    if keyboard.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        ui_scale.0 = (ui_scale.0 + UI_SCALE_STEP).min(MAX_UI_SCALE);
+    }
+    if keyboard.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        ui_scale.0 = (ui_scale.0 - UI_SCALE_STEP).max(MIN_UI_SCALE);
+    }
+}
+
+pub fn update_engine_ui(
+    engine_state: Res<State<EngineState>>,
+    failure_mode: Res<FailureMode>,
+    tumble: Res<TumbleDisarm>,
+    drone_query: Query<&Transform, With<Drone>>,
+    mut engine_ui_query: Query<&mut BackgroundColor, With<EngineUI>>,
+    mut text_query: Query<&mut Text, With<EngineText>>,
+) {
+    let engine_out = *failure_mode == FailureMode::Deadstick && drone_query.iter().any(is_airborne);
+
+    for mut text in text_query.iter_mut() {
+        if *engine_state.get() == EngineState::On {
+            *text = "Engine: On".into();
+            for mut ui in engine_ui_query.iter_mut() {
+                *ui = BackgroundColor(Color::srgba(0. / 255., 210. / 255., 0. / 255., 1.));
+            }
+        } else if tumble.tripped {
+            *text = "TUMBLE DISARM".into();
+            for mut ui in engine_ui_query.iter_mut() {
+                *ui = BackgroundColor(Color::srgba(150. / 255., 0. / 255., 150. / 255., 1.));
+            }
+        } else if engine_out {
+            *text = "ENGINE OUT".into();
+            for mut ui in engine_ui_query.iter_mut() {
+                *ui = BackgroundColor(Color::srgba(230. / 255., 120. / 255., 0. / 255., 1.));
+            }
+        } else {
+            *text = "Engine: Off".into();
+            for mut ui in engine_ui_query.iter_mut() {
+                *ui = BackgroundColor(Color::srgba(210. / 255., 0. / 255., 0. / 255., 1.));
+            }
+        }
+    }
+}
+
+pub fn update_output_y_text(
+    drone_query: Query<&Transform, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<OutputYText>>,
+) {
+    for tf in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            *text = format!("Output Y: {:.2} m", tf.translation.y).into();
+        }
+    }
+}
+
+pub fn update_target_y_text(
+    drone_query: Query<&HoverPid, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<TargetYText>>,
+) {
+    for ctl_y in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            *text = if ctl_y.ceiling_softness > 0.0 {
+                format!(
+                    "Target Y: {:.2} m (ceiling {:.1} m)",
+                    ctl_y.target_y,
+                    ctl_y.effective_ceiling()
+                )
+                .into()
+            } else {
+                format!("Target Y: {:.2} m", ctl_y.target_y).into()
+            };
+        }
+    }
+}
+
+pub fn update_output_pitch_text(
+    drone_query: Query<&Transform, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<OutputPitchText>>,
+) {
+    for tf in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            let (_, x_rad, _) = tf.rotation.to_euler(EulerRot::YXZ);
+            *text = format!("Output Pitch: {:.2} deg", x_rad.to_degrees()).into();
+        }
+    }
+}
+
+pub fn update_target_pitch_text(
+    limit: Res<AttitudeRateLimit>,
+    drone_query: Query<&PitchPid, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<TargetPitchText>>,
+) {
+    for ctl_pitch in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            *text = format!(
+                "Target Pitch: {:.2} deg{}",
+                ctl_pitch.target_angle.to_degrees(),
+                rate_limit_text(limit.pitch)
+            )
+            .into();
+        }
+    }
+}
+
+pub fn update_output_roll_text(
+    drone_query: Query<&Transform, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<OutputRollText>>,
+) {
+    for tf in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            let (_, _, z_rad) = tf.rotation.to_euler(EulerRot::YXZ);
+            *text = format!("Output Roll: {:.2} deg", z_rad.to_degrees()).into();
+        }
+    }
+}
+
+pub fn update_target_roll_text(
+    limit: Res<AttitudeRateLimit>,
+    drone_query: Query<&RollPid, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<TargetRollText>>,
+) {
+    for roll in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            *text = format!(
+                "Target Roll: {:.2} deg{}",
+                roll.target_angle.to_degrees(),
+                rate_limit_text(limit.roll)
+            )
+            .into();
+        }
+    }
+}
+
+pub fn update_output_yaw_text(
+    drone_query: Query<&Transform, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<OutputYawText>>,
+) {
+    for tf in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            let (y_rad, _, _) = tf.rotation.to_euler(EulerRot::YXZ);
+            *text = format!("Output Yaw: {:.2} deg", y_rad.to_degrees()).into();
+        }
+    }
+}
+
+pub fn update_target_yaw_text(
+    limit: Res<AttitudeRateLimit>,
+    drone_query: Query<&YawPid, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<TargetYawText>>,
+) {
+    for yaw in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            *text = format!(
+                "Target Yaw: {:.2} deg{}",
+                yaw.target_angle.to_degrees(),
+                rate_limit_text(limit.yaw)
+            )
+            .into();
+        }
+    }
+}
+
+pub fn spawn_light(mut commands: Commands) {
+    // `--night` dims the sun and sky to moonlight, for flying with the spotlight
+    let night = has_flag("--night");
+    if night {
+        commands.insert_resource(AmbientLight {
+            brightness: 5.0,
+            ..Default::default()
+        });
+        commands.insert_resource(ClearColor(Color::srgb(0.01, 0.01, 0.03)));
+    }
+
+    commands.spawn((
+        DirectionalLight {
+            shadows_enabled: true,
+            illuminance: if night { 5.0 } else { 5000.0 },
+            ..Default::default()
+        },
+        Transform {
+            rotation: Quat::from_rotation_x(-45.0_f32.to_radians())
+                * Quat::from_rotation_y(30.0_f32.to_radians()),
+            ..Default::default()
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    #[test]
+    fn quaternion_errors_survive_gimbal_lock() {
+        // Near 90° pitch, yaw and roll turn about almost the same axis, so
+        // 45° of each nearly cancel out
+        let pitch = 89.0_f32.to_radians();
+        let quarter = 45.0_f32.to_radians();
+        let rotation = Quat::from_euler(EulerRot::YXZ, quarter, pitch, quarter);
+
+        let (_, e_roll, e_yaw) = AttitudeMode::Euler.errors(rotation, pitch, 0.0, 0.0);
+        assert!(e_roll.abs() > 0.7 && e_yaw.abs() > 0.7);
+
+        let target = Quat::from_euler(EulerRot::YXZ, 0.0, pitch, 0.0);
+        let (e_pitch, e_roll, e_yaw) = AttitudeMode::Quaternion.errors(rotation, pitch, 0.0, 0.0);
+        let error = Vec3::new(e_pitch, e_yaw, e_roll).length();
+        assert!((error - rotation.angle_between(target)).abs() < EPSILON);
+        assert!(error < 0.02, "error {error} rad");
+    }
+
+    #[test]
+    fn yaw_error_wraps_around() {
+        let rotation = Quat::from_rotation_y(179.0_f32.to_radians());
+        let target = -179.0_f32.to_radians();
+
+        for mode in [AttitudeMode::Euler, AttitudeMode::Quaternion] {
+            let (e_pitch, e_roll, e_yaw) = mode.errors(rotation, 0.0, 0.0, target);
+            assert!(
+                (e_yaw - 2.0_f32.to_radians()).abs() < EPSILON,
+                "{mode:?}: {e_yaw}"
+            );
+            assert!(e_pitch.abs() < EPSILON && e_roll.abs() < EPSILON);
+        }
+    }
+
+    /// Thrust above the weight and torque the drone's controllers command in
+    /// the first frame after arming, asked to climb 5 cm and pitch nose down,
+    /// with every controller's output multiplied by `output_sign`.
+    fn first_response(output_sign: f32) -> (f32, Vec3) {
+        let mut app = build_app(true);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_micros(
+            16_667,
+        )));
+        app.update();
+        app.update();
+
+        let mut drone_query = app
+            .world_mut()
+            .query_filtered::<(&Transform, &mut HoverPid, &mut PitchPid), With<Drone>>();
+        for (tf, mut ctl_y, mut ctl_pitch) in drone_query.iter_mut(app.world_mut()) {
+            ctl_y.target_y = tf.translation.y + 0.05;
+            ctl_y.output_sign = output_sign;
+            ctl_pitch.target_angle = -0.2;
+            ctl_pitch.output_sign = output_sign;
+        }
+        app.world_mut()
+            .resource_mut::<NextState<EngineState>>()
+            .set(EngineState::On);
+        app.update();
+
+        let mut drone_query = app
+            .world_mut()
+            .query_filtered::<(&Transform, &ExternalForce, &ReadMassProperties), With<Drone>>();
+        let (tf, ext_force, mass_props) = drone_query.single(app.world()).unwrap();
+        let thrust = (tf.rotation.inverse() * ext_force.force).y;
+        let torque = tf.rotation.inverse() * ext_force.torque;
+        (thrust - mass_props.mass * GRAVITY, torque)
+    }
+
+    #[test]
+    fn output_sign_reverses_response() {
+        let (climb, torque) = first_response(1.0);
+        assert!(climb > 0.0, "thrust {climb} N above the weight");
+        assert!(torque.x < 0.0, "pitch torque {}", torque.x);
+
+        let (inverted_climb, inverted_torque) = first_response(-1.0);
+        assert!((inverted_climb + climb).abs() < 1e-3 * climb);
+        assert!((inverted_torque.x + torque.x).abs() < 1e-3 * torque.x.abs());
+    }
+}
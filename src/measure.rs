@@ -0,0 +1,216 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::EguiContexts;
+use bevy_rapier3d::prelude::*;
+
+use crate::{DroneCamera, arg_value, follow_mouse::cursor_ray};
+
+const MEASURE_KEY: KeyCode = KeyCode::Slash;
+const CLEAR_KEY: KeyCode = KeyCode::Backspace;
+/// Furthest a click reaches into the scene before falling back to the
+/// ground plane (m).
+const MAX_PICK_DISTANCE: f32 = 500.0;
+const MARKER_RADIUS: f32 = 0.15;
+const MEASURE_COLOR: Color = Color::srgb(1.0, 0.9, 0.1);
+
+/// Distance tool for level design: with `/` on, left-clicks place two markers
+/// on whatever is under the cursor (gates, drones, the floor) and the
+/// straight-line distance between them is shown with its horizontal and
+/// vertical parts. A third click starts a new measurement; `Backspace`
+/// clears it. Points snap to a `--measure-snap <m>` grid if given.
+#[derive(Resource)]
+pub struct MeasureTool {
+    pub active: bool,
+    pub points: Vec<Vec3>,
+    /// Grid spacing the points are rounded to (m), if any.
+    pub snap: Option<f32>,
+}
+
+impl MeasureTool {
+    pub fn from_args() -> Self {
+        Self {
+            active: false,
+            points: Vec::new(),
+            snap: arg_value("--measure-snap")
+                .and_then(|snap| snap.parse::<f32>().ok())
+                .filter(|snap| *snap > 0.0),
+        }
+    }
+
+    fn place(&mut self, point: Vec3) {
+        let point = match self.snap {
+            Some(snap) => (point / snap).round() * snap,
+            None => point,
+        };
+        if self.points.len() >= 2 {
+            self.points.clear();
+        }
+        self.points.push(point);
+    }
+
+    /// The two points, once both are placed.
+    fn span(&self) -> Option<(Vec3, Vec3)> {
+        match self.points.as_slice() {
+            &[a, b] => Some((a, b)),
+            _ => None,
+        }
+    }
+}
+
+/// Readout floating next to the middle of the measured line.
+#[derive(Component)]
+pub struct MeasureLabel;
+
+pub fn measure_plugin(app: &mut App) {
+    app.insert_resource(MeasureTool::from_args())
+        .add_systems(Startup, spawn_measure_label)
+        .add_systems(
+            Update,
+            (
+                toggle_measure_tool,
+                (place_measure_point, draw_measurement)
+                    .chain()
+                    .run_if(|tool: Res<MeasureTool>| tool.active),
+                place_measure_label,
+            )
+                .chain(),
+        );
+}
+
+pub fn spawn_measure_label(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        MeasureLabel,
+        Text::new(""),
+        TextColor(MEASURE_COLOR),
+        TextFont {
+            font: asset_server.load("./pixeloid_mono.ttf"),
+            font_size: 18.,
+            ..Default::default()
+        },
+        TextShadow::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            ..Default::default()
+        },
+        Visibility::Hidden,
+        Pickable::IGNORE,
+    ));
+}
+
+pub fn toggle_measure_tool(keyboard: Res<ButtonInput<KeyCode>>, mut tool: ResMut<MeasureTool>) {
+    if keyboard.just_pressed(MEASURE_KEY) {
+        tool.active = !tool.active;
+        tool.points.clear();
+        info!(
+            "Measure tool {}",
+            if tool.active {
+                "on, click two points"
+            } else {
+                "off"
+            }
+        );
+    }
+    if keyboard.just_pressed(CLEAR_KEY) {
+        tool.points.clear();
+    }
+}
+
+/// Places a point where the cursor ray first hits a collider, or the ground
+/// plane if it hits nothing. Clicks on the egui panels are left alone.
+pub fn place_measure_point(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut contexts: EguiContexts,
+    rapier_context: ReadRapierContext,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<DroneCamera>>,
+    mut tool: ResMut<MeasureTool>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    if contexts
+        .ctx_mut()
+        .is_ok_and(|ctx| ctx.is_pointer_over_area())
+    {
+        return;
+    }
+    let Some(ray) = cursor_ray(&window_query, &camera_query) else {
+        return;
+    };
+
+    let hit = rapier_context.single().ok().and_then(|context| {
+        context
+            .cast_ray(
+                ray.origin,
+                *ray.direction,
+                MAX_PICK_DISTANCE,
+                true,
+                QueryFilter::default().exclude_sensors(),
+            )
+            .map(|(_, toi)| ray.get_point(toi))
+    });
+    let point = hit.or_else(|| {
+        let distance = ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))?;
+        Some(ray.get_point(distance))
+    });
+
+    if let Some(point) = point {
+        tool.place(point);
+        info!(
+            "Measure point {}: ({:.2}, {:.2}, {:.2})",
+            tool.points.len(),
+            point.x,
+            point.y,
+            point.z
+        );
+    }
+}
+
+/// Markers on the points, the line between them and the right angle of its
+/// horizontal and vertical legs.
+pub fn draw_measurement(tool: Res<MeasureTool>, mut gizmos: Gizmos) {
+    for point in &tool.points {
+        gizmos.sphere(*point, MARKER_RADIUS, MEASURE_COLOR);
+    }
+    let Some((a, b)) = tool.span() else {
+        return;
+    };
+
+    gizmos.line(a, b, MEASURE_COLOR);
+    let corner = Vec3::new(b.x, a.y, b.z);
+    let legs = MEASURE_COLOR.with_alpha(0.4);
+    gizmos.line(a, corner, legs);
+    gizmos.line(corner, b, legs);
+}
+
+pub fn place_measure_label(
+    tool: Res<MeasureTool>,
+    ui_scale: Res<UiScale>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<DroneCamera>>,
+    mut label_query: Query<(&mut Text, &mut Node, &mut Visibility), With<MeasureLabel>>,
+) {
+    let span = tool.span().filter(|_| tool.active);
+    // The first camera the middle of the line is in view of
+    let screen = span.and_then(|(a, b)| {
+        camera_query
+            .iter()
+            .find_map(|(camera, camera_tf)| camera.world_to_viewport(camera_tf, a.midpoint(b)).ok())
+    });
+
+    for (mut text, mut node, mut visibility) in label_query.iter_mut() {
+        let (Some((a, b)), Some(screen)) = (span, screen) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        let delta = b - a;
+        *text = format!(
+            "{:.2} m\nH {:.2} m  V {:+.2} m",
+            delta.length(),
+            Vec2::new(delta.x, delta.z).length(),
+            delta.y
+        )
+        .into();
+        *visibility = Visibility::Visible;
+        node.left = Val::Px(screen.x / ui_scale.0 + 8.0);
+        node.top = Val::Px(screen.y / ui_scale.0 - 10.0);
+    }
+}
@@ -0,0 +1,491 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ActiveDrone, EngineState, PitchPid, RollPid, arg_value, chase_guidance,
+    follow_mouse::FollowCursor,
+    has_flag,
+    landing::LandingState,
+    level::{Level, WaypointAction},
+    orbit::{OrbitPoi, OrbitSettings},
+    position::{Home, PositionPid, release_tilt},
+    position_control,
+    return_home::ReturningHome,
+    trajectory::Trajectory,
+};
+
+/// Below this ground speed the ETA isn't shown; it would be meaningless (m/s).
+const MIN_ETA_SPEED: f32 = 0.2;
+/// Shortest leg of a smooth path (s), so short legs don't demand huge accelerations.
+const MIN_SEGMENT_TIME: f32 = 1.0;
+/// Time between the points the smooth path is drawn through (s).
+const PATH_DRAW_STEP: f32 = 0.1;
+
+/// Settings shared by every mission started with `M`.
+#[derive(Resource)]
+pub struct MissionSettings {
+    /// Distance at which a waypoint counts as reached (m).
+    pub arrival_radius: f32,
+    /// Speed the drone must also be below for a waypoint to count as reached
+    /// (m/s), so it settles at each one instead of sweeping past.
+    pub arrival_speed: Option<f32>,
+    /// After the last waypoint, start over from the first instead of holding.
+    pub looping: bool,
+    /// Time constant of the ground speed filter behind the ETA (s).
+    pub eta_smoothing: f32,
+    /// Fly a smooth trajectory through the waypoints instead of stopping at each.
+    pub smooth: bool,
+    /// Fixed time per leg of the smooth path (s); otherwise it's timed from
+    /// the leg length at `path_speed`.
+    pub segment_time: Option<f32>,
+    pub path_speed: f32,
+}
+
+impl MissionSettings {
+    pub fn from_args() -> Self {
+        Self {
+            arrival_radius: arg_value("--waypoint-radius")
+                .and_then(|radius| radius.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .max(0.1),
+            arrival_speed: arg_value("--waypoint-max-speed")
+                .and_then(|speed| speed.parse::<f32>().ok())
+                .filter(|speed| *speed > 0.0),
+            looping: has_flag("--mission-loop"),
+            eta_smoothing: arg_value("--eta-smoothing")
+                .and_then(|smoothing| smoothing.parse::<f32>().ok())
+                .unwrap_or(2.0)
+                .max(0.0),
+            smooth: has_flag("--smooth-path"),
+            segment_time: arg_value("--segment-time")
+                .and_then(|time| time.parse::<f32>().ok())
+                .map(|time| time.max(MIN_SEGMENT_TIME)),
+            path_speed: arg_value("--path-speed")
+                .and_then(|speed| speed.parse::<f32>().ok())
+                .unwrap_or(3.0)
+                .max(0.1),
+        }
+    }
+
+    /// Smooth path from `start` through `waypoints`.
+    fn trajectory(&self, start: Vec3, waypoints: &[Vec3]) -> Trajectory {
+        let points: Vec<Vec3> = std::iter::once(start)
+            .chain(waypoints.iter().copied())
+            .collect();
+        let durations: Vec<f32> = points
+            .windows(2)
+            .map(|leg| {
+                self.segment_time.unwrap_or_else(|| {
+                    (leg[0].distance(leg[1]) / self.path_speed).max(MIN_SEGMENT_TIME)
+                })
+            })
+            .collect();
+        Trajectory::through(&points, &durations)
+    }
+}
+
+/// Flies the drone through the level's waypoints in order and holds position
+/// over the last one, or starts over from the first in a loop. Either one
+/// straight leg at a time, or along a smooth trajectory whose position is the
+/// target and whose acceleration is fed forward to the position controller.
+/// Waypoints with an action stop the mission there until it's done; a smooth
+/// path is planned to come to rest at them and picks up again from wherever
+/// the action left the drone.
+#[derive(Component)]
+pub struct WaypointMission {
+    pub waypoints: Vec<Vec3>,
+    /// What to do at each waypoint.
+    pub actions: Vec<WaypointAction>,
+    pub trajectory: Option<Trajectory>,
+    /// Index of the waypoint the smooth path's first leg leads to.
+    pub path_start: usize,
+    /// Time along `trajectory` (s).
+    pub elapsed: f32,
+    /// Index of the waypoint being flown to; equal to the count once done.
+    pub current: usize,
+    /// Low-pass filtered ground speed (m/s).
+    pub ground_speed: f32,
+    /// Timed action under way at the waypoint just reached, and its time
+    /// left (s).
+    pub action: Option<(WaypointAction, f32)>,
+    /// Flying the last leg back over the spawn point.
+    pub returning_home: bool,
+    /// Times round the waypoints so far, when looping.
+    pub laps: usize,
+}
+
+impl WaypointMission {
+    pub fn is_complete(&self) -> bool {
+        self.current >= self.waypoints.len() && self.action.is_none()
+    }
+
+    /// Plans the smooth path from `start` up to the next waypoint with an
+    /// action, or the last one.
+    fn plan_path(&mut self, settings: &MissionSettings, start: Vec3) {
+        let end = self.actions[self.current..]
+            .iter()
+            .position(|action| *action != WaypointAction::FlyThrough)
+            .map_or(self.waypoints.len(), |i| self.current + i + 1);
+        self.trajectory = (self.current < end)
+            .then(|| settings.trajectory(start, &self.waypoints[self.current..end]));
+        self.path_start = self.current;
+        self.elapsed = 0.0;
+    }
+
+    /// Replaces the rest of the mission with a leg back over `home` at the
+    /// altitude of the waypoint just reached.
+    fn return_home(&mut self, home: Vec3) {
+        let altitude = self.waypoints[self.current - 1].y;
+        self.waypoints.truncate(self.current);
+        self.actions.truncate(self.current);
+        self.waypoints.push(Vec3::new(home.x, altitude, home.z));
+        self.actions.push(WaypointAction::FlyThrough);
+        self.returning_home = true;
+    }
+
+    /// Distance left along the path from `position`: to the current waypoint,
+    /// then along every leg after it.
+    pub fn remaining_distance(&self, position: Vec3) -> f32 {
+        let Some(next) = self.waypoints.get(self.current) else {
+            return 0.0;
+        };
+        let legs: f32 = self.waypoints[self.current..]
+            .windows(2)
+            .map(|leg| leg[0].distance(leg[1]))
+            .sum();
+        position.distance(*next) + legs
+    }
+
+    /// Time left: to the end of the smooth path if there is one, otherwise at
+    /// the current ground speed, or `None` while (nearly) stationary.
+    pub fn eta(&self, position: Vec3) -> Option<f32> {
+        if let Some(trajectory) = &self.trajectory {
+            return Some((trajectory.duration() - self.elapsed).max(0.0));
+        }
+        (self.ground_speed >= MIN_ETA_SPEED)
+            .then(|| self.remaining_distance(position) / self.ground_speed)
+    }
+}
+
+#[derive(Component)]
+pub struct MissionText;
+
+pub fn mission_plugin(app: &mut App) {
+    app.insert_resource(MissionSettings::from_args())
+        .add_systems(
+            Update,
+            (
+                toggle_mission,
+                mission_guidance
+                    .after(chase_guidance)
+                    .before(position_control)
+                    .run_if(in_state(EngineState::On)),
+            )
+                .chain(),
+        )
+        .add_systems(Update, (update_mission_text, draw_mission));
+}
+
+#[allow(clippy::type_complexity)]
+pub fn toggle_mission(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<MissionSettings>,
+    level: Res<Level>,
+    mut drone_query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut PositionPid,
+            &mut PitchPid,
+            &mut RollPid,
+            Has<WaypointMission>,
+        ),
+        With<ActiveDrone>,
+    >,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+
+    for (drone, tf, mut ctl_pos, mut ctl_pitch, mut ctl_roll, flying) in drone_query.iter_mut() {
+        if flying {
+            commands
+                .entity(drone)
+                .remove::<(WaypointMission, OrbitPoi)>();
+            ctl_pos.disengage();
+            release_tilt(&mut ctl_pitch, &mut ctl_roll);
+            info!("Mission aborted");
+            continue;
+        }
+
+        let Some(first) = level.waypoints.first() else {
+            warn!("The level has no waypoints to fly");
+            continue;
+        };
+
+        let mut mission = WaypointMission {
+            waypoints: level.waypoints.iter().map(|w| w.position).collect(),
+            actions: level.waypoints.iter().map(|w| w.action).collect(),
+            trajectory: None,
+            path_start: 0,
+            elapsed: 0.0,
+            current: 0,
+            ground_speed: 0.0,
+            action: None,
+            returning_home: false,
+            laps: 0,
+        };
+        // A smooth path starts where the drone is rather than at the first waypoint
+        let target = if settings.smooth {
+            mission.plan_path(&settings, tf.translation);
+            tf.translation
+        } else {
+            first.position
+        };
+
+        let tilt = Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle);
+        ctl_pos.engage(target, tf.translation, tilt);
+        info!("Mission started, {} waypoints", mission.waypoints.len());
+        commands
+            .entity(drone)
+            .remove::<(OrbitPoi, FollowCursor, LandingState, ReturningHome)>()
+            .insert(mission);
+    }
+}
+
+/// Starts the action of the waypoint just reached, if it has one.
+fn begin_action(
+    commands: &mut Commands,
+    drone: Entity,
+    orbit_settings: &OrbitSettings,
+    home: &Home,
+    mission: &mut WaypointMission,
+    ctl_pos: &mut PositionPid,
+) {
+    let reached = mission.current - 1;
+    let waypoint = mission.waypoints[reached];
+    let action = mission.actions[reached];
+    match action {
+        WaypointAction::FlyThrough => return,
+        WaypointAction::Loiter(duration) => {
+            info!("Loitering at waypoint {} for {duration} s", reached + 1);
+            mission.action = Some((action, duration));
+            ctl_pos.target = waypoint;
+        }
+        WaypointAction::Orbit(duration) => {
+            info!("Orbiting waypoint {} for {duration} s", reached + 1);
+            mission.action = Some((action, duration));
+            commands.entity(drone).insert(OrbitPoi::joining(
+                orbit_settings,
+                waypoint,
+                waypoint.y,
+                waypoint,
+            ));
+        }
+        WaypointAction::ReturnHome => {
+            info!("Returning home from waypoint {}", reached + 1);
+            mission.return_home(home.0);
+        }
+    }
+    ctl_pos.accel_feedforward = Vec2::ZERO;
+}
+
+pub fn mission_guidance(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<MissionSettings>,
+    orbit_settings: Res<OrbitSettings>,
+    mut drone_query: Query<(
+        Entity,
+        &Transform,
+        &Velocity,
+        &Home,
+        &mut WaypointMission,
+        &mut PositionPid,
+    )>,
+) {
+    let dt = time.delta_secs();
+
+    for (drone, tf, velocity, home, mut mission, mut ctl_pos) in drone_query.iter_mut() {
+        let speed = Vec2::new(velocity.linvel.x, velocity.linvel.z).length();
+        let blend = if settings.eta_smoothing > 0.0 {
+            (dt / settings.eta_smoothing).min(1.0)
+        } else {
+            1.0
+        };
+        mission.ground_speed += (speed - mission.ground_speed) * blend;
+
+        if let Some((action, left)) = mission.action {
+            if left > dt {
+                mission.action = Some((action, left - dt));
+                continue;
+            }
+            mission.action = None;
+            if let WaypointAction::Orbit(_) = action {
+                commands.entity(drone).remove::<OrbitPoi>();
+            }
+            info!("Done at waypoint {}, flying on", mission.current);
+            if settings.smooth {
+                mission.plan_path(&settings, tf.translation);
+            }
+        }
+
+        // Round again; a return home ends the loop
+        if settings.looping && mission.is_complete() && !mission.returning_home {
+            mission.current = 0;
+            mission.laps += 1;
+            info!("Mission lap {} done, starting over", mission.laps);
+            if settings.smooth {
+                mission.plan_path(&settings, tf.translation);
+            }
+        }
+
+        if let Some(trajectory) = &mission.trajectory {
+            let elapsed = mission.elapsed + dt;
+            let passed = mission.path_start + trajectory.points_passed(elapsed);
+            let Some(reference) = trajectory.sample(elapsed) else {
+                continue;
+            };
+            ctl_pos.target = reference.position;
+            ctl_pos.accel_feedforward =
+                Vec2::new(reference.acceleration.x, reference.acceleration.z);
+
+            mission.elapsed = elapsed;
+            if passed > mission.current {
+                mission.current = passed;
+                info!(
+                    "Passed waypoint {}/{}",
+                    mission.current,
+                    mission.waypoints.len()
+                );
+                // Only the last waypoint of a path can have an action
+                let was_returning = mission.returning_home;
+                begin_action(
+                    &mut commands,
+                    drone,
+                    &orbit_settings,
+                    home,
+                    &mut mission,
+                    &mut ctl_pos,
+                );
+                if mission.returning_home && !was_returning {
+                    mission.plan_path(&settings, tf.translation);
+                }
+            }
+            continue;
+        }
+
+        if let Some(&next) = mission.waypoints.get(mission.current)
+            && tf.translation.distance(next) < settings.arrival_radius
+            && settings
+                .arrival_speed
+                .is_none_or(|max| velocity.linvel.length() < max)
+        {
+            mission.current += 1;
+            info!(
+                "Reached waypoint {}/{}",
+                mission.current,
+                mission.waypoints.len()
+            );
+            begin_action(
+                &mut commands,
+                drone,
+                &orbit_settings,
+                home,
+                &mut mission,
+                &mut ctl_pos,
+            );
+            if mission.action.is_some() {
+                continue;
+            }
+        }
+
+        // Hold over the last waypoint once they're all reached
+        let index = mission.current.min(mission.waypoints.len() - 1);
+        ctl_pos.target = mission.waypoints[index];
+    }
+}
+
+pub fn update_mission_text(
+    settings: Res<MissionSettings>,
+    drone_query: Query<(&Transform, Option<&WaypointMission>), With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<MissionText>>,
+) {
+    let Ok((tf, mission)) = drone_query.single() else {
+        return;
+    };
+
+    for mut text in text_query.iter_mut() {
+        *text = match mission {
+            None => "Mission: press M".into(),
+            Some(mission) if mission.is_complete() => {
+                format!("Mission: {0}/{0} done", mission.waypoints.len()).into()
+            }
+            Some(WaypointMission {
+                action: Some((action, left)),
+                current,
+                waypoints,
+                ..
+            }) => {
+                let name = match action {
+                    WaypointAction::Orbit(_) => "orbit",
+                    _ => "loiter",
+                };
+                format!("Mission: {current}/{} {name} {left:.0} s", waypoints.len()).into()
+            }
+            Some(mission) => {
+                let eta = mission
+                    .eta(tf.translation)
+                    .map_or("-".to_string(), |eta| format!("{eta:.0} s"));
+                let leg = if mission.returning_home {
+                    "RTH".to_string()
+                } else if settings.looping {
+                    format!(
+                        "{}/{} lap {}",
+                        mission.current,
+                        mission.waypoints.len(),
+                        mission.laps + 1
+                    )
+                } else {
+                    format!("{}/{}", mission.current, mission.waypoints.len())
+                };
+                format!(
+                    "Mission: {leg} {:.0} m ETA {eta}",
+                    mission.remaining_distance(tf.translation)
+                )
+                .into()
+            }
+        };
+    }
+}
+
+pub fn draw_mission(drone_query: Query<(&Transform, &WaypointMission)>, mut gizmos: Gizmos) {
+    for (tf, mission) in drone_query.iter() {
+        let done = Color::srgb(0.4, 0.4, 0.4);
+        let ahead = Color::srgb(0.2, 0.8, 1.0);
+
+        if let Some(trajectory) = &mission.trajectory {
+            let duration = trajectory.duration();
+            let samples = (duration / PATH_DRAW_STEP).ceil() as usize;
+            let points = (0..=samples).filter_map(|i| {
+                let t = (i as f32 * PATH_DRAW_STEP).min(duration);
+                let color = if t <= mission.elapsed { done } else { ahead };
+                trajectory.sample(t).map(|point| (point.position, color))
+            });
+            gizmos.linestrip_gradient(points);
+        } else {
+            for (i, leg) in mission.waypoints.windows(2).enumerate() {
+                let color = if i + 1 < mission.current { done } else { ahead };
+                gizmos.line(leg[0], leg[1], color);
+            }
+        }
+        for (i, waypoint) in mission.waypoints.iter().enumerate() {
+            let color = if i < mission.current { done } else { ahead };
+            gizmos.sphere(*waypoint, 0.3, color);
+        }
+        if let Some(next) = mission.waypoints.get(mission.current) {
+            gizmos.line(tf.translation, *next, Color::srgb(1.0, 0.8, 0.2));
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use bevy::prelude::*;
+
+use crate::{PROPELLER_OFFSETS, arg_value};
+
+/// Direction each rotor spins in, as the sign of the yaw reaction torque its
+/// thrust produces. Diagonal pairs spin the same way.
+const ROTOR_SPIN: [f32; 4] = [1.0, -1.0, -1.0, 1.0];
+/// Steps of the search for how much of a command still fits.
+const FIT_ITERATIONS: usize = 16;
+
+/// One part of the command the mixer can scale back when the rotors saturate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixAxis {
+    RollPitch,
+    Yaw,
+    Thrust,
+}
+
+impl MixAxis {
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "roll-pitch" | "rp" => Some(Self::RollPitch),
+            "yaw" => Some(Self::Yaw),
+            "thrust" => Some(Self::Thrust),
+            _ => None,
+        }
+    }
+}
+
+/// What gives way when the commanded thrust and torques need more than the
+/// rotors can deliver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixerPriority {
+    /// Clamp each rotor on its own. Simple, but distorts the torques: a
+    /// saturated roll command also changes pitch, yaw and thrust.
+    Clip,
+    /// Give each axis as much as still fits, in this order. Axes later in the
+    /// order are scaled back, keeping the earlier ones exact.
+    Order([MixAxis; 3]),
+}
+
+impl MixerPriority {
+    const DEFAULT: Self = Self::Order([MixAxis::RollPitch, MixAxis::Yaw, MixAxis::Thrust]);
+
+    fn from_args() -> Self {
+        let Some(value) = arg_value("--mixer-priority") else {
+            return Self::DEFAULT;
+        };
+        if value == "clip" {
+            return Self::Clip;
+        }
+
+        let axes = value
+            .split(',')
+            .map(MixAxis::parse)
+            .collect::<Option<Vec<_>>>();
+        match axes.as_deref() {
+            Some(&[a, b, c]) if a != b && b != c && a != c => Self::Order([a, b, c]),
+            _ => {
+                warn!(
+                    "Unknown --mixer-priority {value}, expected clip or an order of \
+                     roll-pitch, yaw and thrust such as roll-pitch,yaw,thrust"
+                );
+                Self::DEFAULT
+            }
+        }
+    }
+}
+
+/// Splits the controllers' thrust and torques over the four rotors and
+/// limits each rotor to `thrust_to_weight` times its share of the weight.
+/// Without `--thrust-to-weight` the rotors have no upper limit and the
+/// torques pass straight through, but the thrust still can't go negative:
+/// rotors only push.
+#[derive(Resource)]
+pub struct Mixer {
+    pub thrust_to_weight: Option<f32>,
+    /// Yaw reaction torque per newton of rotor thrust (m).
+    pub drag_ratio: f32,
+    pub priority: MixerPriority,
+}
+
+impl Mixer {
+    pub fn from_args() -> Self {
+        Self {
+            thrust_to_weight: arg_value("--thrust-to-weight")
+                .and_then(|ratio| ratio.parse::<f32>().ok())
+                .filter(|ratio| *ratio > 0.0),
+            drag_ratio: arg_value("--rotor-drag-ratio")
+                .and_then(|ratio| ratio.parse::<f32>().ok())
+                .unwrap_or(0.05)
+                .max(0.001),
+            priority: MixerPriority::from_args(),
+        }
+    }
+
+    /// Thrust and body torque (pitch, yaw, roll as x, y, z) the rotors can
+    /// actually produce for the commanded ones, for a drone of `weight` (N).
+    pub fn mix(&self, weight: f32, thrust: f32, torque: Vec3) -> (f32, Vec3) {
+        let Some(thrust_to_weight) = self.thrust_to_weight else {
+            return (thrust.max(0.0), torque);
+        };
+        let max_rotor = thrust_to_weight * weight / PROPELLER_OFFSETS.len() as f32;
+
+        let rotors = match self.priority {
+            MixerPriority::Clip => {
+                let mut rotors = [0.0; 4];
+                for axis in [MixAxis::RollPitch, MixAxis::Yaw, MixAxis::Thrust] {
+                    add(&mut rotors, &self.allocate(axis, thrust, torque), 1.0);
+                }
+                rotors.map(|rotor| rotor.clamp(0.0, max_rotor))
+            }
+            MixerPriority::Order(order) => {
+                let mut rotors = [0.0; 4];
+                let mut collective_set = false;
+                for axis in order {
+                    let share = self.allocate(axis, thrust, torque);
+                    if axis == MixAxis::Thrust {
+                        // Shift the collective as far towards the command as
+                        // the differential thrust already placed allows
+                        let (low, high) = range(&rotors);
+                        let collective = share[0].clamp(-low, (max_rotor - high).max(-low));
+                        add(&mut rotors, &[collective; 4], 1.0);
+                        collective_set = true;
+                        continue;
+                    }
+
+                    // Until the collective is set it can still shift, so only
+                    // the spread between the rotors has to fit
+                    let fits = |scale: f32| {
+                        let mut trial = rotors;
+                        add(&mut trial, &share, scale);
+                        let (low, high) = range(&trial);
+                        if collective_set {
+                            low >= 0.0 && high <= max_rotor
+                        } else {
+                            high - low <= max_rotor
+                        }
+                    };
+                    let scale = if fits(1.0) {
+                        1.0
+                    } else {
+                        let (mut lo, mut hi) = (0.0, 1.0);
+                        for _ in 0..FIT_ITERATIONS {
+                            let mid = 0.5 * (lo + hi);
+                            if fits(mid) { lo = mid } else { hi = mid }
+                        }
+                        lo
+                    };
+                    add(&mut rotors, &share, scale);
+                }
+                rotors
+            }
+        };
+
+        self.resultant(&rotors)
+    }
+
+    /// Like [`Mixer::mix`], for a torque in the world frame on a drone at
+    /// `rotation`. The rotors sit in the body frame, so the torque is mixed
+    /// there and the result turned back into the world frame.
+    pub fn mix_world(&self, weight: f32, rotation: Quat, thrust: f32, torque: Vec3) -> (f32, Vec3) {
+        let (thrust, torque) = self.mix(weight, thrust, rotation.inverse() * torque);
+        (thrust, rotation * torque)
+    }
+
+    /// Whether the rotors can't deliver the command as given.
+    pub fn saturates(&self, weight: f32, thrust: f32, torque: Vec3) -> bool {
+        let (mixed_thrust, mixed_torque) = self.mix(weight, thrust, torque);
+        // Torque tolerance taken over a 1m arm
+        let tolerance = 1e-4 * weight;
+        (mixed_thrust - thrust).abs() > tolerance || (mixed_torque - torque).length() > tolerance
+    }
+
+    /// Each rotor's share of one axis of the command.
+    fn allocate(&self, axis: MixAxis, thrust: f32, torque: Vec3) -> [f32; 4] {
+        let count = PROPELLER_OFFSETS.len() as f32;
+        let sum_x2 = PROPELLER_OFFSETS.iter().map(|r| r.x * r.x).sum::<f32>();
+        let sum_z2 = PROPELLER_OFFSETS.iter().map(|r| r.z * r.z).sum::<f32>();
+
+        std::array::from_fn(|i| {
+            let r = PROPELLER_OFFSETS[i];
+            match axis {
+                // Thrust at r along +Y gives torque (-z·f, 0, x·f)
+                MixAxis::RollPitch => -r.z * torque.x / sum_z2 + r.x * torque.z / sum_x2,
+                MixAxis::Yaw => ROTOR_SPIN[i] * torque.y / (count * self.drag_ratio),
+                MixAxis::Thrust => thrust / count,
+            }
+        })
+    }
+
+    /// Total thrust and torque of the rotor thrusts.
+    fn resultant(&self, rotors: &[f32; 4]) -> (f32, Vec3) {
+        let mut thrust = 0.0;
+        let mut torque = Vec3::ZERO;
+        for ((r, spin), f) in PROPELLER_OFFSETS.iter().zip(ROTOR_SPIN).zip(rotors) {
+            thrust += f;
+            torque += Vec3::new(-r.z * f, spin * self.drag_ratio * f, r.x * f);
+        }
+        (thrust, torque)
+    }
+}
+
+fn add(rotors: &mut [f32; 4], share: &[f32; 4], scale: f32) {
+    for (rotor, share) in rotors.iter_mut().zip(share) {
+        *rotor += share * scale;
+    }
+}
+
+/// Lowest and highest rotor thrust.
+fn range(rotors: &[f32; 4]) -> (f32, f32) {
+    rotors
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &f| {
+            (low.min(f), high.max(f))
+        })
+}
+
+pub fn mixer_plugin(app: &mut App) {
+    app.insert_resource(Mixer::from_args());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Weight that, at a thrust-to-weight of 2, caps each rotor at 5 N.
+    const WEIGHT: f32 = 10.0;
+    const MAX_ROTOR: f32 = 5.0;
+    const EPSILON: f32 = 1e-4;
+
+    fn mixer(priority: MixerPriority) -> Mixer {
+        Mixer {
+            thrust_to_weight: Some(2.0),
+            drag_ratio: 0.05,
+            priority,
+        }
+    }
+
+    /// Rotor thrusts behind a mixed thrust and torque.
+    fn rotors(mixer: &Mixer, thrust: f32, torque: Vec3) -> [f32; 4] {
+        let mut rotors = [0.0; 4];
+        for axis in [MixAxis::RollPitch, MixAxis::Yaw, MixAxis::Thrust] {
+            add(&mut rotors, &mixer.allocate(axis, thrust, torque), 1.0);
+        }
+        rotors
+    }
+
+    #[test]
+    fn only_limits_negative_thrust_without_limit() {
+        let mixer = Mixer {
+            thrust_to_weight: None,
+            ..mixer(MixerPriority::DEFAULT)
+        };
+        let torque = Vec3::new(3.0, 2.0, 1.0);
+        assert_eq!(mixer.mix(WEIGHT, 100.0, torque), (100.0, torque));
+        assert_eq!(mixer.mix(WEIGHT, -5.0, torque), (0.0, torque));
+    }
+
+    #[test]
+    fn order_keeps_roll_that_clip_distorts() {
+        // Hover thrust with a roll and yaw that don't fit together
+        let torque = Vec3::new(0.0, 0.5, 4.0);
+
+        let (thrust, clipped) = mixer(MixerPriority::Clip).mix(WEIGHT, WEIGHT, torque);
+        assert!(
+            (clipped.z - torque.z).abs() > 1.0,
+            "clip kept roll {clipped}"
+        );
+        assert!((thrust - WEIGHT).abs() < EPSILON);
+
+        let (thrust, ordered) = mixer(MixerPriority::DEFAULT).mix(WEIGHT, WEIGHT, torque);
+        assert!(
+            (ordered.z - torque.z).abs() < EPSILON,
+            "order gave roll {ordered}"
+        );
+        assert!(ordered.y < torque.y);
+        assert!((thrust - WEIGHT).abs() < EPSILON);
+    }
+
+    #[test]
+    fn priority_axis_stays_exact() {
+        use MixAxis::*;
+
+        // Each axis fits on its own, but not all three together
+        let (thrust, torque) = (1.5 * WEIGHT, Vec3::new(1.5, 0.4, -2.5));
+        let orders = [
+            [RollPitch, Yaw, Thrust],
+            [RollPitch, Thrust, Yaw],
+            [Yaw, RollPitch, Thrust],
+            [Yaw, Thrust, RollPitch],
+            [Thrust, RollPitch, Yaw],
+            [Thrust, Yaw, RollPitch],
+        ];
+        for order in orders {
+            let mixer = mixer(MixerPriority::Order(order));
+            assert!(mixer.saturates(WEIGHT, thrust, torque), "{order:?}");
+
+            let (mixed_thrust, mixed_torque) = mixer.mix(WEIGHT, thrust, torque);
+            let exact = match order[0] {
+                RollPitch => (mixed_torque.xz() - torque.xz()).length(),
+                Yaw => (mixed_torque.y - torque.y).abs(),
+                Thrust => (mixed_thrust - thrust).abs(),
+            };
+            assert!(
+                exact < EPSILON,
+                "{order:?} missed {:?} by {exact}",
+                order[0]
+            );
+        }
+    }
+
+    #[test]
+    fn rotors_stay_within_limits() {
+        let commands = [
+            (WEIGHT, Vec3::new(0.0, 0.5, 4.0)),
+            (3.0 * WEIGHT, Vec3::new(8.0, -1.0, 8.0)),
+            (0.0, Vec3::new(-2.0, 0.3, 1.0)),
+            (-WEIGHT, Vec3::new(0.0, 5.0, 0.0)),
+        ];
+        for priority in [
+            MixerPriority::Clip,
+            MixerPriority::DEFAULT,
+            MixerPriority::Order([MixAxis::Thrust, MixAxis::Yaw, MixAxis::RollPitch]),
+        ] {
+            let mixer = mixer(priority);
+            for (thrust, torque) in commands {
+                let (thrust, torque) = mixer.mix(WEIGHT, thrust, torque);
+                for rotor in rotors(&mixer, thrust, torque) {
+                    assert!(
+                        (-EPSILON..=MAX_ROTOR + EPSILON).contains(&rotor),
+                        "{priority:?}: rotor at {rotor} N"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mixes_in_body_frame_when_yawed() {
+        // Yawed a quarter turn, the world pitch is a body roll and the world
+        // roll a body pitch, so the rotors' limits fall on the other axes
+        let rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let torque = Vec3::new(3.0, 0.2, 1.0);
+        let mixer = mixer(MixerPriority::DEFAULT);
+
+        let (thrust, mixed) = mixer.mix_world(WEIGHT, rotation, WEIGHT, torque);
+        assert!((mixed - torque).length() < EPSILON, "torque {mixed}");
+        for rotor in rotors(&mixer, thrust, rotation.inverse() * mixed) {
+            assert!(
+                (-EPSILON..=MAX_ROTOR + EPSILON).contains(&rotor),
+                "rotor at {rotor} N"
+            );
+        }
+
+        // Mixed as if it were a body torque, the thrust comes out different
+        let (unrotated, _) = mixer.mix(WEIGHT, WEIGHT, torque);
+        assert!((unrotated - thrust).abs() > 1.0, "thrust {thrust}");
+    }
+
+    #[test]
+    fn scales_back_to_what_fits() {
+        // Roll spreads the rotors by 4 N, so only a fifth of the yaw's 5 N
+        // spread still fits under the 5 N cap
+        let torque = Vec3::new(0.0, 0.5, 4.0);
+        let mixer = mixer(MixerPriority::DEFAULT);
+        let (thrust, mixed) = mixer.mix(WEIGHT, WEIGHT, torque);
+        // The search ends within one step of the largest scale that fits
+        let step = torque.y / (1 << FIT_ITERATIONS) as f32;
+        assert!(
+            mixed.y <= 0.1 + EPSILON && mixed.y > 0.1 - 2.0 * step,
+            "yaw {}",
+            mixed.y
+        );
+
+        let (low, high) = range(&rotors(&mixer, thrust, mixed));
+        assert!((high - low - MAX_ROTOR).abs() < EPSILON);
+    }
+
+    #[test]
+    fn fits_around_collective_once_set() {
+        // With the thrust placed first, the roll has to fit around the
+        // 2.5 N collective rather than shifting it
+        let mixer = mixer(MixerPriority::Order([
+            MixAxis::Thrust,
+            MixAxis::RollPitch,
+            MixAxis::Yaw,
+        ]));
+        let (thrust, torque) = mixer.mix(WEIGHT, WEIGHT, Vec3::new(0.0, 0.0, 8.0));
+        assert!((thrust - WEIGHT).abs() < EPSILON);
+        assert!((torque.z - 5.0).abs() < EPSILON, "roll {}", torque.z);
+    }
+}
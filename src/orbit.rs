@@ -0,0 +1,212 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::{
+    ActiveDrone, EngineState, PitchPid, RollPid, YawPid, angle_error, arg_value, chase_guidance,
+    follow_mouse::FollowCursor,
+    landing::LandingState,
+    mission::WaypointMission,
+    position::{PositionPid, release_tilt},
+    position_control,
+    return_home::ReturningHome,
+};
+
+/// Distance from the entry point at which the approach turns into the orbit (m).
+const ORBIT_CAPTURE_DIST: f32 = 1.0;
+/// How far ahead along the circle the position target leads the drone (s).
+const ORBIT_LEAD_TIME: f32 = 0.5;
+/// Fastest the heading setpoint turns towards the center (rad/s), so
+/// engaging the orbit doesn't step the yaw controller.
+const ORBIT_YAW_RATE: f32 = std::f32::consts::FRAC_PI_2;
+
+/// Point-of-interest orbit settings, shared by every orbit started with `Y`.
+#[derive(Resource)]
+pub struct OrbitSettings {
+    pub center: Vec3,
+    pub radius: f32,
+    pub altitude: f32,
+    /// Signed angular speed around the center (rad/s); positive is clockwise seen
+    /// from above.
+    pub angular_speed: f32,
+}
+
+impl OrbitSettings {
+    pub fn from_args() -> Self {
+        Self {
+            center: orbit_center_from_args(),
+            radius: arg_value("--orbit-radius")
+                .and_then(|radius| radius.parse::<f32>().ok())
+                .unwrap_or(8.0)
+                .max(1.0),
+            altitude: arg_value("--orbit-altitude")
+                .and_then(|altitude| altitude.parse::<f32>().ok())
+                .unwrap_or(5.0),
+            angular_speed: arg_value("--orbit-speed")
+                .and_then(|speed| speed.parse::<f32>().ok())
+                .unwrap_or(15.0)
+                .to_radians(),
+        }
+    }
+}
+
+fn orbit_center_from_args() -> Vec3 {
+    let default = Vec3::new(0.0, 1.0, -20.0);
+    let Some(value) = arg_value("--orbit-center") else {
+        return default;
+    };
+
+    let coordinates = value
+        .split(',')
+        .map(|c| c.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<_>>>();
+    match coordinates.as_deref() {
+        Some(&[x, y, z]) => Vec3::new(x, y, z),
+        _ => {
+            warn!("Invalid --orbit-center {value}, expected X,Y,Z");
+            default
+        }
+    }
+}
+
+/// Flies to the circle around `center`, then circles it at `radius` and
+/// `altitude` while yawing to keep the nose on the center.
+#[derive(Component)]
+pub struct OrbitPoi {
+    pub center: Vec3,
+    pub radius: f32,
+    pub altitude: f32,
+    pub angular_speed: f32,
+    /// Current position around the circle (rad, from +X towards +Z).
+    pub angle: f32,
+    /// Still flying to the entry point rather than circling.
+    pub approaching: bool,
+}
+
+impl OrbitPoi {
+    /// Orbit of `center` at `altitude` with the shared radius and speed,
+    /// joining the circle at the point nearest `position`.
+    pub fn joining(settings: &OrbitSettings, center: Vec3, altitude: f32, position: Vec3) -> Self {
+        let offset = position - center;
+        Self {
+            center,
+            radius: settings.radius,
+            altitude,
+            angular_speed: settings.angular_speed,
+            angle: offset.z.atan2(offset.x),
+            approaching: true,
+        }
+    }
+
+    pub fn point(&self, angle: f32) -> Vec3 {
+        Vec3::new(
+            self.center.x + self.radius * angle.cos(),
+            self.altitude,
+            self.center.z + self.radius * angle.sin(),
+        )
+    }
+}
+
+pub fn orbit_plugin(app: &mut App) {
+    app.insert_resource(OrbitSettings::from_args())
+        .add_systems(
+            Update,
+            (
+                toggle_orbit,
+                orbit_guidance
+                    .after(chase_guidance)
+                    .before(position_control)
+                    .run_if(in_state(EngineState::On)),
+            )
+                .chain(),
+        )
+        .add_systems(Update, draw_orbit);
+}
+
+#[allow(clippy::type_complexity)]
+pub fn toggle_orbit(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<OrbitSettings>,
+    mut drone_query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut PositionPid,
+            &mut PitchPid,
+            &mut RollPid,
+            Has<OrbitPoi>,
+        ),
+        With<ActiveDrone>,
+    >,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyY) {
+        return;
+    }
+
+    for (drone, tf, mut ctl_pos, mut ctl_pitch, mut ctl_roll, orbiting) in drone_query.iter_mut() {
+        if orbiting {
+            commands.entity(drone).remove::<OrbitPoi>();
+            ctl_pos.disengage();
+            release_tilt(&mut ctl_pitch, &mut ctl_roll);
+            continue;
+        }
+
+        let orbit = OrbitPoi::joining(
+            &settings,
+            settings.center,
+            settings.altitude,
+            tf.translation,
+        );
+        let tilt = Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle);
+        ctl_pos.engage(orbit.point(orbit.angle), tf.translation, tilt);
+        commands
+            .entity(drone)
+            .remove::<(WaypointMission, FollowCursor, LandingState, ReturningHome)>()
+            .insert(orbit);
+    }
+}
+
+pub fn orbit_guidance(
+    time: Res<Time>,
+    mut drone_query: Query<(&Transform, &mut OrbitPoi, &mut PositionPid, &mut YawPid)>,
+) {
+    let dt = time.delta_secs();
+
+    for (tf, mut orbit, mut ctl_pos, mut ctl_yaw) in drone_query.iter_mut() {
+        if orbit.approaching {
+            ctl_pos.target = orbit.point(orbit.angle);
+            orbit.approaching = tf.translation.distance(ctl_pos.target) > ORBIT_CAPTURE_DIST;
+        } else {
+            orbit.angle += orbit.angular_speed * dt;
+            ctl_pos.target = orbit.point(orbit.angle + orbit.angular_speed * ORBIT_LEAD_TIME);
+        }
+
+        // Keep the nose (-Z) pointed at the center
+        let to_center = orbit.center - tf.translation;
+        if to_center.x != 0.0 || to_center.z != 0.0 {
+            let heading = (-to_center.x).atan2(-to_center.z);
+            let step = ORBIT_YAW_RATE * dt;
+            ctl_yaw.target_angle += angle_error(heading, ctl_yaw.target_angle).clamp(-step, step);
+        }
+    }
+}
+
+pub fn draw_orbit(orbit_query: Query<&OrbitPoi>, mut gizmos: Gizmos) {
+    for orbit in orbit_query.iter() {
+        let color = if orbit.approaching {
+            Color::srgb(1.0, 0.8, 0.2)
+        } else {
+            Color::srgb(0.2, 1.0, 0.4)
+        };
+
+        // Circles are drawn in the XY plane; tip it flat onto XZ
+        let path = Isometry3d::new(
+            Vec3::new(orbit.center.x, orbit.altitude, orbit.center.z),
+            Quat::from_rotation_x(FRAC_PI_2),
+        );
+        gizmos.circle(path, orbit.radius, color).resolution(64);
+        gizmos.sphere(orbit.center, 0.3, color);
+        gizmos.line(orbit.center, orbit.point(orbit.angle), color);
+    }
+}
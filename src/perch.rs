@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{ActiveDrone, Disarmed, Drone, EngineState, arg_value, has_flag};
+
+/// Fastest the drone may still be moving (m/s) or turning (rad/s) while
+/// in contact for the contact to count as stable.
+const MAX_PERCH_SPEED: f32 = 0.2;
+const MAX_PERCH_SPIN: f32 = 0.5;
+
+/// Perch detection: the drone counts as perched once it has rested against a
+/// wall or sloped surface (not the ground) for `stable_time`, without moving.
+/// With `disarm` the engine is stopped as soon as it perches.
+#[derive(Resource)]
+pub struct PerchDetection {
+    pub enabled: bool,
+    /// Shallowest surface that can be perched on, measured from horizontal (rad).
+    pub min_slope: f32,
+    pub stable_time: f32,
+    pub disarm: bool,
+}
+
+impl PerchDetection {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: has_flag("--perch"),
+            min_slope: arg_value("--perch-min-slope")
+                .and_then(|slope| slope.parse::<f32>().ok())
+                .unwrap_or(30.0)
+                .clamp(0.0, 180.0)
+                .to_radians(),
+            stable_time: arg_value("--perch-time")
+                .and_then(|time| time.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .max(0.0),
+            disarm: has_flag("--perch-disarm"),
+        }
+    }
+}
+
+/// Per-drone perch state.
+#[derive(Component, Default)]
+pub struct Perch {
+    /// How long the drone has been resting against a perchable surface (s).
+    pub contact_time: f32,
+    /// Normal of the surface it's resting against, pointing out of it.
+    pub normal: Option<Vec3>,
+    pub perched: bool,
+}
+
+#[derive(Component)]
+pub struct PerchText;
+
+pub fn perch_plugin(app: &mut App) {
+    let perch = PerchDetection::from_args();
+    let enabled = perch.enabled;
+
+    app.insert_resource(perch).add_systems(
+        Update,
+        (add_perch, detect_perch, update_perch_text)
+            .chain()
+            .run_if(move || enabled),
+    );
+}
+
+pub fn add_perch(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<Perch>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(Perch::default());
+    }
+}
+
+pub fn detect_perch(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<PerchDetection>,
+    engine_state: Res<State<EngineState>>,
+    rapier_context: ReadRapierContext,
+    mut drone_query: Query<(Entity, &Velocity, &mut Perch)>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+    let dt = time.delta_secs();
+
+    for (drone, velocity, mut perch) in drone_query.iter_mut() {
+        // Surface normals of everything touched, pointing out of the surface
+        // towards the drone. Manifold normals point from collider 1 to 2.
+        let normal = context
+            .contact_pairs_with(drone)
+            .filter(|pair| pair.has_any_active_contact())
+            .flat_map(|pair| {
+                let sign = if pair.collider1() == Some(drone) {
+                    -1.0
+                } else {
+                    1.0
+                };
+                pair.manifolds()
+                    .map(|manifold| manifold.normal() * sign)
+                    .collect::<Vec<_>>()
+            })
+            .find(|normal| normal.y.clamp(-1.0, 1.0).acos() >= settings.min_slope);
+
+        let still =
+            velocity.linvel.length() < MAX_PERCH_SPEED && velocity.angvel.length() < MAX_PERCH_SPIN;
+        perch.normal = normal;
+        if normal.is_none() || !still {
+            perch.contact_time = 0.0;
+            if perch.perched {
+                perch.perched = false;
+                info!("Left the perch");
+            }
+            continue;
+        }
+
+        perch.contact_time += dt;
+        if !perch.perched && perch.contact_time >= settings.stable_time {
+            perch.perched = true;
+            info!("Perched");
+            if settings.disarm && *engine_state.get() == EngineState::On {
+                commands.entity(drone).insert(Disarmed);
+            }
+        }
+    }
+}
+
+pub fn update_perch_text(
+    settings: Res<PerchDetection>,
+    drone_query: Query<&Perch, With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<PerchText>>,
+) {
+    let Ok(perch) = drone_query.single() else {
+        return;
+    };
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = if perch.perched {
+            ("PERCHED".into(), Color::srgb(0.3, 1.0, 0.4))
+        } else if let Some(normal) = perch.normal {
+            let slope = normal.y.clamp(-1.0, 1.0).acos().to_degrees();
+            (
+                format!(
+                    "Perch: {slope:.0} deg {:.1}/{:.1} s",
+                    perch.contact_time, settings.stable_time
+                )
+                .into(),
+                Color::srgb(1.0, 0.8, 0.2),
+            )
+        } else {
+            ("Perch: -".into(), Color::WHITE)
+        };
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{ActiveDrone, Drone};
+
+const PANEL_KEY: KeyCode = KeyCode::F1;
+const BAR_WIDTH: f32 = 90.0;
+const BAR_HEIGHT: f32 = 10.0;
+
+/// One controller's state on the last step: what it measured against its
+/// setpoint, and what each term added to the output (m/s² for hover, rad/s²
+/// for the attitude axes).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AxisTerms {
+    pub measured: f32,
+    pub target: f32,
+    pub error: f32,
+    pub p: f32,
+    pub i: f32,
+    pub d: f32,
+    pub ff: f32,
+}
+
+impl AxisTerms {
+    pub fn new(measured: f32, target: f32, error: f32, p: f32, i: f32, d: f32, ff: f32) -> Self {
+        Self {
+            measured,
+            target,
+            error,
+            p,
+            i,
+            d,
+            ff,
+        }
+    }
+
+    fn terms(&self) -> [(&'static str, f32); 4] {
+        [("P", self.p), ("I", self.i), ("D", self.d), ("FF", self.ff)]
+    }
+}
+
+/// Per-term breakdown of the four controllers, set by `update_drone_forces`.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct PidTerms {
+    pub hover: AxisTerms,
+    pub pitch: AxisTerms,
+    pub roll: AxisTerms,
+    pub yaw: AxisTerms,
+}
+
+#[derive(Resource, Default)]
+pub struct PidDebugPanel {
+    pub open: bool,
+}
+
+pub fn pid_debug_plugin(app: &mut App) {
+    app.init_resource::<PidDebugPanel>()
+        .add_systems(Update, (add_pid_terms, toggle_pid_debug_panel))
+        .add_systems(EguiPrimaryContextPass, pid_debug_panel);
+}
+
+pub fn add_pid_terms(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<PidTerms>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(PidTerms::default());
+    }
+}
+
+pub fn toggle_pid_debug_panel(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut panel: ResMut<PidDebugPanel>,
+) {
+    if keyboard.just_pressed(PANEL_KEY) {
+        panel.open = !panel.open;
+    }
+}
+
+/// Measurement, setpoint and error of each axis of the selected drone, with
+/// the P, I, D and feedforward contributions as bars scaled to the largest
+/// term of the axis, so it's plain which one is doing the work.
+pub fn pid_debug_panel(
+    mut contexts: EguiContexts,
+    panel: Res<PidDebugPanel>,
+    drone_query: Query<&PidTerms, With<ActiveDrone>>,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+    let Ok(terms) = drone_query.single() else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Window::new("PID debug").show(ctx, |ui| {
+        egui::Grid::new("pid_debug")
+            .num_columns(8)
+            .striped(true)
+            .show(ui, |ui| {
+                for heading in ["", "meas", "target", "error", "P", "I", "D", "FF"] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+
+                axis_row(ui, "Alt (m)", &terms.hover, 1.0);
+                axis_row(ui, "Pitch (°)", &terms.pitch, 1.0_f32.to_degrees());
+                axis_row(ui, "Roll (°)", &terms.roll, 1.0_f32.to_degrees());
+                axis_row(ui, "Yaw (°)", &terms.yaw, 1.0_f32.to_degrees());
+            });
+    });
+
+    Ok(())
+}
+
+/// `scale` converts the measurement, setpoint and error for display.
+fn axis_row(ui: &mut egui::Ui, label: &str, axis: &AxisTerms, scale: f32) {
+    ui.label(label);
+    for value in [axis.measured, axis.target, axis.error] {
+        ui.monospace(format!("{:+8.2}", value * scale));
+    }
+
+    let largest = axis
+        .terms()
+        .iter()
+        .map(|(_, term)| term.abs())
+        .fold(0.0, f32::max);
+    for (name, term) in axis.terms() {
+        term_bar(ui, term, largest).on_hover_text(format!("{name} {term:+.3}"));
+    }
+    ui.end_row();
+}
+
+/// A bar growing right (green) or left (red) of the center line, by
+/// `value / largest` of the half width.
+fn term_bar(ui: &mut egui::Ui, value: f32, largest: f32) -> egui::Response {
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(BAR_WIDTH, BAR_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(40));
+
+    let center = rect.center().x;
+    let fraction = if largest > 0.0 { value / largest } else { 0.0 };
+    let end = center + fraction * rect.width() / 2.0;
+    let color = if value >= 0.0 {
+        egui::Color32::LIGHT_GREEN
+    } else {
+        egui::Color32::LIGHT_RED
+    };
+    painter.rect_filled(
+        egui::Rect::from_x_y_ranges(center.min(end)..=center.max(end), rect.y_range()),
+        0.0,
+        color,
+    );
+    painter.line_segment(
+        [rect.center_top(), rect.center_bottom()],
+        egui::Stroke::new(1.0, egui::Color32::GRAY),
+    );
+    response
+}
@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+
+use crate::{Drone, GRAVITY, HoverPid, PitchPid, RollPid};
+
+/// How long the position controller takes to blend in over the tilt
+/// setpoints it inherits when engaged (s).
+const HANDOVER_TIME: f32 = 0.5;
+
+/// Horizontal position controller. Turns the XZ position error into a desired
+/// acceleration and tilts the pitch/roll setpoints to produce it, while
+/// feeding the target altitude to the hover controller.
+#[derive(Component)]
+pub struct PositionPid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub prev_e: Vec2,
+    pub integral_e: Vec2,
+    pub target: Vec3,
+    pub enabled: bool,
+    /// Pitch/roll setpoints in effect when it was engaged, and how much of the
+    /// handover blend from them is left (s).
+    pub handover: Vec2,
+    pub handover_left: f32,
+    /// Acceleration the target itself is making (world XZ, m/s²), added to
+    /// the feedback so a moving reference is tracked without lag.
+    pub accel_feedforward: Vec2,
+}
+
+/// Where the drone was spawned, for return-to-home.
+#[derive(Component, Clone, Copy)]
+pub struct Home(pub Vec3);
+
+impl PositionPid {
+    /// Starts holding `target`, clearing the error history so the derivative
+    /// doesn't kick. `tilt` is the current (pitch, roll) setpoint, which is
+    /// blended into the controller's own over `HANDOVER_TIME` instead of
+    /// being replaced at once.
+    pub fn engage(&mut self, target: Vec3, current: Vec3, tilt: Vec2) {
+        self.target = target;
+        self.prev_e = Vec2::new(target.x - current.x, target.z - current.z);
+        self.integral_e = Vec2::ZERO;
+        self.enabled = true;
+        self.handover = tilt;
+        self.handover_left = HANDOVER_TIME;
+        self.accel_feedforward = Vec2::ZERO;
+    }
+
+    pub fn disengage(&mut self) {
+        self.enabled = false;
+        self.integral_e = Vec2::ZERO;
+        self.handover_left = 0.0;
+        self.accel_feedforward = Vec2::ZERO;
+    }
+}
+
+/// Hands the tilt setpoints back to the pilot after an autopilot lets go.
+/// They ease back to level like released sticks instead of snapping to zero.
+pub fn release_tilt(ctl_pitch: &mut PitchPid, ctl_roll: &mut RollPid) {
+    ctl_pitch.centering = true;
+    ctl_roll.centering = true;
+}
+
+pub fn position_control(
+    time: Res<Time>,
+    mut drone_query: Query<
+        (
+            &Transform,
+            &mut PositionPid,
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+        ),
+        With<Drone>,
+    >,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+
+    for (tf, mut ctl_pos, mut ctl_y, mut ctl_pitch, mut ctl_roll) in drone_query.iter_mut() {
+        if !ctl_pos.enabled {
+            continue;
+        }
+
+        let e = Vec2::new(
+            ctl_pos.target.x - tf.translation.x,
+            ctl_pos.target.z - tf.translation.z,
+        );
+        ctl_pos.integral_e += e * dt;
+
+        // Desired horizontal acceleration in world XZ
+        let a = ctl_pos.kp * e
+            + ctl_pos.ki * ctl_pos.integral_e
+            + ctl_pos.kd * (e - ctl_pos.prev_e) / dt
+            + ctl_pos.accel_feedforward;
+        ctl_pos.prev_e = e;
+
+        // Project onto the drone's heading: forward is -Z and right is +X in the body frame
+        let (yaw, _, _) = tf.rotation.to_euler(EulerRot::YXZ);
+        let forward = Vec2::new(-yaw.sin(), -yaw.cos());
+        let right = Vec2::new(yaw.cos(), -yaw.sin());
+
+        // Tilting the thrust by θ accelerates the drone by g·tan(θ); negative pitch/roll
+        // tilt towards forward/right. Limits are applied by `clamp_setpoints`
+        let mut tilt = Vec2::new(-a.dot(forward).atan2(GRAVITY), -a.dot(right).atan2(GRAVITY));
+
+        // Bumpless transfer: start from the setpoints it took over
+        if ctl_pos.handover_left > 0.0 {
+            let weight = ctl_pos.handover_left / HANDOVER_TIME;
+            tilt = tilt.lerp(ctl_pos.handover, weight);
+            ctl_pos.handover_left -= dt;
+        }
+        ctl_pitch.target_angle = tilt.x;
+        ctl_roll.target_angle = tilt.y;
+        ctl_pitch.centering = false;
+        ctl_roll.centering = false;
+
+        ctl_y.target_y = ctl_pos.target.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::{EngineState, build_app};
+
+    const FRAME: Duration = Duration::from_micros(16_667);
+    /// Largest setpoint change (rad) per frame that still counts as smooth.
+    const MAX_STEP: f32 = 0.05;
+
+    /// Headless sim hovering at 3 m with the pilot holding a tilt.
+    fn tilted_hover() -> App {
+        let mut app = build_app(true);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(FRAME));
+        app.update();
+        app.world_mut()
+            .resource_mut::<NextState<EngineState>>()
+            .set(EngineState::On);
+        for _ in 0..60 {
+            app.update();
+        }
+
+        let mut drone_query = app
+            .world_mut()
+            .query_filtered::<(&mut PitchPid, &mut RollPid), With<Drone>>();
+        for (mut ctl_pitch, mut ctl_roll) in drone_query.iter_mut(app.world_mut()) {
+            ctl_pitch.target_angle = -0.15;
+            ctl_roll.target_angle = 0.1;
+        }
+        app
+    }
+
+    fn tilt(app: &mut App) -> Vec2 {
+        let mut drone_query = app
+            .world_mut()
+            .query_filtered::<(&PitchPid, &RollPid), With<Drone>>();
+        let (ctl_pitch, ctl_roll) = drone_query.single(app.world()).unwrap();
+        Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle)
+    }
+
+    /// Runs `frames` frames and returns the largest change in the tilt
+    /// setpoints from one frame to the next, starting from `tilt`.
+    fn largest_step(app: &mut App, mut last: Vec2, frames: usize) -> f32 {
+        let mut largest = 0.0_f32;
+        for _ in 0..frames {
+            app.update();
+            let tilt = tilt(app);
+            largest = largest.max((tilt - last).abs().max_element());
+            last = tilt;
+        }
+        largest
+    }
+
+    fn engage(app: &mut App) {
+        let mut drone_query = app
+            .world_mut()
+            .query_filtered::<(&Transform, &mut PositionPid, &PitchPid, &RollPid), With<Drone>>();
+        for (tf, mut ctl_pos, ctl_pitch, ctl_roll) in drone_query.iter_mut(app.world_mut()) {
+            // Far enough away that the controller wants a full tilt
+            let target = tf.translation + Vec3::new(10.0, 0.0, 10.0);
+            let tilt = Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle);
+            ctl_pos.engage(target, tf.translation, tilt);
+        }
+    }
+
+    #[test]
+    fn engage_starts_from_inherited_tilt() {
+        let mut app = tilted_hover();
+        let inherited = tilt(&mut app);
+        engage(&mut app);
+
+        app.update();
+        assert!((tilt(&mut app) - inherited).length() < 1e-4);
+
+        let last = tilt(&mut app);
+        let step = largest_step(&mut app, last, 60);
+        assert!(step < MAX_STEP, "setpoint stepped {step} rad");
+    }
+
+    #[test]
+    fn disengage_eases_tilt_back() {
+        let mut app = tilted_hover();
+        engage(&mut app);
+        for _ in 0..30 {
+            app.update();
+        }
+        let held = tilt(&mut app);
+        assert!(held.length() > 0.1, "flying at {held}");
+
+        let mut drone_query = app
+            .world_mut()
+            .query_filtered::<(&mut PositionPid, &mut PitchPid, &mut RollPid), With<Drone>>();
+        let (mut ctl_pos, mut ctl_pitch, mut ctl_roll) =
+            drone_query.single_mut(app.world_mut()).unwrap();
+        ctl_pos.disengage();
+        release_tilt(&mut ctl_pitch, &mut ctl_roll);
+        let center_step = ctl_pitch.center_rate.max(ctl_roll.center_rate) * FRAME.as_secs_f32();
+
+        let step = largest_step(&mut app, held, 60);
+        assert!(step <= center_step + 1e-4, "setpoint stepped {step} rad");
+        assert_eq!(tilt(&mut app), Vec2::ZERO);
+    }
+}
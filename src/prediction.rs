@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{ActiveDrone, EngineState, GRAVITY, Wind, arg_value, has_flag};
+
+const PREDICTION_STEP: f32 = 0.05;
+
+/// Short look-ahead of where the active drone is heading, assuming the current
+/// thrust stays constant while gravity and air drag act on it.
+#[derive(Resource)]
+pub struct TrajectoryPrediction {
+    pub enabled: bool,
+    /// How far ahead to predict (s).
+    pub horizon: f32,
+}
+
+pub fn prediction_plugin(app: &mut App) {
+    app.insert_resource(TrajectoryPrediction {
+        enabled: has_flag("--predict"),
+        horizon: arg_value("--predict-horizon")
+            .and_then(|horizon| horizon.parse::<f32>().ok())
+            .unwrap_or(2.0)
+            .max(PREDICTION_STEP),
+    })
+    .add_systems(Update, (toggle_prediction, predict_trajectory).chain());
+}
+
+pub fn toggle_prediction(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut prediction: ResMut<TrajectoryPrediction>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyJ) {
+        prediction.enabled = !prediction.enabled;
+    }
+}
+
+/// Forward-Euler integration of the drone's translation, drawn as a gizmo arc.
+pub fn predict_trajectory(
+    prediction: Res<TrajectoryPrediction>,
+    wind: Res<Wind>,
+    engine_state: Res<State<EngineState>>,
+    drone_query: Query<
+        (&Transform, &Velocity, &ReadMassProperties, &ExternalForce),
+        With<ActiveDrone>,
+    >,
+    mut gizmos: Gizmos,
+) {
+    if !prediction.enabled {
+        return;
+    }
+
+    for (tf, velocity, mass_props, force) in drone_query.iter() {
+        if mass_props.mass <= 0.0 {
+            continue;
+        }
+        // Thrust and wind are only applied while the engine runs
+        let engine_on = *engine_state.get() == EngineState::On;
+        let thrust = if engine_on { force.force } else { Vec3::ZERO };
+
+        let mut position = tf.translation;
+        let mut linvel = velocity.linvel;
+        let mut points = vec![position];
+        let steps = (prediction.horizon / PREDICTION_STEP).ceil() as usize;
+
+        for _ in 0..steps {
+            let drag = if engine_on {
+                wind.drag(position.y, linvel, tf.rotation)
+            } else {
+                Vec3::ZERO
+            };
+            let accel = (thrust + drag) / mass_props.mass - Vec3::Y * GRAVITY;
+            linvel += accel * PREDICTION_STEP;
+            position += linvel * PREDICTION_STEP;
+            points.push(position);
+
+            // Stop at the ground
+            if position.y <= 0.0 {
+                break;
+            }
+        }
+
+        gizmos.linestrip(points, Color::srgb(0.2, 1.0, 0.9));
+    }
+}
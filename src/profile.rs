@@ -0,0 +1,277 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::{
+    ActiveDrone, Drone, HoverPid, PitchPid, RollPid, YawPid, arg_value,
+    compare::{GainSet, Gains},
+};
+
+const PROFILE_KEY: KeyCode = KeyCode::F11;
+
+/// Every gain a profile sets. The hover `kp` is the two ends of its altitude
+/// schedule, since `kp` itself is recomputed from them each frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GainSnapshot {
+    /// `[min_kp, max_kp, ki, kd]`
+    pub hover: [f32; 4],
+    /// `[kp, ki, kd]` per axis.
+    pub pitch: [f32; 3],
+    pub roll: [f32; 3],
+    pub yaw: [f32; 3],
+}
+
+impl GainSnapshot {
+    pub fn read(
+        ctl_y: &HoverPid,
+        ctl_pitch: &PitchPid,
+        ctl_roll: &RollPid,
+        ctl_yaw: &YawPid,
+    ) -> Self {
+        Self {
+            hover: [ctl_y.min_kp, ctl_y.max_kp, ctl_y.ki, ctl_y.kd],
+            pitch: [ctl_pitch.kp, ctl_pitch.ki, ctl_pitch.kd],
+            roll: [ctl_roll.kp, ctl_roll.ki, ctl_roll.kd],
+            yaw: [ctl_yaw.kp, ctl_yaw.ki, ctl_yaw.kd],
+        }
+    }
+
+    pub fn write(
+        &self,
+        ctl_y: &mut HoverPid,
+        ctl_pitch: &mut PitchPid,
+        ctl_roll: &mut RollPid,
+        ctl_yaw: &mut YawPid,
+    ) {
+        [ctl_y.min_kp, ctl_y.max_kp, ctl_y.ki, ctl_y.kd] = self.hover;
+        [ctl_pitch.kp, ctl_pitch.ki, ctl_pitch.kd] = self.pitch;
+        [ctl_roll.kp, ctl_roll.ki, ctl_roll.kd] = self.roll;
+        [ctl_yaw.kp, ctl_yaw.ki, ctl_yaw.kd] = self.yaw;
+    }
+
+    /// These gains with the axes `set` gives replaced, the way `--compare`
+    /// applies a gain set: the hover `kp` scales the schedule by its ratio to
+    /// `hover_kp`.
+    pub fn with(&self, set: &GainSet, hover_kp: f32) -> Self {
+        let axis =
+            |gains: Option<Gains>, current: [f32; 3]| gains.map_or(current, |g| [g.kp, g.ki, g.kd]);
+        let hover = set.hover.map_or(self.hover, |g| {
+            let scale = g.kp / hover_kp;
+            [self.hover[0] * scale, self.hover[1] * scale, g.ki, g.kd]
+        });
+        Self {
+            hover,
+            pitch: axis(set.pitch, self.pitch),
+            roll: axis(set.roll, self.roll),
+            yaw: axis(set.yaw, self.yaw),
+        }
+    }
+
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        fn mix<const N: usize>(a: [f32; N], b: [f32; N], t: f32) -> [f32; N] {
+            std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+        }
+        Self {
+            hover: mix(self.hover, other.hover, t),
+            pitch: mix(self.pitch, other.pitch, t),
+            roll: mix(self.roll, other.roll, t),
+            yaw: mix(self.yaw, other.yaw, t),
+        }
+    }
+}
+
+/// Tuning profiles to switch between in flight with `F11`: the gains at
+/// startup, then each gain set file listed in `--profiles a.ron,b.ron` (same
+/// format as `--compare`). A switch ramps the gains from where they are to the
+/// new profile over `ramp_time` instead of snapping, so it doesn't jolt the
+/// drone.
+#[derive(Resource)]
+pub struct TuningProfiles {
+    /// Name and gains of each profile after the startup one.
+    pub profiles: Vec<(String, GainSet)>,
+    /// Time a switch blends over (s); 0 switches at once.
+    pub ramp_time: f32,
+}
+
+impl TuningProfiles {
+    pub fn from_args() -> Self {
+        let profiles = arg_value("--profiles")
+            .map(|paths| {
+                paths
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .map(|path| {
+                        let name = Path::new(path)
+                            .file_stem()
+                            .map_or(path.to_string(), |stem| stem.to_string_lossy().into_owned());
+                        (name, GainSet::load(path))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            profiles,
+            ramp_time: arg_value("--gain-ramp")
+                .and_then(|time| time.parse::<f32>().ok())
+                .unwrap_or(2.0)
+                .max(0.0),
+        }
+    }
+
+    pub fn name(&self, profile: usize) -> &str {
+        match profile {
+            0 => "startup",
+            _ => &self.profiles[profile - 1].0,
+        }
+    }
+}
+
+/// A blend from one set of gains to another.
+#[derive(Clone, Copy, Debug)]
+pub struct GainRamp {
+    pub from: GainSnapshot,
+    pub to: GainSnapshot,
+    pub elapsed: f32,
+}
+
+/// A drone's profile state.
+#[derive(Component)]
+pub struct GainProfile {
+    /// Gains at startup, which the other profiles are applied on top of.
+    pub base: GainSnapshot,
+    /// Hover `kp` at startup, for scaling the schedule.
+    pub base_hover_kp: f32,
+    pub current: usize,
+    pub ramp: Option<GainRamp>,
+}
+
+#[derive(Component)]
+pub struct ProfileText;
+
+pub fn profile_plugin(app: &mut App) {
+    let profiles = TuningProfiles::from_args();
+    let enabled = !profiles.profiles.is_empty();
+
+    app.insert_resource(profiles).add_systems(
+        Update,
+        (
+            (add_gain_profile, switch_profile, ramp_gains)
+                .chain()
+                .run_if(move || enabled),
+            update_profile_text,
+        ),
+    );
+}
+
+#[allow(clippy::type_complexity)]
+pub fn add_gain_profile(
+    mut commands: Commands,
+    drone_query: Query<
+        (Entity, &HoverPid, &PitchPid, &RollPid, &YawPid),
+        (With<Drone>, Without<GainProfile>),
+    >,
+) {
+    for (drone, ctl_y, ctl_pitch, ctl_roll, ctl_yaw) in drone_query.iter() {
+        commands.entity(drone).insert(GainProfile {
+            base: GainSnapshot::read(ctl_y, ctl_pitch, ctl_roll, ctl_yaw),
+            base_hover_kp: ctl_y.kp,
+            current: 0,
+            ramp: None,
+        });
+    }
+}
+
+/// `F11` moves the active drone on to the next profile, starting the ramp
+/// from its gains as they are (slider tweaks or a ramp in progress included).
+pub fn switch_profile(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    profiles: Res<TuningProfiles>,
+    mut drone_query: Query<
+        (&mut GainProfile, &HoverPid, &PitchPid, &RollPid, &YawPid),
+        With<ActiveDrone>,
+    >,
+) {
+    if !keyboard.just_pressed(PROFILE_KEY) {
+        return;
+    }
+
+    for (mut profile, ctl_y, ctl_pitch, ctl_roll, ctl_yaw) in drone_query.iter_mut() {
+        profile.current = (profile.current + 1) % (profiles.profiles.len() + 1);
+        let to = match profile.current {
+            0 => profile.base,
+            i => profile
+                .base
+                .with(&profiles.profiles[i - 1].1, profile.base_hover_kp),
+        };
+        profile.ramp = Some(GainRamp {
+            from: GainSnapshot::read(ctl_y, ctl_pitch, ctl_roll, ctl_yaw),
+            to,
+            elapsed: 0.0,
+        });
+        info!(
+            "Switching to gain profile {} over {} s",
+            profiles.name(profile.current),
+            profiles.ramp_time
+        );
+    }
+}
+
+pub fn ramp_gains(
+    time: Res<Time>,
+    profiles: Res<TuningProfiles>,
+    mut drone_query: Query<(
+        &mut GainProfile,
+        &mut HoverPid,
+        &mut PitchPid,
+        &mut RollPid,
+        &mut YawPid,
+    )>,
+) {
+    for (mut profile, mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut()
+    {
+        let Some(ramp) = profile.ramp.as_mut() else {
+            continue;
+        };
+        ramp.elapsed += time.delta_secs();
+        let t = if profiles.ramp_time > 0.0 {
+            (ramp.elapsed / profiles.ramp_time).min(1.0)
+        } else {
+            1.0
+        };
+
+        ramp.from
+            .lerp(&ramp.to, t)
+            .write(&mut ctl_y, &mut ctl_pitch, &mut ctl_roll, &mut ctl_yaw);
+        if t >= 1.0 {
+            profile.ramp = None;
+        }
+    }
+}
+
+pub fn update_profile_text(
+    profiles: Res<TuningProfiles>,
+    drone_query: Query<&GainProfile, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<ProfileText>>,
+) {
+    let profile = drone_query.single().ok();
+
+    for mut text in text_query.iter_mut() {
+        *text = match profile {
+            _ if profiles.profiles.is_empty() => "Profile: -".into(),
+            None => "Profile: startup".into(),
+            Some(profile) => {
+                let name = profiles.name(profile.current);
+                match profile.ramp {
+                    Some(ramp) if profiles.ramp_time > 0.0 => format!(
+                        "Profile: {name} {:.0}%",
+                        (ramp.elapsed / profiles.ramp_time).min(1.0) * 100.0
+                    )
+                    .into(),
+                    _ => format!("Profile: {name}").into(),
+                }
+            }
+        };
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::{prelude::*, time::Stopwatch};
+
+use crate::gate::GatePassed;
+
+const MISSED_GATE_PENALTY: f32 = 2.0;
+
+#[derive(Component)]
+pub struct NextGateText;
+
+#[derive(Component)]
+pub struct LapTimeText;
+
+#[derive(Component)]
+pub struct BestLapText;
+
+#[derive(Component)]
+pub struct PenaltyText;
+
+/// Tracks a lap race through the level's gates.
+///
+/// `order[0]` is the start/finish gate. `next` is the position in `order` the
+/// drone is expected to fly through, where `order.len()` means the finish.
+#[derive(Resource, Default)]
+pub struct RaceState {
+    pub order: Vec<usize>,
+    pub passed: Vec<usize>,
+    pub started: bool,
+    pub next: usize,
+    pub lap: Stopwatch,
+    pub missed: usize,
+    pub lap_times: Vec<f32>,
+    pub best_lap: Option<f32>,
+}
+
+impl RaceState {
+    pub fn new(order: Vec<usize>) -> Self {
+        let mut lap = Stopwatch::new();
+        lap.pause();
+
+        Self {
+            order,
+            lap,
+            ..Default::default()
+        }
+    }
+
+    /// Clears the current race but keeps the session's best lap.
+    pub fn reset(&mut self) {
+        let best_lap = self.best_lap;
+        *self = Self::new(std::mem::take(&mut self.order));
+        self.best_lap = best_lap;
+    }
+
+    pub fn next_gate(&self) -> Option<usize> {
+        if self.order.is_empty() {
+            return None;
+        }
+        Some(self.order[self.next % self.order.len()])
+    }
+
+    pub fn penalty(&self) -> f32 {
+        self.missed as f32 * MISSED_GATE_PENALTY
+    }
+
+    pub fn gate_passed(&mut self, index: usize) {
+        self.passed.push(index);
+
+        let Some(pos) = self.order.iter().position(|&gate| gate == index) else {
+            return;
+        };
+
+        if !self.started {
+            if pos == 0 {
+                self.started = true;
+                self.next = 1;
+                self.lap.reset();
+                self.lap.unpause();
+            }
+            return;
+        }
+
+        // Crossing the start/finish gate counts as the end of the sequence
+        let pos = if pos == 0 { self.order.len() } else { pos };
+        if pos < self.next {
+            return;
+        }
+
+        self.missed += pos - self.next;
+
+        if pos == self.order.len() {
+            let lap_time = self.lap.elapsed_secs() + self.penalty();
+            self.lap_times.push(lap_time);
+            self.best_lap = Some(self.best_lap.map_or(lap_time, |best| best.min(lap_time)));
+
+            self.next = 1;
+            self.missed = 0;
+            self.lap.reset();
+        } else {
+            self.next = pos + 1;
+        }
+    }
+}
+
+pub fn track_race(
+    mut gate_passed: EventReader<GatePassed>,
+    mut race: ResMut<RaceState>,
+    time: Res<Time>,
+) {
+    race.lap.tick(time.delta());
+
+    for event in gate_passed.read() {
+        race.gate_passed(event.index);
+    }
+}
+
+pub fn reset_race(keyboard: Res<ButtonInput<KeyCode>>, mut race: ResMut<RaceState>) {
+    if keyboard.just_pressed(KeyCode::KeyG) {
+        race.reset();
+    }
+}
+
+pub fn update_next_gate_text(
+    race: Res<RaceState>,
+    mut text_query: Query<&mut Text, With<NextGateText>>,
+) {
+    for mut text in text_query.iter_mut() {
+        match race.next_gate() {
+            Some(gate) => {
+                *text = format!("Next Gate: {}/{}", gate + 1, race.order.len()).into();
+            }
+            None => *text = "Next Gate: -".into(),
+        }
+    }
+}
+
+pub fn update_lap_time_text(
+    race: Res<RaceState>,
+    mut text_query: Query<&mut Text, With<LapTimeText>>,
+) {
+    for mut text in text_query.iter_mut() {
+        *text = format!(
+            "Lap {}: {:.2} s",
+            race.lap_times.len() + 1,
+            race.lap.elapsed_secs()
+        )
+        .into();
+    }
+}
+
+pub fn update_best_lap_text(
+    race: Res<RaceState>,
+    mut text_query: Query<&mut Text, With<BestLapText>>,
+) {
+    for mut text in text_query.iter_mut() {
+        match race.best_lap {
+            Some(best) => *text = format!("Best Lap: {:.2} s", best).into(),
+            None => *text = "Best Lap: -".into(),
+        }
+    }
+}
+
+pub fn update_penalty_text(
+    race: Res<RaceState>,
+    mut text_query: Query<&mut Text, With<PenaltyText>>,
+) {
+    for mut text in text_query.iter_mut() {
+        *text = format!("Missed: {} (+{:.1} s)", race.missed, race.penalty()).into();
+    }
+}
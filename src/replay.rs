@@ -0,0 +1,344 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_rapier3d::prelude::*;
+
+use crate::{Drone, DroneId, arg_value, spawn_drone};
+
+const RECORDING_HEADER: &str = "time,drone,x,y,z,qx,qy,qz,qw";
+/// Header of a single saved track, which has no drone column.
+const TRACK_HEADER: &str = "time,x,y,z,qx,qy,qz,qw";
+/// Playback speeds `[` and `]` step through.
+const REPLAY_SPEEDS: [f32; 8] = [0.1, 0.25, 0.5, 1.0, 1.5, 2.0, 3.0, 5.0];
+const MIN_REPLAY_SPEED: f32 = 0.1;
+const MAX_REPLAY_SPEED: f32 = 5.0;
+
+pub struct PoseSample {
+    pub time: f32,
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+#[derive(Default)]
+pub struct FlightRecording {
+    pub samples: Vec<PoseSample>,
+}
+
+impl FlightRecording {
+    /// The first drone's track from a recording.
+    pub fn load(path: &str) -> Result<Self, String> {
+        Ok(Self::load_tracks(path)?
+            .into_values()
+            .next()
+            .unwrap_or_default())
+    }
+
+    /// Every drone's track from a recording, by `DroneId`. A file without a
+    /// drone column holds a single track, flown by drone 1.
+    pub fn load_tracks(path: &str) -> Result<BTreeMap<usize, Self>, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut tracks = BTreeMap::<usize, Self>::new();
+
+        for (i, line) in contents.lines().enumerate().skip(1) {
+            let mut values = line
+                .split(',')
+                .map(|value| value.trim().parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("line {}: {e}", i + 1))?;
+            // Single tracks have no drone column
+            let drone = if values.len() == 9 {
+                values.remove(1) as usize
+            } else {
+                1
+            };
+            let [time, x, y, z, qx, qy, qz, qw] = values[..] else {
+                return Err(format!("line {}: expected 8 or 9 columns", i + 1));
+            };
+
+            tracks.entry(drone).or_default().samples.push(PoseSample {
+                time,
+                translation: Vec3::new(x, y, z),
+                rotation: Quat::from_xyzw(qx, qy, qz, qw).normalize(),
+            });
+        }
+
+        Ok(tracks)
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{TRACK_HEADER}")?;
+        for sample in &self.samples {
+            let (t, q) = (sample.translation, sample.rotation);
+            writeln!(
+                writer,
+                "{:.4},{:.4},{:.4},{:.4},{:.5},{:.5},{:.5},{:.5}",
+                sample.time, t.x, t.y, t.z, q.x, q.y, q.z, q.w
+            )?;
+        }
+        writer.flush()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.samples.last().map_or(0.0, |sample| sample.time)
+    }
+
+    /// The latest sample recorded at or before `time`.
+    pub fn sample(&self, time: f32) -> Option<&PoseSample> {
+        let i = self.samples.partition_point(|sample| sample.time <= time);
+        self.samples.get(i.saturating_sub(1))
+    }
+
+    /// The pose at `time`, interpolated between the samples either side so
+    /// slow motion doesn't step from one recorded frame to the next.
+    pub fn pose(&self, time: f32) -> Option<(Vec3, Quat)> {
+        let i = self.samples.partition_point(|sample| sample.time <= time);
+        let before = self.samples.get(i.saturating_sub(1))?;
+        let Some(after) = self.samples.get(i).filter(|after| after.time > before.time) else {
+            return Some((before.translation, before.rotation));
+        };
+
+        let t = ((time - before.time) / (after.time - before.time)).clamp(0.0, 1.0);
+        Some((
+            before.translation.lerp(after.translation, t),
+            before.rotation.slerp(after.rotation, t),
+        ))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct FlightRecorder {
+    pub file: Option<BufWriter<File>>,
+    pub start: Option<f32>,
+}
+
+impl FlightRecorder {
+    pub fn new(path: Option<&str>) -> Self {
+        let file = path.and_then(|path| match File::create(path) {
+            Ok(file) => {
+                let mut writer = BufWriter::new(file);
+                let _ = writeln!(writer, "{RECORDING_HEADER}");
+                Some(writer)
+            }
+            Err(e) => {
+                warn!("Failed to create flight recording {path}: {e}");
+                None
+            }
+        });
+
+        Self { file, start: None }
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayScrubber {
+    /// Each drone's track, by `DroneId`.
+    pub tracks: BTreeMap<usize, FlightRecording>,
+    pub time: f32,
+    pub playing: bool,
+    /// Playback speed, as a multiple of real time.
+    pub speed: f32,
+}
+
+impl ReplayScrubber {
+    pub fn new(tracks: BTreeMap<usize, FlightRecording>) -> Self {
+        Self {
+            tracks,
+            time: 0.0,
+            playing: true,
+            speed: arg_value("--replay-speed")
+                .and_then(|speed| speed.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED),
+        }
+    }
+
+    /// Length of the longest track.
+    pub fn duration(&self) -> f32 {
+        self.tracks
+            .values()
+            .map(FlightRecording::duration)
+            .fold(0.0, f32::max)
+    }
+
+    /// Steps the speed to the next preset up (`faster`) or down.
+    pub fn step_speed(&mut self, faster: bool) {
+        let next = if faster {
+            REPLAY_SPEEDS.into_iter().find(|speed| *speed > self.speed)
+        } else {
+            REPLAY_SPEEDS
+                .into_iter()
+                .rev()
+                .find(|speed| *speed < self.speed)
+        };
+        if let Some(speed) = next {
+            self.speed = speed;
+        }
+    }
+}
+
+/// Replays `--replay <path>` with a scrub bar, otherwise records the flight
+/// to `--record <path>` when given.
+pub fn replay_plugin(app: &mut App) {
+    let Some(path) = arg_value("--replay") else {
+        app.insert_resource(FlightRecorder::new(arg_value("--record").as_deref()))
+            .add_systems(Update, record_flight);
+        return;
+    };
+
+    match FlightRecording::load_tracks(&path) {
+        Ok(tracks) => {
+            app.insert_resource(ReplayScrubber::new(tracks))
+                .add_systems(Startup, start_replay.after(spawn_drone))
+                .add_systems(
+                    Update,
+                    (change_replay_speed, advance_replay, apply_replay).chain(),
+                )
+                .add_systems(EguiPrimaryContextPass, replay_ui);
+        }
+        Err(e) => error!("Failed to load replay {path}: {e}"),
+    }
+}
+
+pub fn record_flight(
+    time: Res<Time>,
+    mut recorder: ResMut<FlightRecorder>,
+    drone_query: Query<(&DroneId, &Transform), With<Drone>>,
+) {
+    let now = time.elapsed_secs();
+    let start = *recorder.start.get_or_insert(now);
+    let Some(file) = recorder.file.as_mut() else {
+        return;
+    };
+
+    for (id, tf) in drone_query.iter() {
+        let (t, q) = (tf.translation, tf.rotation);
+        let _ = writeln!(
+            file,
+            "{:.4},{},{:.4},{:.4},{:.4},{:.5},{:.5},{:.5},{:.5}",
+            now - start,
+            id.0,
+            t.x,
+            t.y,
+            t.z,
+            q.x,
+            q.y,
+            q.z,
+            q.w
+        );
+    }
+    let _ = file.flush();
+}
+
+/// Hands the drones over to the replay: kinematic bodies follow their
+/// transform instead of being simulated.
+pub fn start_replay(
+    mut commands: Commands,
+    scrubber: Res<ReplayScrubber>,
+    drone_query: Query<(Entity, &DroneId), With<Drone>>,
+) {
+    for (drone, _) in drone_query.iter() {
+        commands
+            .entity(drone)
+            .insert(RigidBody::KinematicPositionBased);
+    }
+
+    let missing = scrubber
+        .tracks
+        .keys()
+        .filter(|id| !drone_query.iter().any(|(_, drone_id)| drone_id.0 == **id))
+        .count();
+    if missing > 0 {
+        warn!(
+            "Replay has tracks for {missing} more drone(s) than were spawned, \
+             run with --drones {}",
+            scrubber.tracks.len()
+        );
+    }
+}
+
+/// `[` slows the playback down, `]` speeds it up.
+pub fn change_replay_speed(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut scrubber: ResMut<ReplayScrubber>,
+) {
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        scrubber.step_speed(false);
+    }
+    if keyboard.just_pressed(KeyCode::BracketRight) {
+        scrubber.step_speed(true);
+    }
+}
+
+pub fn advance_replay(time: Res<Time>, mut scrubber: ResMut<ReplayScrubber>) {
+    if !scrubber.playing {
+        return;
+    }
+
+    let duration = scrubber.duration();
+    scrubber.time = (scrubber.time + time.delta_secs() * scrubber.speed).min(duration);
+    if scrubber.time >= duration {
+        scrubber.playing = false;
+    }
+}
+
+/// Puts every drone at its own track's pose. Drones the recording has no
+/// track for stay where they are.
+pub fn apply_replay(
+    scrubber: Res<ReplayScrubber>,
+    mut drone_query: Query<(&DroneId, &mut Transform), With<Drone>>,
+) {
+    for (id, mut tf) in drone_query.iter_mut() {
+        let Some((translation, rotation)) = scrubber
+            .tracks
+            .get(&id.0)
+            .and_then(|track| track.pose(scrubber.time))
+        else {
+            continue;
+        };
+        tf.translation = translation;
+        tf.rotation = rotation;
+    }
+}
+
+pub fn replay_ui(mut contexts: EguiContexts, mut scrubber: ResMut<ReplayScrubber>) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let duration = scrubber.duration();
+
+    egui::TopBottomPanel::bottom("replay").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            if ui.button("⏮").clicked() {
+                scrubber.time = 0.0;
+            }
+            let label = if scrubber.playing { "⏸" } else { "▶" };
+            if ui.button(label).clicked() {
+                if !scrubber.playing && scrubber.time >= duration {
+                    scrubber.time = 0.0;
+                }
+                scrubber.playing = !scrubber.playing;
+            }
+
+            if ui.button("⏪").clicked() {
+                scrubber.step_speed(false);
+            }
+            ui.label(format!("{:.2}x", scrubber.speed));
+            if ui.button("⏩").clicked() {
+                scrubber.step_speed(true);
+            }
+
+            ui.spacing_mut().slider_width = ui.available_width() - 120.0;
+            ui.add(
+                egui::Slider::new(&mut scrubber.time, 0.0..=duration)
+                    .suffix(" s")
+                    .fixed_decimals(2),
+            );
+        });
+    });
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+
+use crate::{
+    ActiveDrone, EngineState, PitchPid, RollPid, chase_guidance,
+    follow_mouse::FollowCursor,
+    landing::LandingState,
+    mission::WaypointMission,
+    orbit::OrbitPoi,
+    position::{Home, PositionPid, release_tilt},
+    position_control,
+};
+
+const RTH_KEY: KeyCode = KeyCode::KeyH;
+/// Horizontal distance from home at which the drone counts as back (m).
+const ARRIVAL_RADIUS: f32 = 0.5;
+
+/// Flies the drone back over its spawn point at the altitude it had when
+/// `H` was pressed, and holds there.
+#[derive(Component, Default)]
+pub struct ReturningHome {
+    pub arrived: bool,
+}
+
+#[derive(Component)]
+pub struct HomeText;
+
+pub fn return_home_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            toggle_return_home,
+            track_return_home
+                .after(chase_guidance)
+                .before(position_control)
+                .run_if(in_state(EngineState::On)),
+        )
+            .chain(),
+    )
+    .add_systems(Update, update_home_text);
+}
+
+#[allow(clippy::type_complexity)]
+pub fn toggle_return_home(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut drone_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Home,
+            &mut PositionPid,
+            &mut PitchPid,
+            &mut RollPid,
+            Has<ReturningHome>,
+        ),
+        With<ActiveDrone>,
+    >,
+) {
+    if !keyboard.just_pressed(RTH_KEY) {
+        return;
+    }
+
+    for (drone, tf, home, mut ctl_pos, mut ctl_pitch, mut ctl_roll, returning) in
+        drone_query.iter_mut()
+    {
+        if returning {
+            commands.entity(drone).remove::<ReturningHome>();
+            ctl_pos.disengage();
+            release_tilt(&mut ctl_pitch, &mut ctl_roll);
+            info!("Return to home cancelled");
+            continue;
+        }
+
+        // Come back at the current altitude so nothing in between gets clipped
+        let target = Vec3::new(home.0.x, tf.translation.y, home.0.z);
+        let tilt = Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle);
+        ctl_pos.engage(target, tf.translation, tilt);
+        info!(
+            "Returning home, {:.1} m away",
+            Vec2::new(target.x - tf.translation.x, target.z - tf.translation.z).length()
+        );
+        commands
+            .entity(drone)
+            .remove::<(WaypointMission, OrbitPoi, FollowCursor, LandingState)>()
+            .insert(ReturningHome::default());
+    }
+}
+
+pub fn track_return_home(mut drone_query: Query<(&Transform, &PositionPid, &mut ReturningHome)>) {
+    for (tf, ctl_pos, mut returning) in drone_query.iter_mut() {
+        let distance = Vec2::new(
+            ctl_pos.target.x - tf.translation.x,
+            ctl_pos.target.z - tf.translation.z,
+        )
+        .length();
+        if !returning.arrived && distance < ARRIVAL_RADIUS {
+            returning.arrived = true;
+            info!("Home, holding position");
+        }
+    }
+}
+
+pub fn update_home_text(
+    drone_query: Query<(&Transform, &Home, Option<&ReturningHome>), With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<HomeText>>,
+) {
+    let Ok((tf, home, returning)) = drone_query.single() else {
+        return;
+    };
+    let distance = Vec2::new(home.0.x - tf.translation.x, home.0.z - tf.translation.z).length();
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = match returning {
+            Some(returning) if returning.arrived => {
+                ("RTH: holding".into(), Color::srgb(0.3, 1.0, 0.4))
+            }
+            Some(_) => (
+                format!("RTH: {distance:.1} m").into(),
+                Color::srgb(1.0, 0.8, 0.2),
+            ),
+            None => (format!("Home: {distance:.1} m").into(), Color::WHITE),
+        };
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    Drone, EngineState, HoverPid, PROPELLER_OFFSETS, PitchPid, Propeller, RollPid, Wind, YawPid,
+    level::Level, position::Home, spawn_gates,
+};
+
+/// Everything spawned from the level file, despawned when it is reloaded.
+#[derive(Component)]
+pub struct SceneEntity;
+
+/// Stops the engine and puts every drone back on its spawn point, as `Shift+F9` does.
+#[derive(Event)]
+pub struct ResetDrones;
+
+pub fn scene_plugin(app: &mut App) {
+    app.add_event::<ResetDrones>()
+        .add_systems(Update, (reload_scene, reset_drones));
+}
+
+/// `F9` re-reads the level file and rebuilds the gates and wind from it, so a
+/// level can be edited while the app runs. The drones are left where they
+/// are; `Shift+F9` also puts them back on their spawn points.
+pub fn reload_scene(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut level: ResMut<Level>,
+    mut wind: ResMut<Wind>,
+    scene_query: Query<Entity, With<SceneEntity>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    for entity in scene_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    *level = Level::load(&level.path);
+    wind.set_layers(level.wind.clone());
+    // Runs once the despawns above have been applied; also restarts the race
+    commands.run_system_cached(spawn_gates);
+    info!(
+        "Reloaded level {} ({} gates)",
+        level.path,
+        level.gates.len()
+    );
+}
+
+/// With `Shift+F9` or a `ResetDrones` event, stops the engine and puts every
+/// drone back on its spawn point.
+#[allow(clippy::type_complexity)]
+pub fn reset_drones(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut reset_events: EventReader<ResetDrones>,
+    mut next_engine_state: ResMut<NextState<EngineState>>,
+    mut drone_query: Query<
+        (
+            Entity,
+            &Home,
+            &mut Transform,
+            &mut Velocity,
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+            &mut YawPid,
+        ),
+        (With<Drone>, Without<Propeller>),
+    >,
+    mut propeller_query: Query<(&Propeller, &ImpulseJoint, &mut Transform), Without<Drone>>,
+) {
+    let requested = reset_events.read().count() > 0;
+    let pressed = keyboard.just_pressed(KeyCode::F9)
+        && keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !requested && !pressed {
+        return;
+    }
+
+    next_engine_state.set(EngineState::Off);
+    for (drone, home, mut tf, mut velocity, mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in
+        drone_query.iter_mut()
+    {
+        *tf = Transform::from_translation(home.0);
+        *velocity = Velocity::zero();
+        ctl_y.target_y = home.0.y;
+        ctl_pitch.target_angle = 0.0;
+        ctl_roll.target_angle = 0.0;
+        ctl_yaw.target_angle = 0.0;
+        ctl_y.integral_e = 0.0;
+        ctl_pitch.integral_e = 0.0;
+        ctl_roll.integral_e = 0.0;
+        ctl_yaw.integral_e = 0.0;
+
+        // Move the propellers along so the joints don't yank them back
+        for (propeller, joint, mut prop_tf) in propeller_query.iter_mut() {
+            if joint.parent == drone {
+                *prop_tf = Transform::from_translation(home.0 + PROPELLER_OFFSETS[propeller.0]);
+            }
+        }
+    }
+}
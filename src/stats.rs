@@ -0,0 +1,228 @@
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+};
+
+use bevy::prelude::*;
+
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    Drone, EngineState, GRAVITY, HoverPid, PitchPid, RollPid, RotorLag, YawPid, arg_value,
+    energy::EnergyBudget, mixer::Mixer,
+};
+
+const STATS_HEADER: &str = "duration,rms_altitude_error,max_altitude_error,mean_abs_pitch_error,mean_abs_roll_error,mean_abs_yaw_error,thrust_variation,torque_effort,energy,saturation_events";
+
+/// Time-weighted tracking error totals while the engine is on, and the
+/// control effort spent on them.
+#[derive(Resource, Default)]
+pub struct TrackingStats {
+    pub path: Option<String>,
+    pub duration: f32,
+    pub sq_altitude_error: f32,
+    pub max_altitude_error: f32,
+    pub abs_pitch_error: f32,
+    pub abs_roll_error: f32,
+    pub abs_yaw_error: f32,
+    /// Sum of |thrust change| between steps (N); low for smooth throttle.
+    pub thrust_variation: f32,
+    /// Integral of the applied torque magnitude (N·m·s).
+    pub torque_effort: f32,
+    /// Electrical energy drawn, from the energy budget's power model (J).
+    pub energy: f32,
+    /// Times a drone's command went from fitting the rotors to saturating them.
+    pub saturation_events: usize,
+}
+
+/// Per-drone state the effort totals are taken from.
+#[derive(Component, Default)]
+pub struct EffortTracker {
+    pub prev_thrust: Option<f32>,
+    pub saturated: bool,
+}
+
+impl TrackingStats {
+    pub fn new(path: Option<String>) -> Self {
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.path.take());
+    }
+
+    pub fn rms_altitude_error(&self) -> f32 {
+        self.mean(self.sq_altitude_error).sqrt()
+    }
+
+    fn mean(&self, total: f32) -> f32 {
+        if self.duration > 0.0 {
+            total / self.duration
+        } else {
+            0.0
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{:.3},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.5},{:.3},{}",
+            self.duration,
+            self.rms_altitude_error(),
+            self.max_altitude_error,
+            self.mean(self.abs_pitch_error).to_degrees(),
+            self.mean(self.abs_roll_error).to_degrees(),
+            self.mean(self.abs_yaw_error).to_degrees(),
+            self.thrust_variation,
+            self.torque_effort,
+            self.energy,
+            self.saturation_events,
+        )
+    }
+
+    pub fn report(&self) {
+        info!(
+            "Tracking over {:.1}s: altitude RMS {:.3} m, max {:.3} m | mean |error| pitch {:.2} deg, roll {:.2} deg, yaw {:.2} deg",
+            self.duration,
+            self.rms_altitude_error(),
+            self.max_altitude_error,
+            self.mean(self.abs_pitch_error).to_degrees(),
+            self.mean(self.abs_roll_error).to_degrees(),
+            self.mean(self.abs_yaw_error).to_degrees(),
+        );
+        info!(
+            "Control effort: thrust variation {:.3} N, torque {:.4} N·m·s, energy {:.1} J, {} saturation events",
+            self.thrust_variation, self.torque_effort, self.energy, self.saturation_events,
+        );
+
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        // Append so repeated runs (e.g. a gain sweep) end up in one table
+        let file = OpenOptions::new().create(true).append(true).open(path);
+        match file {
+            Ok(file) => {
+                let is_empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+                let mut writer = BufWriter::new(file);
+                if is_empty {
+                    let _ = writeln!(writer, "{STATS_HEADER}");
+                }
+                let _ = writeln!(writer, "{}", self.csv_row());
+            }
+            Err(e) => warn!("Failed to open stats file {path}: {e}"),
+        }
+    }
+}
+
+pub fn stats_plugin(app: &mut App) {
+    app.insert_resource(TrackingStats::new(arg_value("--stats")))
+        .add_systems(
+            Update,
+            (add_effort_tracker, accumulate_stats, accumulate_effort)
+                .chain()
+                .run_if(in_state(EngineState::On)),
+        )
+        .add_systems(OnExit(EngineState::On), reset_effort_trackers)
+        .add_systems(Update, stats_control)
+        .add_systems(Last, report_stats_on_exit);
+}
+
+/// Tallies the errors the controllers just acted on.
+pub fn accumulate_stats(
+    time: Res<Time>,
+    mut stats: ResMut<TrackingStats>,
+    drone_query: Query<(&HoverPid, &PitchPid, &RollPid, &YawPid), With<Drone>>,
+) {
+    let dt = time.delta_secs();
+
+    for (ctl_y, ctl_pitch, ctl_roll, ctl_yaw) in drone_query.iter() {
+        stats.duration += dt;
+        stats.sq_altitude_error += ctl_y.prev_e * ctl_y.prev_e * dt;
+        stats.max_altitude_error = stats.max_altitude_error.max(ctl_y.prev_e.abs());
+        stats.abs_pitch_error += ctl_pitch.prev_e.abs() * dt;
+        stats.abs_roll_error += ctl_roll.prev_e.abs() * dt;
+        stats.abs_yaw_error += ctl_yaw.prev_e.abs() * dt;
+    }
+}
+
+pub fn add_effort_tracker(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<EffortTracker>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(EffortTracker::default());
+    }
+}
+
+/// Tallies the effort of the forces just applied.
+#[allow(clippy::type_complexity)]
+pub fn accumulate_effort(
+    time: Res<Time>,
+    mixer: Res<Mixer>,
+    energy: Res<EnergyBudget>,
+    mut stats: ResMut<TrackingStats>,
+    mut drone_query: Query<
+        (
+            &HoverPid,
+            &PitchPid,
+            &RollPid,
+            &YawPid,
+            Option<&RotorLag>,
+            &ExternalForce,
+            &ReadMassProperties,
+            &Transform,
+            &mut EffortTracker,
+        ),
+        With<Drone>,
+    >,
+) {
+    let dt = time.delta_secs();
+
+    for (ctl_y, ctl_pitch, ctl_roll, ctl_yaw, rotor_lag, force, mass_props, tf, mut tracker) in
+        drone_query.iter_mut()
+    {
+        let thrust = force.force.length();
+        if let Some(prev) = tracker.prev_thrust {
+            stats.thrust_variation += (thrust - prev).abs();
+        }
+        tracker.prev_thrust = Some(thrust);
+        stats.torque_effort += force.torque.length() * dt;
+        stats.energy += energy.model.power(thrust) * dt;
+
+        // The command as it went into the mixer, in the body frame
+        let yaw_torque = rotor_lag.map_or(ctl_yaw.output, |lag| lag.yaw_torque);
+        let torque = Vec3::new(ctl_pitch.output, yaw_torque, ctl_roll.output);
+        let torque = tf.rotation.inverse() * torque;
+        let saturated = mixer.saturates(mass_props.mass * GRAVITY, ctl_y.output, torque);
+        if saturated && !tracker.saturated {
+            stats.saturation_events += 1;
+        }
+        tracker.saturated = saturated;
+    }
+}
+
+/// The engine restarting isn't a thrust change of the controllers' making.
+pub fn reset_effort_trackers(mut tracker_query: Query<&mut EffortTracker>) {
+    for mut tracker in tracker_query.iter_mut() {
+        *tracker = EffortTracker::default();
+    }
+}
+
+pub fn stats_control(keyboard: Res<ButtonInput<KeyCode>>, mut stats: ResMut<TrackingStats>) {
+    if keyboard.just_pressed(KeyCode::KeyO) {
+        stats.report();
+    }
+    if keyboard.just_pressed(KeyCode::KeyU) {
+        stats.reset();
+        info!("Tracking stats reset");
+    }
+}
+
+pub fn report_stats_on_exit(mut exit: EventReader<AppExit>, stats: Res<TrackingStats>) {
+    if exit.read().next().is_some() {
+        stats.report();
+    }
+}
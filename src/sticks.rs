@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+
+use crate::{
+    ActiveDrone, Delay, HoverPid, PitchPid, RollPid, YawPid, angle_error, has_flag, smooth_output,
+    spawn_ui,
+};
+
+const BOX_SIZE: f32 = 120.;
+const DOT_SIZE: f32 = 14.;
+/// Time constant used to smooth the rate-derived throttle and yaw sticks, which
+/// would otherwise flicker with the stepped target updates.
+const RATE_STICK_TAU: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StickSide {
+    /// Throttle (vertical) and yaw (horizontal).
+    Left,
+    /// Pitch (vertical) and roll (horizontal).
+    Right,
+}
+
+/// Dot inside a stick box, positioned from the active PID targets.
+#[derive(Component)]
+pub struct StickDot(pub StickSide);
+
+#[derive(Component)]
+pub struct StickDisplay;
+
+/// Virtual transmitter stick positions, each axis in `[-1, 1]`.
+#[derive(Resource, Default)]
+pub struct Sticks {
+    pub throttle: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    prev_target_y: Option<f32>,
+    prev_target_yaw: Option<f32>,
+}
+
+pub fn sticks_plugin(app: &mut App) {
+    app.init_resource::<Sticks>()
+        .add_systems(Startup, spawn_sticks.after(spawn_ui))
+        .add_systems(
+            Update,
+            (toggle_sticks, update_sticks, update_stick_dots).chain(),
+        );
+}
+
+pub fn spawn_sticks(mut commands: Commands) {
+    let visibility = if has_flag("--sticks") {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    commands
+        .spawn((
+            StickDisplay,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(20.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(40.),
+                ..Default::default()
+            },
+            visibility,
+        ))
+        .with_children(|parent| {
+            for side in [StickSide::Left, StickSide::Right] {
+                parent.spawn(stick_box()).with_children(|parent| {
+                    parent.spawn(crosshair_line(true));
+                    parent.spawn(crosshair_line(false));
+                    parent.spawn((
+                        StickDot(side),
+                        Node {
+                            position_type: PositionType::Absolute,
+                            width: Val::Px(DOT_SIZE),
+                            height: Val::Px(DOT_SIZE),
+                            left: Val::Percent(50.),
+                            top: Val::Percent(50.),
+                            margin: UiRect::new(
+                                Val::Px(-DOT_SIZE / 2.),
+                                Val::ZERO,
+                                Val::Px(-DOT_SIZE / 2.),
+                                Val::ZERO,
+                            ),
+                            ..Default::default()
+                        },
+                        BorderRadius::MAX,
+                        BackgroundColor(Color::srgb(1., 0.8, 0.)),
+                    ));
+                });
+            }
+        });
+}
+
+fn stick_box() -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(BOX_SIZE),
+            height: Val::Px(BOX_SIZE),
+            border: UiRect::all(Val::Px(2.)),
+            ..Default::default()
+        },
+        BorderColor(Color::WHITE),
+        BackgroundColor(Color::srgba(0., 0., 0., 0.6)),
+    )
+}
+
+fn crosshair_line(horizontal: bool) -> impl Bundle {
+    let (width, height, left, top) = if horizontal {
+        (
+            Val::Percent(100.),
+            Val::Px(1.),
+            Val::ZERO,
+            Val::Percent(50.),
+        )
+    } else {
+        (
+            Val::Px(1.),
+            Val::Percent(100.),
+            Val::Percent(50.),
+            Val::ZERO,
+        )
+    };
+
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            width,
+            height,
+            left,
+            top,
+            ..Default::default()
+        },
+        BackgroundColor(Color::srgba(1., 1., 1., 0.3)),
+    )
+}
+
+pub fn toggle_sticks(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut display_query: Query<&mut Visibility, With<StickDisplay>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyX) {
+        return;
+    }
+
+    for mut visibility in display_query.iter_mut() {
+        visibility.toggle_inherited_hidden();
+    }
+}
+
+/// Derives stick positions from the controller targets: pitch and roll are
+/// attitude setpoints, while throttle and yaw are how fast the altitude and
+/// heading targets are currently moving relative to their full rate.
+pub fn update_sticks(
+    time: Res<Time>,
+    delay: Res<Delay>,
+    mut sticks: ResMut<Sticks>,
+    drone_query: Query<(&HoverPid, &PitchPid, &RollPid, &YawPid), With<ActiveDrone>>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+    let step = delay.timer.duration().as_secs_f32();
+
+    for (ctl_y, ctl_pitch, ctl_roll, ctl_yaw) in drone_query.iter() {
+        // Pushing the right stick forward pitches nose-down (negative target),
+        // pushing it right rolls right (negative target).
+        sticks.pitch = (-ctl_pitch.target_angle / ctl_pitch.max_angle.abs()).clamp(-1.0, 1.0);
+        sticks.roll = (-ctl_roll.target_angle / ctl_roll.max_angle.abs()).clamp(-1.0, 1.0);
+
+        let prev_y = sticks.prev_target_y.replace(ctl_y.target_y);
+        let climb = prev_y.map_or(0.0, |prev| (ctl_y.target_y - prev) / dt);
+        let throttle = (climb * step / ctl_y.v_rate).clamp(-1.0, 1.0);
+        sticks.throttle = smooth_output(true, sticks.throttle, throttle, RATE_STICK_TAU, dt);
+
+        let prev_yaw = sticks.prev_target_yaw.replace(ctl_yaw.target_angle);
+        let turn = prev_yaw.map_or(0.0, |prev| angle_error(ctl_yaw.target_angle, prev) / dt);
+        let yaw = (turn * step / ctl_yaw.angle_rate).clamp(-1.0, 1.0);
+        sticks.yaw = smooth_output(true, sticks.yaw, yaw, RATE_STICK_TAU, dt);
+    }
+}
+
+pub fn update_stick_dots(sticks: Res<Sticks>, mut dot_query: Query<(&StickDot, &mut Node)>) {
+    for (dot, mut node) in dot_query.iter_mut() {
+        let (x, y) = match dot.0 {
+            StickSide::Left => (sticks.yaw, sticks.throttle),
+            StickSide::Right => (sticks.roll, sticks.pitch),
+        };
+
+        node.left = Val::Percent(50. + 50. * x);
+        node.top = Val::Percent(50. - 50. * y);
+    }
+}
@@ -0,0 +1,257 @@
+use std::{f32::consts::TAU, fs::File, io::Write};
+
+use bevy::prelude::*;
+
+use crate::{
+    ActiveDrone, EngineState, HoverPid, PitchPid, RollPid, YawPid, angle_error, arg_value,
+    clamp_setpoints,
+};
+
+/// Frequencies the response is evaluated at, log-spaced over the sweep.
+const BODE_POINTS: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChirpAxis {
+    Hover,
+    Pitch,
+    Roll,
+    Yaw,
+}
+
+impl ChirpAxis {
+    fn from_args() -> Self {
+        match arg_value("--chirp-axis").as_deref() {
+            None | Some("pitch") => Self::Pitch,
+            Some("roll") => Self::Roll,
+            Some("yaw") => Self::Yaw,
+            Some("hover") => Self::Hover,
+            Some(other) => {
+                warn!("Unknown --chirp-axis {other}, expected hover, pitch, roll or yaw");
+                Self::Pitch
+            }
+        }
+    }
+}
+
+/// One frame of the test: time, excitation added to the setpoint and the
+/// measured deviation from where the setpoint was before the test.
+#[derive(Clone, Copy)]
+struct ChirpSample {
+    t: f32,
+    dt: f32,
+    input: f32,
+    output: f32,
+}
+
+/// Frequency-sweep identification of one closed-loop axis. `F5` sweeps the
+/// axis setpoint with a logarithmic chirp from `start` to `end` Hz; with
+/// `--bode <file.csv>` the recorded setpoint and response are turned into a
+/// frequency response and written out ready to plot as a Bode diagram.
+#[derive(Resource)]
+pub struct ChirpTest {
+    pub axis: ChirpAxis,
+    /// Excitation amplitude (rad, or m for hover).
+    pub amplitude: f32,
+    pub start: f32,
+    pub end: f32,
+    pub duration: f32,
+    pub export_path: Option<String>,
+    pub running: bool,
+    pub elapsed: f32,
+    pub phase: f32,
+    /// Setpoint the excitation is added to.
+    pub base: f32,
+    samples: Vec<ChirpSample>,
+}
+
+impl ChirpTest {
+    pub fn from_args() -> Self {
+        let axis = ChirpAxis::from_args();
+        let amplitude = arg_value("--chirp-amplitude")
+            .and_then(|amplitude| amplitude.parse::<f32>().ok())
+            .unwrap_or(if axis == ChirpAxis::Hover { 0.5 } else { 5.0 })
+            .abs();
+        let start = arg_value("--chirp-start")
+            .and_then(|f| f.parse::<f32>().ok())
+            .unwrap_or(0.1)
+            .max(0.01);
+
+        Self {
+            axis,
+            // Angles are given in degrees, the hover amplitude in meters
+            amplitude: if axis == ChirpAxis::Hover {
+                amplitude
+            } else {
+                amplitude.to_radians()
+            },
+            start,
+            end: arg_value("--chirp-end")
+                .and_then(|f| f.parse::<f32>().ok())
+                .unwrap_or(5.0)
+                .max(start),
+            duration: arg_value("--chirp-duration")
+                .and_then(|duration| duration.parse::<f32>().ok())
+                .unwrap_or(30.0)
+                .max(1.0),
+            export_path: arg_value("--bode"),
+            running: false,
+            elapsed: 0.0,
+            phase: 0.0,
+            base: 0.0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Instantaneous sweep frequency (Hz).
+    pub fn frequency(&self) -> f32 {
+        self.start * (self.end / self.start).powf(self.elapsed / self.duration)
+    }
+}
+
+pub fn sysid_plugin(app: &mut App) {
+    app.insert_resource(ChirpTest::from_args())
+        .add_systems(
+            Update,
+            (start_chirp, run_chirp)
+                .chain()
+                .after(clamp_setpoints)
+                .run_if(in_state(EngineState::On)),
+        )
+        .add_systems(OnExit(EngineState::On), abort_chirp);
+}
+
+pub fn abort_chirp(mut chirp: ResMut<ChirpTest>) {
+    if chirp.running {
+        chirp.running = false;
+        warn!("Chirp aborted, engine stopped");
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn start_chirp(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut chirp: ResMut<ChirpTest>,
+    drone_query: Query<(&HoverPid, &PitchPid, &RollPid, &YawPid), With<ActiveDrone>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F5) || chirp.running {
+        return;
+    }
+
+    for (ctl_y, ctl_pitch, ctl_roll, ctl_yaw) in drone_query.iter() {
+        chirp.base = match chirp.axis {
+            ChirpAxis::Hover => ctl_y.target_y,
+            ChirpAxis::Pitch => ctl_pitch.target_angle,
+            ChirpAxis::Roll => ctl_roll.target_angle,
+            ChirpAxis::Yaw => ctl_yaw.target_angle,
+        };
+        chirp.running = true;
+        chirp.elapsed = 0.0;
+        chirp.phase = 0.0;
+        chirp.samples.clear();
+        info!(
+            "Chirp on {:?}: {:.2}-{:.2} Hz over {:.0}s",
+            chirp.axis, chirp.start, chirp.end, chirp.duration
+        );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn run_chirp(
+    time: Res<Time>,
+    mut chirp: ResMut<ChirpTest>,
+    mut drone_query: Query<
+        (
+            &Transform,
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+            &mut YawPid,
+        ),
+        With<ActiveDrone>,
+    >,
+) {
+    if !chirp.running {
+        return;
+    }
+
+    let dt = time.delta_secs();
+    let done = chirp.elapsed >= chirp.duration;
+    let excitation = if done {
+        0.0
+    } else {
+        chirp.amplitude * chirp.phase.sin()
+    };
+
+    for (tf, mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        let (yaw, pitch, roll) = tf.rotation.to_euler(EulerRot::YXZ);
+        let base = chirp.base;
+        let (setpoint, output) = match chirp.axis {
+            ChirpAxis::Hover => (&mut ctl_y.target_y, tf.translation.y - base),
+            ChirpAxis::Pitch => (&mut ctl_pitch.target_angle, angle_error(pitch, base)),
+            ChirpAxis::Roll => (&mut ctl_roll.target_angle, angle_error(roll, base)),
+            ChirpAxis::Yaw => (&mut ctl_yaw.target_angle, angle_error(yaw, base)),
+        };
+        *setpoint = base + excitation;
+
+        let t = chirp.elapsed;
+        chirp.samples.push(ChirpSample {
+            t,
+            dt,
+            input: excitation,
+            output,
+        });
+    }
+
+    if !done {
+        chirp.phase = (chirp.phase + TAU * chirp.frequency() * dt) % TAU;
+        chirp.elapsed += dt;
+        return;
+    }
+
+    chirp.running = false;
+    info!("Chirp finished, {} samples", chirp.samples.len());
+    if let Some(path) = chirp.export_path.clone() {
+        match export_bode(&chirp, &path) {
+            Ok(()) => info!("Frequency response written to {path}"),
+            Err(err) => warn!("Failed to write frequency response to {path}: {err}"),
+        }
+    }
+}
+
+/// Fourier coefficient (re, im) of `signal` at `frequency`. Frames aren't
+/// evenly spaced, so each sample is weighted by its own time step.
+fn fourier(samples: &[ChirpSample], signal: fn(&ChirpSample) -> f32, frequency: f32) -> (f32, f32) {
+    samples.iter().fold((0.0, 0.0), |(re, im), sample| {
+        let (sin, cos) = (TAU * frequency * sample.t).sin_cos();
+        let x = signal(sample) * sample.dt;
+        (re + x * cos, im - x * sin)
+    })
+}
+
+/// Estimates the closed-loop response (measured / setpoint excitation) at
+/// log-spaced frequencies across the sweep and writes it as CSV.
+fn export_bode(chirp: &ChirpTest, path: &str) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "frequency_hz,magnitude_db,phase_deg")?;
+
+    for i in 0..BODE_POINTS {
+        let frequency =
+            chirp.start * (chirp.end / chirp.start).powf(i as f32 / (BODE_POINTS - 1) as f32);
+        let (in_re, in_im) = fourier(&chirp.samples, |s| s.input, frequency);
+        let (out_re, out_im) = fourier(&chirp.samples, |s| s.output, frequency);
+
+        let input_power = in_re * in_re + in_im * in_im;
+        if input_power <= f32::EPSILON {
+            continue;
+        }
+        // H = Y / X = Y · conj(X) / |X|²
+        let re = (out_re * in_re + out_im * in_im) / input_power;
+        let im = (out_im * in_re - out_re * in_im) / input_power;
+
+        let magnitude_db = 20.0 * (re * re + im * im).sqrt().max(f32::EPSILON).log10();
+        let phase_deg = im.atan2(re).to_degrees();
+        writeln!(file, "{frequency:.4},{magnitude_db:.3},{phase_deg:.2}")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,537 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use bevy::{ecs::query::QueryItem, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    Drone, DroneId, EngineState, HoverPid, PitchPid, RollPid, YawPid, arg_value, has_flag,
+    update_drone_forces,
+};
+
+/// Where the log goes without `--telemetry`.
+const DEFAULT_PATH: &str = "telemetry.csv";
+const COLUMNS: [&str; 11] = [
+    "time",
+    "y",
+    "target_y",
+    "pitch",
+    "target_pitch",
+    "roll",
+    "target_roll",
+    "yaw",
+    "target_yaw",
+    "thrust",
+    "torque",
+];
+
+/// Output format of the telemetry log, picked with `--log-format`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+    /// Columnar, buffered in memory and written on exit.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl LogFormat {
+    pub fn from_args() -> Self {
+        match arg_value("--log-format").as_deref() {
+            None | Some("csv") => Self::Csv,
+            Some("jsonl" | "json") => Self::JsonLines,
+            #[cfg(feature = "parquet")]
+            Some("parquet") => Self::Parquet,
+            #[cfg(not(feature = "parquet"))]
+            Some("parquet") => {
+                warn!("Parquet logging needs the `parquet` feature, falling back to CSV");
+                Self::Csv
+            }
+            Some(other) => {
+                warn!("Unknown --log-format {other}, expected csv, jsonl or parquet");
+                Self::Csv
+            }
+        }
+    }
+}
+
+/// Name of the drone id column in a combined log.
+const DRONE_COLUMN: &str = "drone";
+/// Key the startup banner is stored under in JSON Lines and Parquet logs.
+const BANNER_KEY: &str = "banner";
+/// Key that flushes the log to disk.
+const FLUSH_KEY: KeyCode = KeyCode::KeyF;
+
+/// One telemetry row. Angles are in radians, thrust/torque are the magnitudes
+/// of the applied `ExternalForce`.
+#[derive(Clone, Copy)]
+pub struct TelemetrySample {
+    pub time: f32,
+    /// `DroneId` of the drone the row describes.
+    pub drone: usize,
+    pub values: [f32; COLUMNS.len() - 1],
+}
+
+/// Components a telemetry row is read from.
+pub type TelemetryData = (
+    &'static Transform,
+    &'static HoverPid,
+    &'static PitchPid,
+    &'static RollPid,
+    &'static YawPid,
+    &'static ExternalForce,
+    &'static DroneId,
+);
+
+impl TelemetrySample {
+    pub fn capture(
+        time: f32,
+        (tf, ctl_y, ctl_pitch, ctl_roll, ctl_yaw, force, id): QueryItem<TelemetryData>,
+    ) -> Self {
+        let (yaw, pitch, roll) = tf.rotation.to_euler(EulerRot::YXZ);
+        Self {
+            time,
+            drone: id.0,
+            values: [
+                tf.translation.y,
+                ctl_y.target_y,
+                pitch,
+                ctl_pitch.target_angle,
+                roll,
+                ctl_roll.target_angle,
+                yaw,
+                ctl_yaw.target_angle,
+                force.force.length(),
+                force.torque.length(),
+            ],
+        }
+    }
+
+    /// Value of the named column.
+    pub fn value(&self, column: &str) -> Option<f32> {
+        let i = COLUMNS[1..].iter().position(|name| *name == column)?;
+        Some(self.values[i])
+    }
+
+    /// The row as a JSON object, as written to `jsonl` logs and streamed by
+    /// the telemetry server.
+    pub fn to_json(self, with_drone: bool) -> String {
+        let mut json = format!("{{\"{}\":{:.4}", COLUMNS[0], self.time);
+        if with_drone {
+            json += &format!(",\"{DRONE_COLUMN}\":{}", self.drone);
+        }
+        for (name, value) in COLUMNS[1..].iter().zip(self.values) {
+            json += &format!(",\"{name}\":{value:.4}");
+        }
+        json.push('}');
+        json
+    }
+
+    /// Parses a row from `(column, value)` pairs, or `None` if a column is
+    /// missing. Logs without a drone column are taken to be of drone 1.
+    fn from_fields(fields: &[(&str, f32)]) -> Option<Self> {
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(column, _)| *column == name)
+                .map(|(_, value)| *value)
+        };
+
+        let mut values = [0.0; COLUMNS.len() - 1];
+        for (value, name) in values.iter_mut().zip(&COLUMNS[1..]) {
+            *value = field(name)?;
+        }
+        Some(Self {
+            time: field(COLUMNS[0])?,
+            drone: field(DRONE_COLUMN).map_or(1, |drone| drone as usize),
+            values,
+        })
+    }
+}
+
+/// `text` as a quoted JSON string.
+fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            c if c.is_control() => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Reads a `csv` or `jsonl` telemetry log back, and whether its rows carry
+/// the drone id column.
+pub fn read_log(path: &str) -> Result<(Vec<TelemetrySample>, bool), String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    // Skip the banner at the top
+    let banner = format!("{{\"{BANNER_KEY}\"");
+    let mut lines = contents.lines().filter(|line| {
+        let line = line.trim();
+        !line.is_empty() && !line.starts_with('#') && !line.starts_with(&banner)
+    });
+    let mut samples = Vec::new();
+    let mut with_drone = false;
+
+    if contents.trim_start().starts_with('{') {
+        for line in lines {
+            let fields = line
+                .trim()
+                .trim_start_matches('{')
+                .trim_end_matches('}')
+                .split(',')
+                .filter_map(|field| {
+                    let (name, value) = field.split_once(':')?;
+                    Some((name.trim().trim_matches('"'), value.trim().parse().ok()?))
+                })
+                .collect::<Vec<_>>();
+            with_drone |= fields.iter().any(|(name, _)| *name == DRONE_COLUMN);
+            samples.push(TelemetrySample::from_fields(&fields).ok_or("malformed row")?);
+        }
+    } else {
+        let header = lines.next().ok_or("empty log")?;
+        let names = header.split(',').map(str::trim).collect::<Vec<_>>();
+        with_drone = names.contains(&DRONE_COLUMN);
+        for line in lines {
+            let fields = names
+                .iter()
+                .zip(line.split(','))
+                .filter_map(|(name, value)| Some((*name, value.trim().parse().ok()?)))
+                .collect::<Vec<_>>();
+            samples.push(TelemetrySample::from_fields(&fields).ok_or("malformed row")?);
+        }
+    }
+
+    Ok((samples, with_drone))
+}
+
+/// How rows from several drones are split over files, picked with
+/// `--log-per-drone`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLayout {
+    /// Every drone in one file, with a `drone` column after `time`.
+    #[default]
+    Combined,
+    /// One file per drone, named after the `--telemetry` path with the drone
+    /// id appended (`flight.csv` → `flight_drone2.csv`).
+    PerDrone,
+}
+
+/// One open log file.
+pub struct LogWriter {
+    pub path: String,
+    pub file: BufWriter<File>,
+    /// Whether rows carry the drone id column.
+    pub with_drone: bool,
+    /// Rows waiting to be written by formats that need the whole log at once.
+    #[cfg(feature = "parquet")]
+    pub buffer: Vec<TelemetrySample>,
+    /// Startup banner, stored as file metadata when the log is written.
+    #[cfg(feature = "parquet")]
+    pub header: String,
+}
+
+impl LogWriter {
+    /// Creates the file, starting it with `header` (the startup banner): `#`
+    /// comment lines in a CSV, a `{"banner": [...]}` first line in a JSON
+    /// Lines log and `banner` key-value metadata in a Parquet file.
+    pub fn create(
+        path: String,
+        format: LogFormat,
+        with_drone: bool,
+        header: &[String],
+    ) -> Option<Self> {
+        let file = match File::create(&path) {
+            Ok(file) => BufWriter::new(file),
+            Err(e) => {
+                warn!("Failed to create telemetry log {path}: {e}");
+                return None;
+            }
+        };
+
+        let mut writer = Self {
+            path,
+            file,
+            with_drone,
+            #[cfg(feature = "parquet")]
+            buffer: Vec::new(),
+            #[cfg(feature = "parquet")]
+            header: header.join("\n"),
+        };
+        match format {
+            LogFormat::Csv => {
+                for line in header {
+                    let _ = writeln!(writer.file, "# {line}");
+                }
+            }
+            LogFormat::JsonLines if !header.is_empty() => {
+                let lines = header.iter().map(|line| json_string(line));
+                let _ = writeln!(
+                    writer.file,
+                    "{{\"{BANNER_KEY}\":[{}]}}",
+                    lines.collect::<Vec<_>>().join(",")
+                );
+            }
+            _ => {}
+        }
+        if format == LogFormat::Csv {
+            let _ = write!(writer.file, "{}", COLUMNS[0]);
+            if with_drone {
+                let _ = write!(writer.file, ",{DRONE_COLUMN}");
+            }
+            let _ = writeln!(writer.file, ",{}", COLUMNS[1..].join(","));
+        }
+        Some(writer)
+    }
+
+    pub fn write(&mut self, format: LogFormat, sample: TelemetrySample) {
+        let file = &mut self.file;
+        match format {
+            LogFormat::Csv => {
+                let _ = write!(file, "{:.4}", sample.time);
+                if self.with_drone {
+                    let _ = write!(file, ",{}", sample.drone);
+                }
+                for value in sample.values {
+                    let _ = write!(file, ",{value:.4}");
+                }
+                let _ = writeln!(file);
+            }
+            LogFormat::JsonLines => {
+                let _ = writeln!(file, "{}", sample.to_json(self.with_drone));
+            }
+            #[cfg(feature = "parquet")]
+            LogFormat::Parquet => self.buffer.push(sample),
+        }
+    }
+
+    /// Pushes the rows written so far out to disk. Parquet rows stay
+    /// buffered, since the file can only be written once.
+    pub fn flush(&mut self) {
+        let _ = self.file.flush();
+    }
+
+    /// Writes anything still buffered and flushes the file.
+    pub fn finish(self, format: LogFormat) {
+        #[cfg(feature = "parquet")]
+        if format == LogFormat::Parquet {
+            let file = self.file.into_inner().map_err(|e| e.to_string());
+            let result = file
+                .and_then(|file| write_parquet(file, &self.buffer, self.with_drone, &self.header));
+            if let Err(e) = result {
+                warn!("Failed to write parquet telemetry {}: {e}", self.path);
+            }
+            info!("Telemetry written to {} ({format:?})", self.path);
+            return;
+        }
+
+        let mut file = self.file;
+        let _ = file.flush();
+        info!("Telemetry written to {} ({format:?})", self.path);
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct TelemetryLogger {
+    pub path: Option<String>,
+    pub format: LogFormat,
+    pub layout: LogLayout,
+    /// The combined log under id 0, or each drone's log under its id. Per-drone
+    /// files are created when the drone is first logged.
+    pub writers: BTreeMap<usize, LogWriter>,
+    /// Logs whose file couldn't be created, so it isn't retried every frame.
+    pub failed: BTreeSet<usize>,
+    /// Lines written at the top of each file; the startup banner.
+    pub header: Vec<String>,
+    pub start: Option<f32>,
+}
+
+impl TelemetryLogger {
+    /// Files are created when the first row is logged, once the header is known.
+    pub fn new(path: Option<String>, format: LogFormat, layout: LogLayout) -> Self {
+        Self {
+            path,
+            format,
+            layout,
+            writers: BTreeMap::new(),
+            failed: BTreeSet::new(),
+            header: Vec::new(),
+            start: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some() || !self.writers.is_empty()
+    }
+
+    fn write(&mut self, sample: TelemetrySample) {
+        let (key, with_drone) = match self.layout {
+            LogLayout::Combined => (0, true),
+            LogLayout::PerDrone => (sample.drone, false),
+        };
+        if !self.writers.contains_key(&key) {
+            if self.failed.contains(&key) {
+                return;
+            }
+            let Some(path) = self.path.as_deref() else {
+                return;
+            };
+            let path = match self.layout {
+                LogLayout::Combined => path.to_string(),
+                LogLayout::PerDrone => drone_log_path(path, key),
+            };
+            let Some(writer) = LogWriter::create(path, self.format, with_drone, &self.header)
+            else {
+                self.failed.insert(key);
+                return;
+            };
+            self.writers.insert(key, writer);
+        }
+
+        let format = self.format;
+        if let Some(writer) = self.writers.get_mut(&key) {
+            writer.write(format, sample);
+        }
+    }
+
+    pub fn flush(&mut self) {
+        for writer in self.writers.values_mut() {
+            writer.flush();
+        }
+    }
+
+    /// Writes anything still buffered and flushes the files.
+    pub fn finish(&mut self) {
+        for (_, writer) in std::mem::take(&mut self.writers) {
+            writer.finish(self.format);
+        }
+    }
+}
+
+/// `flight.csv` → `flight_drone2.csv`.
+fn drone_log_path(path: &str, drone: usize) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}_drone{drone}.{}", extension.to_string_lossy()),
+        None => format!("{stem}_drone{drone}"),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet(
+    file: File,
+    samples: &[TelemetrySample],
+    with_drone: bool,
+    header: &str,
+) -> Result<(), String> {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, Float32Array, UInt32Array},
+        record_batch::RecordBatch,
+    };
+    use parquet::{arrow::ArrowWriter, file::properties::WriterProperties, format::KeyValue};
+
+    let mut columns: Vec<(&str, ArrayRef)> = COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let values = samples.iter().map(|sample| match i {
+                0 => sample.time,
+                _ => sample.values[i - 1],
+            });
+            let array: ArrayRef = Arc::new(Float32Array::from_iter_values(values));
+            (*name, array)
+        })
+        .collect();
+    if with_drone {
+        let ids = samples.iter().map(|sample| sample.drone as u32);
+        columns.insert(
+            1,
+            (DRONE_COLUMN, Arc::new(UInt32Array::from_iter_values(ids))),
+        );
+    }
+
+    let batch = RecordBatch::try_from_iter(columns).map_err(|e| e.to_string())?;
+    let properties = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            BANNER_KEY.to_string(),
+            header.to_string(),
+        )]))
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(file, batch.schema(), Some(properties)).map_err(|e| e.to_string())?;
+    writer.write(&batch).map_err(|e| e.to_string())?;
+    writer.close().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn telemetry_plugin(app: &mut App) {
+    let layout = if has_flag("--log-per-drone") {
+        LogLayout::PerDrone
+    } else {
+        LogLayout::Combined
+    };
+
+    app.insert_resource(TelemetryLogger::new(
+        Some(arg_value("--telemetry").unwrap_or_else(|| DEFAULT_PATH.to_string())),
+        LogFormat::from_args(),
+        layout,
+    ))
+    .add_systems(
+        FixedUpdate,
+        log_telemetry
+            .after(update_drone_forces)
+            .run_if(in_state(EngineState::On)),
+    )
+    .add_systems(Update, flush_telemetry)
+    .add_systems(Last, finish_telemetry_on_exit);
+}
+
+/// Logs each physics step, with the forces the controllers just applied.
+pub fn log_telemetry(
+    time: Res<Time<Fixed>>,
+    mut logger: ResMut<TelemetryLogger>,
+    drone_query: Query<TelemetryData, With<Drone>>,
+) {
+    // Nothing moves while paused
+    if !logger.is_enabled() || time.delta_secs() <= 0.0 {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    let start = *logger.start.get_or_insert(now);
+
+    for drone in drone_query.iter() {
+        logger.write(TelemetrySample::capture(now - start, drone));
+    }
+}
+
+/// `F` flushes the log so far to disk, to look at it mid-flight.
+pub fn flush_telemetry(keyboard: Res<ButtonInput<KeyCode>>, mut logger: ResMut<TelemetryLogger>) {
+    if keyboard.just_pressed(FLUSH_KEY) && logger.is_enabled() {
+        logger.flush();
+        info!("Telemetry flushed");
+    }
+}
+
+pub fn finish_telemetry_on_exit(
+    mut exit: EventReader<AppExit>,
+    mut logger: ResMut<TelemetryLogger>,
+) {
+    if exit.read().next().is_some() {
+        logger.finish();
+    }
+}
@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+
+use crate::{arg_value, telemetry::read_log};
+
+/// Address the server listens on when `--serve` isn't given one.
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9001";
+
+/// Address from `--serve <address>`, if the server is wanted.
+fn address_from_args() -> Option<String> {
+    arg_value("--serve")
+}
+
+/// Streams telemetry rows to WebSocket clients as JSON text messages, one row
+/// per message, with the same fields as a `jsonl` telemetry log. Needs the
+/// `websocket` feature.
+#[cfg(feature = "websocket")]
+#[derive(Resource)]
+pub struct TelemetryServer {
+    clients: std::sync::Arc<std::sync::Mutex<Vec<tungstenite::WebSocket<std::net::TcpStream>>>>,
+    pub start: Option<f32>,
+}
+
+#[cfg(feature = "websocket")]
+impl TelemetryServer {
+    /// Longest a slow client may hold up a frame before it is dropped.
+    const WRITE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(50);
+
+    /// Listens on `address`, accepting clients on a background thread.
+    pub fn bind(address: &str) -> std::io::Result<Self> {
+        let listener = std::net::TcpListener::bind(address)?;
+        let clients = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let accepted = clients.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_nodelay(true);
+                let _ = stream.set_write_timeout(Some(Self::WRITE_TIMEOUT));
+                match tungstenite::accept(stream) {
+                    Ok(socket) => {
+                        info!("Telemetry client connected");
+                        if let Ok(mut clients) = accepted.lock() {
+                            clients.push(socket);
+                        }
+                    }
+                    Err(e) => warn!("Telemetry client handshake failed: {e}"),
+                }
+            }
+        });
+
+        info!("Serving telemetry on ws://{address}");
+        Ok(Self {
+            clients,
+            start: None,
+        })
+    }
+
+    pub fn has_clients(&self) -> bool {
+        self.clients.lock().is_ok_and(|clients| !clients.is_empty())
+    }
+
+    /// Sends `message` to every client, dropping the ones that fail.
+    pub fn broadcast(&self, message: &str) {
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+        clients.retain_mut(|socket| {
+            let sent = socket.send(tungstenite::Message::text(message)).is_ok();
+            if !sent {
+                info!("Telemetry client disconnected");
+            }
+            sent
+        });
+    }
+}
+
+pub fn telemetry_server_plugin(app: &mut App) {
+    let Some(address) = address_from_args() else {
+        return;
+    };
+
+    #[cfg(feature = "websocket")]
+    match TelemetryServer::bind(&address) {
+        Ok(server) => {
+            app.insert_resource(server).add_systems(
+                Update,
+                stream_telemetry.run_if(in_state(crate::EngineState::On)),
+            );
+        }
+        Err(e) => warn!("Failed to serve telemetry on {address}: {e}"),
+    }
+
+    #[cfg(not(feature = "websocket"))]
+    {
+        let _ = app;
+        warn!("Serving telemetry on {address} needs the `websocket` feature");
+    }
+}
+
+#[cfg(feature = "websocket")]
+pub fn stream_telemetry(
+    time: Res<Time>,
+    mut server: ResMut<TelemetryServer>,
+    drone_query: Query<crate::telemetry::TelemetryData, With<crate::Drone>>,
+) {
+    if !server.has_clients() {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    let start = *server.start.get_or_insert(now);
+    for drone in drone_query.iter() {
+        let sample = crate::telemetry::TelemetrySample::capture(now - start, drone);
+        server.broadcast(&sample.to_json(true));
+    }
+}
+
+/// `--serve-replay <log>`: instead of running the sim, streams a recorded
+/// `csv` or `jsonl` telemetry log over the telemetry server at its original
+/// timing, so a dashboard can be developed without flying. Waits for a client
+/// before starting and loops until killed.
+pub fn serve_replay(path: &str) {
+    let (samples, with_drone) = match read_log(path) {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("Failed to read telemetry log {path}: {e}");
+            return;
+        }
+    };
+    if samples.is_empty() {
+        eprintln!("Telemetry log {path} has no rows");
+        return;
+    }
+    let address = address_from_args().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+
+    #[cfg(feature = "websocket")]
+    {
+        use std::{
+            thread,
+            time::{Duration, Instant},
+        };
+
+        let server = match TelemetryServer::bind(&address) {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("Failed to serve telemetry on {address}: {e}");
+                return;
+            }
+        };
+        println!(
+            "Replaying {path} ({} rows) on ws://{address}",
+            samples.len()
+        );
+
+        loop {
+            while !server.has_clients() {
+                thread::sleep(Duration::from_millis(100));
+            }
+
+            let start = Instant::now();
+            let first = samples[0].time;
+            for sample in &samples {
+                let due = Duration::from_secs_f32((sample.time - first).max(0.0));
+                if let Some(wait) = due.checked_sub(start.elapsed()) {
+                    thread::sleep(wait);
+                }
+                server.broadcast(&sample.to_json(with_drone));
+            }
+            println!("Replay finished, starting over");
+        }
+    }
+
+    #[cfg(not(feature = "websocket"))]
+    {
+        let _ = with_drone;
+        eprintln!("Serving {path} on {address} needs the `websocket` feature");
+    }
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{Drone, arg_value, position::Home};
+
+/// Where the tether attaches under the drone, in its body frame.
+const ATTACH_POINT: Vec3 = Vec3::new(0.0, -0.05, 0.0);
+/// Fraction of the length from which the tether is drawn as taut.
+const TAUT_FRACTION: f32 = 0.98;
+/// Points along the drawn tether.
+const TETHER_SEGMENTS: usize = 16;
+
+/// Tethered flight: each drone is tied to a ground anchor by a tether of
+/// `length` that only pulls once it's taut (a Rapier rope joint). The anchor
+/// is `--tether-anchor` if given, otherwise the ground under the drone's
+/// spawn point. Off unless `--tether <length>` is given.
+#[derive(Resource)]
+pub struct TetherSettings {
+    pub length: Option<f32>,
+    pub anchor: Option<Vec3>,
+}
+
+impl TetherSettings {
+    pub fn from_args() -> Self {
+        Self {
+            length: arg_value("--tether")
+                .and_then(|length| length.parse::<f32>().ok())
+                .filter(|length| *length > 0.0),
+            anchor: arg_value("--tether-anchor").and_then(|anchor| {
+                let values = anchor
+                    .split(',')
+                    .map(|value| value.trim().parse::<f32>().ok())
+                    .collect::<Option<Vec<_>>>()?;
+                match values[..] {
+                    [x, y, z] => Some(Vec3::new(x, y, z)),
+                    _ => {
+                        warn!("Invalid --tether-anchor {anchor}, expected x,y,z");
+                        None
+                    }
+                }
+            }),
+        }
+    }
+}
+
+/// A drone's tether. The joint itself sits on a fixed body at `anchor`.
+#[derive(Component)]
+pub struct Tether {
+    pub anchor: Vec3,
+    pub length: f32,
+}
+
+pub fn tether_plugin(app: &mut App) {
+    let settings = TetherSettings::from_args();
+    let enabled = settings.length.is_some();
+
+    app.insert_resource(settings).add_systems(
+        Update,
+        (add_tether, draw_tether).chain().run_if(move || enabled),
+    );
+}
+
+#[allow(clippy::type_complexity)]
+pub fn add_tether(
+    mut commands: Commands,
+    settings: Res<TetherSettings>,
+    drone_query: Query<(Entity, &Home), (With<Drone>, Without<Tether>)>,
+) {
+    let Some(length) = settings.length else {
+        return;
+    };
+
+    for (drone, home) in drone_query.iter() {
+        let anchor = settings
+            .anchor
+            .unwrap_or(Vec3::new(home.0.x, 0.0, home.0.z));
+        // The joint lives on the anchor with the drone as its parent, so the
+        // drone's own joints are left alone
+        let rope = RopeJointBuilder::new(length)
+            .local_anchor1(ATTACH_POINT)
+            .local_anchor2(Vec3::ZERO);
+        commands.spawn((
+            RigidBody::Fixed,
+            Transform::from_translation(anchor),
+            ImpulseJoint::new(drone, rope),
+        ));
+
+        commands.entity(drone).insert(Tether { anchor, length });
+        info!("Drone tethered to {anchor} with {length} m");
+    }
+}
+
+/// The tether as a straight line while taut, or hanging with some sag while
+/// slack. Taut tethers are drawn red.
+pub fn draw_tether(drone_query: Query<(&Transform, &Tether)>, mut gizmos: Gizmos) {
+    for (tf, tether) in drone_query.iter() {
+        let end = tf.transform_point(ATTACH_POINT);
+        let span = tether.anchor.distance(end);
+        let taut = span >= tether.length * TAUT_FRACTION;
+
+        // Parabola through both ends, sagging by about half the slack
+        let sag = 0.5
+            * (tether.length * tether.length - span * span)
+                .max(0.0)
+                .sqrt();
+        let points = (0..=TETHER_SEGMENTS).map(|i| {
+            let t = i as f32 / TETHER_SEGMENTS as f32;
+            let point = tether.anchor.lerp(end, t);
+            let drop = 4.0 * sag * t * (1.0 - t);
+            Vec3::new(point.x, (point.y - drop).max(0.0), point.z)
+        });
+
+        let color = if taut {
+            Color::srgb(1.0, 0.2, 0.2)
+        } else {
+            Color::srgb(0.9, 0.9, 0.6)
+        };
+        gizmos.linestrip(points, color);
+        gizmos.sphere(tether.anchor, 0.15, color);
+    }
+}
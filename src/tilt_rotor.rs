@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use crate::{ActiveDrone, EngineState, Piloted};
+
+/// Tilts the thrust vector about the body X axis independently of the airframe,
+/// so the drone can accelerate horizontally while staying level. Follows the
+/// pitch convention: a negative tilt points the thrust forward.
+#[derive(Component)]
+pub struct TiltRotor {
+    pub tilt_angle: f32,
+    pub tilt_rate: f32,
+    pub min_tilt: f32,
+    pub max_tilt: f32,
+}
+
+impl TiltRotor {
+    /// Thrust direction in the body frame.
+    pub fn thrust_dir(&self) -> Vec3 {
+        Quat::from_rotation_x(self.tilt_angle) * Vec3::Y
+    }
+}
+
+#[derive(Component)]
+pub struct TiltText;
+
+pub fn tilt_rotor_control(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    engine_state: Res<State<EngineState>>,
+    time: Res<Time>,
+    mut drone_query: Query<&mut TiltRotor, Piloted>,
+) {
+    if *engine_state.get() != EngineState::On {
+        return;
+    }
+
+    let dt = time.delta_secs();
+    for mut tilt in drone_query.iter_mut() {
+        if keyboard.pressed(KeyCode::KeyI) {
+            tilt.tilt_angle -= tilt.tilt_rate * dt;
+        }
+        if keyboard.pressed(KeyCode::KeyK) {
+            tilt.tilt_angle += tilt.tilt_rate * dt;
+        }
+        tilt.tilt_angle = tilt.tilt_angle.clamp(tilt.min_tilt, tilt.max_tilt);
+    }
+}
+
+pub fn update_tilt_text(
+    drone_query: Query<Option<&TiltRotor>, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<TiltText>>,
+) {
+    for tilt in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            match tilt {
+                Some(tilt) => {
+                    *text = format!("Tilt: {:.2} deg", tilt.tilt_angle.to_degrees()).into();
+                }
+                None => *text = "Tilt: off".into(),
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier3d::prelude::*;
+
+use crate::{arg_value, has_flag};
+
+/// Default physics and controller rate (Hz).
+const DEFAULT_PHYSICS_RATE: f32 = 60.0;
+
+/// Fixed physics rate with render interpolation. The force computation and
+/// the physics both run in `FixedUpdate`, stepping at `--physics-rate <hz>`
+/// (60 by default) as often as real time requires, so every controller
+/// update sees the same `dt` whatever the framerate and a tuning behaves the
+/// same on every machine. Pilot input, the autopilots and the HUD still run
+/// once per frame in `Update`.
+///
+/// When the rates differ the bodies would move in visible steps, so each
+/// dynamic body is drawn between its last two physics states. `F7` (or
+/// `--no-interpolation`) switches the interpolation off to compare. The
+/// controllers in `FixedUpdate` see the physics state itself; everything in
+/// `Update` sees the drawn one, up to one physics step late.
+#[derive(Resource)]
+pub struct PhysicsTimestep {
+    pub rate: f32,
+    pub interpolate: bool,
+}
+
+impl PhysicsTimestep {
+    pub fn from_args() -> Self {
+        Self {
+            rate: arg_value("--physics-rate")
+                .and_then(|rate| rate.parse::<f32>().ok())
+                .filter(|rate| *rate > 0.0)
+                .unwrap_or(DEFAULT_PHYSICS_RATE),
+            interpolate: !has_flag("--no-interpolation"),
+        }
+    }
+}
+
+/// Physics poses a body is drawn between. Rapier's own
+/// `TransformInterpolation` only blends within its per-frame stepping, not
+/// across `FixedUpdate` runs, so the blend is done here, and the drawn pose
+/// is put back before the next physics step so the physics never sees it.
+#[derive(Component, Default)]
+pub struct PhysicsInterpolation {
+    /// Pose after the previous and after the latest physics step.
+    previous: Option<Transform>,
+    current: Option<Transform>,
+    /// Pose last drawn, to tell it apart from a teleport made in between.
+    drawn: Option<Transform>,
+}
+
+impl PhysicsInterpolation {
+    /// The pose the transform still holds if nothing but the interpolation
+    /// touched it.
+    fn expected(&self) -> Option<Transform> {
+        self.drawn.or(self.current)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+pub fn timestep_plugin(app: &mut App) {
+    let timestep = PhysicsTimestep::from_args();
+
+    app.insert_resource(Time::<Fixed>::from_hz(timestep.rate as f64))
+        .insert_resource(TimestepMode::Fixed {
+            dt: 1.0 / timestep.rate,
+            substeps: 1,
+        })
+        .insert_resource(timestep)
+        .add_systems(Update, (toggle_interpolation, update_interpolation).chain())
+        .add_systems(FixedFirst, restore_physics_pose)
+        .add_systems(
+            FixedUpdate,
+            record_physics_pose.after(PhysicsSet::Writeback),
+        )
+        .add_systems(
+            PostUpdate,
+            interpolate_physics_pose.before(TransformSystem::TransformPropagate),
+        );
+}
+
+pub fn toggle_interpolation(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut timestep: ResMut<PhysicsTimestep>,
+) {
+    if keyboard.just_pressed(KeyCode::F7) {
+        timestep.interpolate = !timestep.interpolate;
+        info!(
+            "Physics interpolation {}",
+            if timestep.interpolate { "on" } else { "off" }
+        );
+    }
+}
+
+/// Keeps `PhysicsInterpolation` on every dynamic body while interpolation is
+/// on, including ones spawned later, and removes it when switched off.
+#[allow(clippy::type_complexity)]
+pub fn update_interpolation(
+    mut commands: Commands,
+    timestep: Res<PhysicsTimestep>,
+    mut body_query: Query<(
+        Entity,
+        &RigidBody,
+        &mut Transform,
+        Option<&PhysicsInterpolation>,
+    )>,
+) {
+    for (entity, body, mut tf, interpolation) in body_query.iter_mut() {
+        let wanted = timestep.interpolate && *body == RigidBody::Dynamic;
+        match interpolation {
+            None if wanted => {
+                commands
+                    .entity(entity)
+                    .insert(PhysicsInterpolation::default());
+            }
+            Some(interpolation) if !wanted => {
+                // Leave the body where the physics has it
+                if let (Some(drawn), Some(current)) = (interpolation.drawn, interpolation.current)
+                    && *tf == drawn
+                {
+                    *tf = current;
+                }
+                commands.entity(entity).remove::<PhysicsInterpolation>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Puts the physics pose back before a step. A transform that changed since
+/// it was drawn was moved on purpose (a reset, say), so it's left for the
+/// physics to pick up.
+pub fn restore_physics_pose(mut body_query: Query<(&mut Transform, &mut PhysicsInterpolation)>) {
+    for (mut tf, mut interpolation) in body_query.iter_mut() {
+        let (Some(drawn), Some(current)) = (interpolation.drawn, interpolation.current) else {
+            continue;
+        };
+        if *tf == drawn {
+            *tf = current;
+            interpolation.drawn = None;
+        } else {
+            interpolation.reset();
+        }
+    }
+}
+
+pub fn record_physics_pose(mut body_query: Query<(&Transform, &mut PhysicsInterpolation)>) {
+    for (tf, mut interpolation) in body_query.iter_mut() {
+        interpolation.previous = interpolation.current.or(Some(*tf));
+        interpolation.current = Some(*tf);
+        interpolation.drawn = None;
+    }
+}
+
+/// Draws each body between its last two physics poses, by how far real time
+/// has got into the next step.
+pub fn interpolate_physics_pose(
+    fixed_time: Res<Time<Fixed>>,
+    mut body_query: Query<(&mut Transform, &mut PhysicsInterpolation)>,
+) {
+    let t = fixed_time.overstep_fraction();
+
+    for (mut tf, mut interpolation) in body_query.iter_mut() {
+        let (Some(previous), Some(current)) = (interpolation.previous, interpolation.current)
+        else {
+            continue;
+        };
+        if interpolation.expected() != Some(*tf) {
+            interpolation.reset();
+            continue;
+        }
+
+        let drawn = Transform {
+            translation: previous.translation.lerp(current.translation, t),
+            rotation: previous.rotation.slerp(current.rotation, t),
+            scale: current.scale,
+        };
+        *tf = drawn;
+        interpolation.drawn = Some(drawn);
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+/// One polynomial piece of a trajectory: position is
+/// `c0 + c1·t + c2·t² + c3·t³ + c4·t⁴ + c5·t⁵` for `t` in `0..=duration`.
+#[derive(Clone, Debug)]
+pub struct Segment {
+    pub duration: f32,
+    pub coefficients: [Vec3; 6],
+}
+
+/// Reference position and acceleration at one instant. The reference
+/// velocity isn't needed: the position controller's derivative term already
+/// sees it as the rate of change of the position error.
+#[derive(Clone, Copy, Debug)]
+pub struct TrajectoryPoint {
+    pub position: Vec3,
+    pub acceleration: Vec3,
+}
+
+impl Segment {
+    /// The minimum-jerk (quintic) piece that starts at position `p0`,
+    /// velocity `v0` and acceleration `a0`, and ends at `p1`, `v1`, `a1` after
+    /// `duration`.
+    fn quintic(
+        (p0, v0, a0): (Vec3, Vec3, Vec3),
+        (p1, v1, a1): (Vec3, Vec3, Vec3),
+        duration: f32,
+    ) -> Self {
+        let t = duration;
+        let (t2, t3, t4, t5) = (t * t, t * t * t, t * t * t * t, t * t * t * t * t);
+        let c3 =
+            (20.0 * (p1 - p0) - (8.0 * v1 + 12.0 * v0) * t - (3.0 * a0 - a1) * t2) / (2.0 * t3);
+        let c4 = (30.0 * (p0 - p1) + (14.0 * v1 + 16.0 * v0) * t + (3.0 * a0 - 2.0 * a1) * t2)
+            / (2.0 * t4);
+        let c5 = (12.0 * (p1 - p0) - 6.0 * (v1 + v0) * t - (a0 - a1) * t2) / (2.0 * t5);
+
+        Self {
+            duration,
+            coefficients: [p0, v0, a0 / 2.0, c3, c4, c5],
+        }
+    }
+
+    fn sample(&self, t: f32) -> TrajectoryPoint {
+        let t = t.clamp(0.0, self.duration);
+        let [c0, c1, c2, c3, c4, c5] = self.coefficients;
+        TrajectoryPoint {
+            position: c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * c5)))),
+            acceleration: 2.0 * c2 + t * (6.0 * c3 + t * (12.0 * c4 + t * 20.0 * c5)),
+        }
+    }
+}
+
+/// Smooth path through a list of points, made of minimum-jerk segments that
+/// join with matching position, velocity and acceleration. It starts and
+/// ends at rest but flies through the points in between without stopping:
+/// the velocity at each inner point is the central difference of its
+/// neighbours over the two segment times, with zero acceleration.
+#[derive(Clone, Debug, Default)]
+pub struct Trajectory {
+    pub segments: Vec<Segment>,
+}
+
+impl Trajectory {
+    /// Path through `points`, taking `durations[i]` seconds from point `i`
+    /// to `i + 1`.
+    pub fn through(points: &[Vec3], durations: &[f32]) -> Self {
+        let velocity = |i: usize| {
+            if i == 0 || i + 1 >= points.len() {
+                Vec3::ZERO
+            } else {
+                (points[i + 1] - points[i - 1]) / (durations[i - 1] + durations[i])
+            }
+        };
+
+        let segments = points
+            .windows(2)
+            .zip(durations)
+            .enumerate()
+            .map(|(i, (pair, &duration))| {
+                Segment::quintic(
+                    (pair[0], velocity(i), Vec3::ZERO),
+                    (pair[1], velocity(i + 1), Vec3::ZERO),
+                    duration.max(f32::EPSILON),
+                )
+            })
+            .collect();
+        Self { segments }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.segments.iter().map(|segment| segment.duration).sum()
+    }
+
+    /// Reference at `t` seconds from the start, held at the end once it's over.
+    pub fn sample(&self, mut t: f32) -> Option<TrajectoryPoint> {
+        let last = self.segments.last()?;
+        for segment in &self.segments {
+            if t <= segment.duration {
+                return Some(segment.sample(t));
+            }
+            t -= segment.duration;
+        }
+
+        let end = last.sample(last.duration);
+        Some(TrajectoryPoint {
+            position: end.position,
+            acceleration: Vec3::ZERO,
+        })
+    }
+
+    /// How many points after the first have been passed at time `t`.
+    pub fn points_passed(&self, t: f32) -> usize {
+        let mut end = 0.0;
+        self.segments
+            .iter()
+            .take_while(|segment| {
+                end += segment.duration;
+                end <= t
+            })
+            .count()
+    }
+}
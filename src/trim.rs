@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ActiveDrone, Drone, EngineState, HoverPid, PitchPid, RollPid, YawPid, arg_value,
+    reset_feedforward,
+};
+
+pub const DEFAULT_TRIM_PATH: &str = "trims.ron";
+/// Starts a capture, or discards one.
+const CAPTURE_KEY: KeyCode = KeyCode::Backquote;
+/// Saves the captured trim.
+const SAVE_KEY: KeyCode = KeyCode::Enter;
+/// Stable hover the trim is averaged over (s).
+const CAPTURE_TIME: f32 = 3.0;
+/// Largest speed (m/s), spin (rad/s) and altitude error (m) that still count
+/// as a stable hover.
+const MAX_STABLE_SPEED: f32 = 0.2;
+const MAX_STABLE_SPIN: f32 = 0.2;
+const MAX_STABLE_ERROR: f32 = 0.2;
+
+/// Steady-state integral outputs of the four controllers: the extra thrust
+/// (m/s²) and the attitude corrections (rad/s²) a payload needs to hover
+/// level on target.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Trim {
+    pub thrust: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    pub yaw: f32,
+}
+
+impl Trim {
+    pub fn read(
+        ctl_y: &HoverPid,
+        ctl_pitch: &PitchPid,
+        ctl_roll: &RollPid,
+        ctl_yaw: &YawPid,
+    ) -> Self {
+        Self {
+            thrust: ctl_y.ki * ctl_y.integral_e,
+            pitch: ctl_pitch.ki * ctl_pitch.integral_e,
+            roll: ctl_roll.ki * ctl_roll.integral_e,
+            yaw: ctl_yaw.ki * ctl_yaw.integral_e,
+        }
+    }
+
+    /// Preloads the integrators so they start out holding the trim.
+    pub fn apply(
+        &self,
+        ctl_y: &mut HoverPid,
+        ctl_pitch: &mut PitchPid,
+        ctl_roll: &mut RollPid,
+        ctl_yaw: &mut YawPid,
+    ) {
+        let preload = |trim: f32, ki: f32| if ki > 0.0 { trim / ki } else { 0.0 };
+        ctl_y.integral_e = preload(self.thrust, ctl_y.ki);
+        ctl_pitch.integral_e = preload(self.pitch, ctl_pitch.ki);
+        ctl_roll.integral_e = preload(self.roll, ctl_roll.ki);
+        ctl_yaw.integral_e = preload(self.yaw, ctl_yaw.ki);
+    }
+
+    /// Moves this running mean towards `sample` with weight `t`.
+    fn blend(&mut self, sample: Self, t: f32) {
+        self.thrust += (sample.thrust - self.thrust) * t;
+        self.pitch += (sample.pitch - self.pitch) * t;
+        self.roll += (sample.roll - self.roll) * t;
+        self.yaw += (sample.yaw - self.yaw) * t;
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "T{:+.3} P{:+.3} R{:+.3} Y{:+.3}",
+            self.thrust, self.pitch, self.roll, self.yaw
+        )
+    }
+}
+
+/// Where the auto-trim is.
+#[derive(Clone, Copy, Debug)]
+pub enum TrimStep {
+    /// Averaging the integral outputs over a stable hover; the stable time
+    /// so far (s) and the mean.
+    Capturing(f32, Trim),
+    /// Done, waiting for `Enter` to save it.
+    Captured(Trim),
+}
+
+/// Auto-trim: `` ` `` waits for the active drone to hover stably, averages
+/// what its integrators are holding over `CAPTURE_TIME`, and shows the result;
+/// `Enter` saves it under the `--payload` name (default `default`) to
+/// `--trim-file` (default `trims.ron`). The trim saved for the payload is
+/// preloaded into the integrators whenever the engine starts, so a swapped
+/// payload doesn't have to wind them up again.
+#[derive(Resource)]
+pub struct TrimStore {
+    pub path: String,
+    pub payload: String,
+    pub trims: BTreeMap<String, Trim>,
+    pub step: Option<TrimStep>,
+}
+
+impl TrimStore {
+    pub fn from_args() -> Self {
+        let path = arg_value("--trim-file").unwrap_or(DEFAULT_TRIM_PATH.to_string());
+        let trims = match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                ron::from_str::<BTreeMap<String, Trim>>(&contents).unwrap_or_else(|e| {
+                    warn!("Failed to load trims {path}: {e}");
+                    BTreeMap::new()
+                })
+            }
+            Err(_) => BTreeMap::new(),
+        };
+
+        Self {
+            path,
+            payload: arg_value("--payload").unwrap_or("default".to_string()),
+            trims,
+            step: None,
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(&self.trims, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, contents).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Component)]
+pub struct TrimText;
+
+pub fn trim_plugin(app: &mut App) {
+    app.insert_resource(TrimStore::from_args())
+        .add_systems(
+            Update,
+            (
+                capture_trim.run_if(in_state(EngineState::On)),
+                save_trim,
+                update_trim_text,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(EngineState::On),
+            apply_trim.after(reset_feedforward),
+        )
+        .add_systems(OnExit(EngineState::On), stop_trim_capture);
+}
+
+pub fn apply_trim(
+    store: Res<TrimStore>,
+    mut drone_query: Query<(&mut HoverPid, &mut PitchPid, &mut RollPid, &mut YawPid), With<Drone>>,
+) {
+    let Some(trim) = store.trims.get(&store.payload) else {
+        return;
+    };
+
+    for (mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        trim.apply(&mut ctl_y, &mut ctl_pitch, &mut ctl_roll, &mut ctl_yaw);
+    }
+    info!("Applied trim for {}: {}", store.payload, trim.summary());
+}
+
+#[allow(clippy::type_complexity)]
+pub fn capture_trim(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut store: ResMut<TrimStore>,
+    drone_query: Query<
+        (
+            &Transform,
+            &Velocity,
+            &HoverPid,
+            &PitchPid,
+            &RollPid,
+            &YawPid,
+        ),
+        With<ActiveDrone>,
+    >,
+) {
+    if keyboard.just_pressed(CAPTURE_KEY) {
+        store.step = match store.step {
+            None => {
+                info!("Trim capture started, hold a steady hover");
+                Some(TrimStep::Capturing(0.0, Trim::default()))
+            }
+            Some(_) => {
+                info!("Trim discarded");
+                None
+            }
+        };
+    }
+    let Some(TrimStep::Capturing(stable_time, mut mean)) = store.step else {
+        return;
+    };
+    let Ok((tf, velocity, ctl_y, ctl_pitch, ctl_roll, ctl_yaw)) = drone_query.single() else {
+        return;
+    };
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+
+    let stable = velocity.linvel.length() < MAX_STABLE_SPEED
+        && velocity.angvel.length() < MAX_STABLE_SPIN
+        && (ctl_y.soft_target() - tf.translation.y).abs() < MAX_STABLE_ERROR;
+    if !stable {
+        store.step = Some(TrimStep::Capturing(0.0, Trim::default()));
+        return;
+    }
+
+    let stable_time = stable_time + dt;
+    mean.blend(
+        Trim::read(ctl_y, ctl_pitch, ctl_roll, ctl_yaw),
+        dt / stable_time,
+    );
+    store.step = Some(if stable_time >= CAPTURE_TIME {
+        info!("Trim captured: {} (Enter saves)", mean.summary());
+        TrimStep::Captured(mean)
+    } else {
+        TrimStep::Capturing(stable_time, mean)
+    });
+}
+
+pub fn save_trim(keyboard: Res<ButtonInput<KeyCode>>, mut store: ResMut<TrimStore>) {
+    if !keyboard.just_pressed(SAVE_KEY) {
+        return;
+    }
+    let Some(TrimStep::Captured(trim)) = store.step else {
+        return;
+    };
+
+    let payload = store.payload.clone();
+    store.trims.insert(payload.clone(), trim);
+    store.step = None;
+    match store.save() {
+        Ok(()) => info!("Saved trim for {payload} to {}", store.path),
+        Err(e) => warn!("Failed to save trims {}: {e}", store.path),
+    }
+}
+
+/// A capture can't finish with the engine off; a captured trim is kept.
+pub fn stop_trim_capture(mut store: ResMut<TrimStore>) {
+    if let Some(TrimStep::Capturing(..)) = store.step {
+        store.step = None;
+    }
+}
+
+pub fn update_trim_text(
+    store: Res<TrimStore>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<TrimText>>,
+) {
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = match store.step {
+            Some(TrimStep::Capturing(stable_time, _)) => (
+                format!("Trim: hover steady {stable_time:.1}/{CAPTURE_TIME:.0} s").into(),
+                Color::srgb(1.0, 0.8, 0.2),
+            ),
+            Some(TrimStep::Captured(trim)) => (
+                format!("Trim {} - Enter saves", trim.summary()).into(),
+                Color::srgb(0.3, 1.0, 0.4),
+            ),
+            None => match store.trims.get(&store.payload) {
+                Some(_) => (format!("Trim: {}", store.payload).into(), Color::WHITE),
+                None => ("Trim: -".into(), Color::WHITE),
+            },
+        };
+    }
+}
@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    ActiveDrone, AntiWindup, AttitudeMode, AxisShape, CoordinatedTurn, GamepadInput, HoverPid,
+    PitchPid, RollPid, RotorLag, Wind, YawPid,
+};
+
+#[derive(Resource, Default)]
+pub struct TuningPanel {
+    pub open: bool,
+}
+
+pub fn toggle_tuning_panel(keyboard: Res<ButtonInput<KeyCode>>, mut panel: ResMut<TuningPanel>) {
+    if keyboard.just_pressed(KeyCode::KeyT) {
+        panel.open = !panel.open;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn tuning_ui(
+    mut contexts: EguiContexts,
+    panel: Res<TuningPanel>,
+    mut wind: ResMut<Wind>,
+    mut attitude_mode: ResMut<AttitudeMode>,
+    mut gamepad: ResMut<GamepadInput>,
+    mut turn_assist: ResMut<CoordinatedTurn>,
+    mut drone_query: Query<
+        (
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+            &mut YawPid,
+            Option<&mut RotorLag>,
+        ),
+        With<ActiveDrone>,
+    >,
+) -> Result {
+    if !panel.open {
+        return Ok(());
+    }
+
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Window::new("Tuning").show(ctx, |ui| {
+        for (mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw, rotor_lag) in
+            drone_query.iter_mut()
+        {
+            // Reborrow so egui widgets can hold several fields of one component at once
+            let (ctl_y, ctl_pitch, ctl_roll, ctl_yaw) =
+                (&mut *ctl_y, &mut *ctl_pitch, &mut *ctl_roll, &mut *ctl_yaw);
+
+            // Hover kp is scheduled between min and max with altitude
+            ui.heading("Gains");
+            egui::Grid::new("gains").num_columns(5).show(ui, |ui| {
+                ui.label("Hover");
+                ui.add(egui::Slider::new(&mut ctl_y.min_kp, 0.0..=20.0).text("min kp"));
+                ui.add(egui::Slider::new(&mut ctl_y.max_kp, 0.0..=20.0).text("max kp"));
+                ui.add(egui::Slider::new(&mut ctl_y.ki, 0.0..=5.0).text("ki"));
+                ui.add(egui::Slider::new(&mut ctl_y.kd, 0.0..=10.0).text("kd"));
+                ui.end_row();
+                gains_row(
+                    ui,
+                    "Pitch",
+                    &mut ctl_pitch.kp,
+                    &mut ctl_pitch.ki,
+                    &mut ctl_pitch.kd,
+                );
+                gains_row(
+                    ui,
+                    "Roll",
+                    &mut ctl_roll.kp,
+                    &mut ctl_roll.ki,
+                    &mut ctl_roll.kd,
+                );
+                gains_row(ui, "Yaw", &mut ctl_yaw.kp, &mut ctl_yaw.ki, &mut ctl_yaw.kd);
+            });
+
+            ui.heading("Output smoothing");
+            egui::Grid::new("smoothing").num_columns(3).show(ui, |ui| {
+                smoothing_row(ui, "Hover", &mut ctl_y.smoothing, &mut ctl_y.smoothing_tau);
+                smoothing_row(
+                    ui,
+                    "Pitch",
+                    &mut ctl_pitch.smoothing,
+                    &mut ctl_pitch.smoothing_tau,
+                );
+                smoothing_row(
+                    ui,
+                    "Roll",
+                    &mut ctl_roll.smoothing,
+                    &mut ctl_roll.smoothing_tau,
+                );
+                smoothing_row(
+                    ui,
+                    "Yaw",
+                    &mut ctl_yaw.smoothing,
+                    &mut ctl_yaw.smoothing_tau,
+                );
+            });
+
+            ui.heading("Anti-windup");
+            egui::Grid::new("anti_windup")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    anti_windup_row(
+                        ui,
+                        "Hover",
+                        &mut ctl_y.anti_windup,
+                        &mut ctl_y.integral_max,
+                        &mut ctl_y.integral_max_frac,
+                    );
+                    anti_windup_row(
+                        ui,
+                        "Pitch",
+                        &mut ctl_pitch.anti_windup,
+                        &mut ctl_pitch.integral_max,
+                        &mut ctl_pitch.integral_max_frac,
+                    );
+                    anti_windup_row(
+                        ui,
+                        "Roll",
+                        &mut ctl_roll.anti_windup,
+                        &mut ctl_roll.integral_max,
+                        &mut ctl_roll.integral_max_frac,
+                    );
+                    anti_windup_row(
+                        ui,
+                        "Yaw",
+                        &mut ctl_yaw.anti_windup,
+                        &mut ctl_yaw.integral_max,
+                        &mut ctl_yaw.integral_max_frac,
+                    );
+                });
+
+            ui.heading("Feedforward");
+            egui::Grid::new("feedforward")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    feedforward_row(ui, "Hover", &mut ctl_y.feedforward_gain);
+                    feedforward_row(ui, "Pitch", &mut ctl_pitch.feedforward_gain);
+                    feedforward_row(ui, "Roll", &mut ctl_roll.feedforward_gain);
+                    feedforward_row(ui, "Yaw", &mut ctl_yaw.feedforward_gain);
+                });
+
+            ui.heading("Invert output");
+            ui.horizontal(|ui| {
+                invert_checkbox(ui, "Hover", &mut ctl_y.output_sign);
+                invert_checkbox(ui, "Pitch", &mut ctl_pitch.output_sign);
+                invert_checkbox(ui, "Roll", &mut ctl_roll.output_sign);
+                invert_checkbox(ui, "Yaw", &mut ctl_yaw.output_sign);
+            });
+
+            ui.heading("Stick centering");
+            ui.add(
+                egui::Slider::new(&mut ctl_pitch.center_rate, 0.1..=20.0)
+                    .text("pitch (rad/s)")
+                    .logarithmic(true),
+            );
+            ui.add(
+                egui::Slider::new(&mut ctl_roll.center_rate, 0.1..=20.0)
+                    .text("roll (rad/s)")
+                    .logarithmic(true),
+            );
+
+            ui.heading("Soft ceiling");
+            ui.add(
+                egui::Slider::new(&mut ctl_y.ceiling_softness, 0.0..=20.0)
+                    .text("softness below max Y (m)"),
+            );
+
+            ui.heading("Tilt compensation");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut ctl_y.tilt_compensation, "Thrust / cos(tilt)");
+                ui.add_enabled(
+                    ctl_y.tilt_compensation,
+                    egui::Slider::new(&mut ctl_y.max_tilt_factor, 1.0..=3.0).text("max factor"),
+                );
+            });
+
+            if let Some(mut lag) = rotor_lag {
+                ui.heading("Rotor lag");
+                ui.add(egui::Slider::new(&mut lag.yaw_tau, 0.0..=1.0).text("yaw spin-up tau (s)"));
+            }
+        }
+
+        // The attitude error and drag are shared by every drone
+        ui.heading("Attitude error");
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut *attitude_mode, AttitudeMode::Quaternion, "Quaternion");
+            ui.selectable_value(&mut *attitude_mode, AttitudeMode::Euler, "Euler");
+        });
+
+        ui.heading("Drag coefficients");
+        let cd = &mut wind.drag_coefficients;
+        ui.add(egui::Slider::new(&mut cd.x, 0.0..=5.0).text("sideways (X)"));
+        ui.add(egui::Slider::new(&mut cd.y, 0.0..=5.0).text("vertical (Y)"));
+        ui.add(egui::Slider::new(&mut cd.z, 0.0..=5.0).text("forward/back (Z)"));
+
+        ui.heading("Coordinated turn");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut turn_assist.enabled, "Bank into yaw turns");
+            ui.add_enabled(
+                turn_assist.enabled,
+                egui::Slider::new(&mut turn_assist.gain, 0.0..=2.0).text("gain"),
+            );
+        });
+
+        ui.heading("Stick shaping");
+        egui::Grid::new("stick_shaping")
+            .num_columns(4)
+            .show(ui, |ui| {
+                stick_shaping_row(ui, "Roll", &mut gamepad.roll);
+                stick_shaping_row(ui, "Pitch", &mut gamepad.pitch);
+                stick_shaping_row(ui, "Yaw", &mut gamepad.yaw);
+                stick_shaping_row(ui, "Throttle", &mut gamepad.throttle);
+            });
+    });
+
+    Ok(())
+}
+
+fn gains_row(ui: &mut egui::Ui, label: &str, kp: &mut f32, ki: &mut f32, kd: &mut f32) {
+    ui.label(label);
+    ui.add(egui::Slider::new(kp, 0.0..=30.0).text("kp"));
+    ui.add(egui::Slider::new(ki, 0.0..=5.0).text("ki"));
+    ui.add(egui::Slider::new(kd, 0.0..=10.0).text("kd"));
+    ui.end_row();
+}
+
+fn smoothing_row(ui: &mut egui::Ui, label: &str, enabled: &mut bool, tau: &mut f32) {
+    ui.checkbox(enabled, label);
+    ui.add_enabled(
+        *enabled,
+        egui::Slider::new(tau, 0.01..=1.0)
+            .text("tau (s)")
+            .logarithmic(true),
+    );
+    ui.end_row();
+}
+
+fn feedforward_row(ui: &mut egui::Ui, label: &str, gain: &mut f32) {
+    ui.label(label);
+    ui.add(egui::Slider::new(gain, 0.0..=5.0).text("gain (1/s)"));
+    ui.end_row();
+}
+
+fn invert_checkbox(ui: &mut egui::Ui, label: &str, output_sign: &mut f32) {
+    let mut inverted = *output_sign < 0.0;
+    if ui.checkbox(&mut inverted, label).changed() {
+        *output_sign = if inverted { -1.0 } else { 1.0 };
+    }
+}
+
+fn stick_shaping_row(ui: &mut egui::Ui, label: &str, shape: &mut AxisShape) {
+    ui.label(label);
+    ui.add(egui::Slider::new(&mut shape.deadzone, 0.0..=0.5).text("deadzone"));
+    ui.add(egui::Slider::new(&mut shape.expo, 0.0..=1.0).text("expo"));
+    response_curve(ui, shape);
+    ui.end_row();
+}
+
+/// Small plot of stick deflection (x) against shaped output (y), -1..=1 on both.
+fn response_curve(ui: &mut egui::Ui, shape: &AxisShape) {
+    const SIZE: f32 = 48.0;
+    const POINTS: usize = 41;
+
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(SIZE, SIZE), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let to_screen = |x: f32, y: f32| {
+        egui::pos2(
+            rect.center().x + x * SIZE / 2.0,
+            rect.center().y - y * SIZE / 2.0,
+        )
+    };
+
+    let axis = egui::Stroke::new(1.0, egui::Color32::DARK_GRAY);
+    painter.rect_stroke(rect, 0.0, axis, egui::StrokeKind::Inside);
+    painter.line_segment([to_screen(-1.0, 0.0), to_screen(1.0, 0.0)], axis);
+    painter.line_segment([to_screen(0.0, -1.0), to_screen(0.0, 1.0)], axis);
+
+    let curve = (0..POINTS)
+        .map(|i| {
+            let x = -1.0 + 2.0 * i as f32 / (POINTS - 1) as f32;
+            to_screen(x, shape.apply(x))
+        })
+        .collect();
+    painter.add(egui::Shape::line(
+        curve,
+        egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN),
+    ));
+}
+
+fn anti_windup_row(
+    ui: &mut egui::Ui,
+    label: &str,
+    mode: &mut AntiWindup,
+    integral_max: &mut f32,
+    integral_max_frac: &mut f32,
+) {
+    egui::ComboBox::from_id_salt(label)
+        .selected_text(format!("{label}: {mode:?}"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(mode, AntiWindup::Off, "Off");
+            ui.selectable_value(mode, AntiWindup::Clamp, "Clamp");
+            ui.selectable_value(mode, AntiWindup::OutputFraction, "OutputFraction");
+            ui.selectable_value(mode, AntiWindup::Conditional, "Conditional");
+        });
+    match mode {
+        AntiWindup::Off => {
+            ui.label("");
+        }
+        AntiWindup::Clamp | AntiWindup::Conditional => {
+            ui.add(egui::Slider::new(integral_max, 0.0..=20.0).text("max integral"));
+        }
+        AntiWindup::OutputFraction => {
+            ui.add(egui::Slider::new(integral_max_frac, 0.0..=2.0).text("max fraction"));
+        }
+    }
+    ui.end_row();
+}
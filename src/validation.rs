@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+
+use crate::{Drone, HoverPid, PitchPid, RollPid, YawPid, has_flag};
+
+/// Checks the controller parameters once everything has been spawned and the
+/// gain files applied. Out-of-range values are clamped with a warning, or with
+/// `--strict-gains` abort the start with a list of every problem.
+pub fn validation_plugin(app: &mut App) {
+    app.add_systems(PostStartup, validate_pid_parameters);
+}
+
+struct Validator {
+    strict: bool,
+    drone: String,
+    axis: &'static str,
+    problems: Vec<String>,
+}
+
+impl Validator {
+    fn report(&mut self, problem: String) {
+        let problem = format!("{} {}.{problem}", self.drone, self.axis);
+        if !self.strict {
+            warn!("Invalid PID parameter: {problem}");
+        }
+        self.problems.push(problem);
+    }
+
+    fn non_negative(&mut self, name: &str, value: &mut f32) {
+        if *value < 0.0 || value.is_nan() {
+            self.report(format!("{name} = {value} must not be negative, using 0"));
+            *value = 0.0;
+        }
+    }
+
+    fn positive(&mut self, name: &str, value: &mut f32, fallback: f32) {
+        if *value <= 0.0 || value.is_nan() {
+            self.report(format!(
+                "{name} = {value} must be positive, using {fallback}"
+            ));
+            *value = fallback;
+        }
+    }
+
+    fn ordered(&mut self, min_name: &str, min: &mut f32, max_name: &str, max: &mut f32) {
+        // A NaN limit would compare false either way and slip through
+        if min.is_nan() {
+            self.report(format!(
+                "{min_name} = {min} is not a number, leaving it unbounded"
+            ));
+            *min = f32::NEG_INFINITY;
+        }
+        if max.is_nan() {
+            self.report(format!(
+                "{max_name} = {max} is not a number, leaving it unbounded"
+            ));
+            *max = f32::INFINITY;
+        }
+        if *min > *max {
+            self.report(format!(
+                "{min_name} = {min} is above {max_name} = {max}, swapping them"
+            ));
+            std::mem::swap(min, max);
+        }
+    }
+
+    /// Checks shared by all four controllers.
+    fn gains(&mut self, kp: &mut f32, ki: &mut f32, kd: &mut f32) {
+        self.non_negative("kp", kp);
+        self.non_negative("ki", ki);
+        self.non_negative("kd", kd);
+    }
+
+    fn gain_schedule(&mut self, min_kp: &mut f32, max_kp: &mut f32) {
+        self.non_negative("min_kp", min_kp);
+        self.non_negative("max_kp", max_kp);
+        self.ordered("min_kp", min_kp, "max_kp", max_kp);
+    }
+
+    fn integral_limits(&mut self, integral_max: &mut f32, integral_max_frac: &mut f32) {
+        self.non_negative("integral_max", integral_max);
+        self.non_negative("integral_max_frac", integral_max_frac);
+    }
+
+    /// In strict mode, aborts with every problem found.
+    fn finish(&self) {
+        if self.strict && !self.problems.is_empty() {
+            panic!(
+                "Invalid PID parameters (--strict-gains):\n  {}",
+                self.problems.join("\n  ")
+            );
+        }
+    }
+}
+
+pub fn validate_pid_parameters(
+    mut drone_query: Query<
+        (
+            &Name,
+            &mut HoverPid,
+            &mut PitchPid,
+            &mut RollPid,
+            &mut YawPid,
+        ),
+        With<Drone>,
+    >,
+) {
+    let mut validator = Validator {
+        strict: has_flag("--strict-gains"),
+        drone: String::new(),
+        axis: "",
+        problems: Vec::new(),
+    };
+
+    for (name, mut ctl_y, mut ctl_pitch, mut ctl_roll, mut ctl_yaw) in drone_query.iter_mut() {
+        validator.drone = name.to_string();
+
+        let ctl_y = &mut *ctl_y;
+        validator.axis = "hover";
+        validator.gains(&mut ctl_y.kp, &mut ctl_y.ki, &mut ctl_y.kd);
+        validator.gain_schedule(&mut ctl_y.min_kp, &mut ctl_y.max_kp);
+        validator.integral_limits(&mut ctl_y.integral_max, &mut ctl_y.integral_max_frac);
+        validator.non_negative("v_rate", &mut ctl_y.v_rate);
+        validator.ordered("min_y", &mut ctl_y.min_y, "max_y", &mut ctl_y.max_y);
+        validator.positive("smoothing_tau", &mut ctl_y.smoothing_tau, 0.1);
+
+        let ctl_pitch = &mut *ctl_pitch;
+        validator.axis = "pitch";
+        validator.gains(&mut ctl_pitch.kp, &mut ctl_pitch.ki, &mut ctl_pitch.kd);
+        validator.gain_schedule(&mut ctl_pitch.min_kp, &mut ctl_pitch.max_kp);
+        validator.integral_limits(
+            &mut ctl_pitch.integral_max,
+            &mut ctl_pitch.integral_max_frac,
+        );
+        validator.non_negative("angle_rate", &mut ctl_pitch.angle_rate);
+        validator.ordered(
+            "min_angle",
+            &mut ctl_pitch.min_angle,
+            "max_angle",
+            &mut ctl_pitch.max_angle,
+        );
+        validator.non_negative("center_rate", &mut ctl_pitch.center_rate);
+        validator.positive("smoothing_tau", &mut ctl_pitch.smoothing_tau, 0.1);
+
+        let ctl_roll = &mut *ctl_roll;
+        validator.axis = "roll";
+        validator.gains(&mut ctl_roll.kp, &mut ctl_roll.ki, &mut ctl_roll.kd);
+        validator.gain_schedule(&mut ctl_roll.min_kp, &mut ctl_roll.max_kp);
+        validator.integral_limits(&mut ctl_roll.integral_max, &mut ctl_roll.integral_max_frac);
+        validator.non_negative("angle_rate", &mut ctl_roll.angle_rate);
+        validator.ordered(
+            "min_angle",
+            &mut ctl_roll.min_angle,
+            "max_angle",
+            &mut ctl_roll.max_angle,
+        );
+        validator.non_negative("center_rate", &mut ctl_roll.center_rate);
+        validator.positive("smoothing_tau", &mut ctl_roll.smoothing_tau, 0.1);
+
+        let ctl_yaw = &mut *ctl_yaw;
+        validator.axis = "yaw";
+        validator.gains(&mut ctl_yaw.kp, &mut ctl_yaw.ki, &mut ctl_yaw.kd);
+        validator.gain_schedule(&mut ctl_yaw.min_kp, &mut ctl_yaw.max_kp);
+        validator.integral_limits(&mut ctl_yaw.integral_max, &mut ctl_yaw.integral_max_frac);
+        validator.non_negative("angle_rate", &mut ctl_yaw.angle_rate);
+        validator.ordered(
+            "min_angle",
+            &mut ctl_yaw.min_angle,
+            "max_angle",
+            &mut ctl_yaw.max_angle,
+        );
+        validator.positive("smoothing_tau", &mut ctl_yaw.smoothing_tau, 0.1);
+    }
+
+    validator.finish();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator(strict: bool) -> Validator {
+        Validator {
+            strict,
+            drone: "Drone".into(),
+            axis: "hover",
+            problems: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn non_negative_clamps_to_zero() {
+        let mut validator = validator(false);
+        let (mut ok, mut negative, mut nan) = (0.0, -1.0, f32::NAN);
+        validator.non_negative("ok", &mut ok);
+        validator.non_negative("negative", &mut negative);
+        validator.non_negative("nan", &mut nan);
+
+        assert_eq!((ok, negative, nan), (0.0, 0.0, 0.0));
+        assert_eq!(validator.problems.len(), 2);
+        assert!(validator.problems[0].starts_with("Drone hover.negative = -1"));
+    }
+
+    #[test]
+    fn positive_falls_back() {
+        let mut validator = validator(false);
+        let (mut ok, mut zero, mut nan) = (0.5, 0.0, f32::NAN);
+        validator.positive("ok", &mut ok, 0.1);
+        validator.positive("zero", &mut zero, 0.1);
+        validator.positive("nan", &mut nan, 0.1);
+
+        assert_eq!((ok, zero, nan), (0.5, 0.1, 0.1));
+        assert_eq!(validator.problems.len(), 2);
+    }
+
+    #[test]
+    fn ordered_swaps_reversed_limits() {
+        let mut validator = validator(false);
+        let (mut min, mut max) = (1.0, -1.0);
+        validator.ordered("min", &mut min, "max", &mut max);
+        assert_eq!((min, max), (-1.0, 1.0));
+
+        let (mut min, mut max) = (-2.0, 2.0);
+        validator.ordered("min", &mut min, "max", &mut max);
+        assert_eq!((min, max), (-2.0, 2.0));
+        assert_eq!(validator.problems.len(), 1);
+    }
+
+    #[test]
+    fn ordered_rejects_nan() {
+        let mut validator = validator(false);
+        let (mut min, mut max) = (f32::NAN, 1.0);
+        validator.ordered("min", &mut min, "max", &mut max);
+        assert_eq!((min, max), (f32::NEG_INFINITY, 1.0));
+
+        let (mut min, mut max) = (-1.0, f32::NAN);
+        validator.ordered("min", &mut min, "max", &mut max);
+        assert_eq!((min, max), (-1.0, f32::INFINITY));
+        assert_eq!(validator.problems.len(), 2);
+    }
+
+    #[test]
+    fn lenient_mode_carries_on() {
+        let mut validator = validator(false);
+        validator.non_negative("kp", &mut -1.0);
+        validator.finish();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Invalid PID parameters (--strict-gains):\n  Drone hover.kp = -1 must not be negative, using 0\n  Drone hover.ki = NaN must not be negative, using 0"
+    )]
+    fn strict_mode_lists_every_problem() {
+        let mut validator = validator(true);
+        let (mut kp, mut ki) = (-1.0, f32::NAN);
+        validator.non_negative("kp", &mut kp);
+        validator.non_negative("ki", &mut ki);
+        validator.finish();
+    }
+}
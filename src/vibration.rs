@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{Drone, HoverPid, PROPELLER_OFFSETS, arg_value};
+
+/// Each motor runs slightly off the base frequency so their ripples beat
+/// against each other instead of adding up into one clean sine.
+const MOTOR_DETUNE: [f32; 4] = [1.0, 1.037, 0.971, 1.052];
+/// A second, weaker harmonic per motor roughens the ripple further.
+const HARMONIC_RATIO: f32 = 2.31;
+const HARMONIC_WEIGHT: f32 = 0.35;
+
+/// Motor thrust ripple. Every motor's share of the hover thrust is modulated
+/// by a small high-frequency wobble, which shakes the drone up and down and,
+/// as the motors drift out of phase, rocks it in pitch and roll.
+#[derive(Resource)]
+pub struct ThrustNoise {
+    /// Peak ripple as a fraction of each motor's thrust.
+    pub amplitude: f32,
+    /// Base ripple frequency (Hz). Above half the physics rate it aliases down
+    /// to a slower wobble, just as a real IMU sampling the vibration would.
+    pub frequency: f32,
+}
+
+impl ThrustNoise {
+    pub fn from_args() -> Self {
+        Self {
+            amplitude: arg_value("--thrust-noise")
+                .and_then(|amplitude| amplitude.parse::<f32>().ok())
+                .unwrap_or(0.0)
+                .max(0.0),
+            frequency: arg_value("--thrust-noise-freq")
+                .and_then(|frequency| frequency.parse::<f32>().ok())
+                .unwrap_or(25.0)
+                .max(0.0),
+        }
+    }
+
+    /// Ripple of motor `motor` at time `t`, as a fraction of its thrust.
+    pub fn ripple(&self, motor: usize, phase: f32, t: f32) -> f32 {
+        let w = std::f32::consts::TAU * self.frequency * MOTOR_DETUNE[motor];
+        let base = (w * t + phase).sin();
+        let harmonic = (HARMONIC_RATIO * w * t + 2.0 * phase).sin();
+        self.amplitude * (base + HARMONIC_WEIGHT * harmonic) / (1.0 + HARMONIC_WEIGHT)
+    }
+}
+
+pub fn vibration_plugin(app: &mut App) {
+    let noise = ThrustNoise::from_args();
+    let enabled = noise.amplitude > 0.0;
+
+    app.insert_resource(noise).add_systems(
+        FixedUpdate,
+        apply_thrust_noise
+            .before(PhysicsSet::SyncBackend)
+            .run_if(move || enabled),
+    );
+}
+
+pub fn apply_thrust_noise(
+    time: Res<Time<Fixed>>,
+    noise: Res<ThrustNoise>,
+    mut drone_query: Query<(Entity, &Transform, &HoverPid, &mut ExternalImpulse), With<Drone>>,
+) {
+    let dt = time.delta_secs();
+    let t = time.elapsed_secs();
+
+    for (entity, tf, ctl_y, mut ext_impulse) in drone_query.iter_mut() {
+        if ctl_y.output <= 0.0 {
+            continue;
+        }
+
+        // Drones shouldn't all vibrate in lockstep
+        let drone_phase = entity.index() as f32 * 1.7;
+        let motor_thrust = ctl_y.output / PROPELLER_OFFSETS.len() as f32;
+        let up = tf.rotation * Vec3::Y;
+
+        let mut force = Vec3::ZERO;
+        let mut torque = Vec3::ZERO;
+        for (motor, offset) in PROPELLER_OFFSETS.iter().enumerate() {
+            let phase = drone_phase + motor as f32 * 0.9;
+            let motor_force = up * motor_thrust * noise.ripple(motor, phase, t);
+            force += motor_force;
+            torque += (tf.rotation * *offset).cross(motor_force);
+        }
+
+        ext_impulse.impulse += force * dt;
+        ext_impulse.torque_impulse += torque * dt;
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{ActiveDrone, Drone, arg_value, has_flag};
+
+/// Time constant over which the ring builds up and clears (s).
+const BUILD_TIME: f32 = 0.4;
+/// Fraction of the onset descent rate from which the HUD warns of the risk.
+const WARNING_FRACTION: f32 = 0.7;
+
+/// Vortex ring state: descending into its own downwash, the rotor recirculates
+/// its wake and loses lift. Once the descent rate along the rotor axis passes
+/// `onset` the thrust drops, reaching `max_loss` at twice the onset rate.
+/// Flying sideways moves the rotor into clean air, so the effect fades out as
+/// the lateral speed approaches `escape_speed`. Off unless `--vrs` is given.
+#[derive(Resource)]
+pub struct VortexRingModel {
+    pub enabled: bool,
+    /// Descent rate where lift starts to drop (m/s).
+    pub onset: f32,
+    /// Largest fraction of thrust lost.
+    pub max_loss: f32,
+    /// Lateral speed that clears the ring completely (m/s).
+    pub escape_speed: f32,
+}
+
+impl VortexRingModel {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: has_flag("--vrs"),
+            onset: arg_value("--vrs-onset")
+                .and_then(|onset| onset.parse::<f32>().ok())
+                .unwrap_or(3.0)
+                .max(0.1),
+            max_loss: arg_value("--vrs-loss")
+                .and_then(|loss| loss.parse::<f32>().ok())
+                .unwrap_or(0.5)
+                .clamp(0.0, 1.0),
+            escape_speed: arg_value("--vrs-escape-speed")
+                .and_then(|speed| speed.parse::<f32>().ok())
+                .unwrap_or(4.0)
+                .max(0.1),
+        }
+    }
+
+    /// How deep into the ring a drone is, from 0 (clean air) to 1 (full
+    /// loss), for its descent rate along the rotor axis and lateral speed.
+    pub fn severity(&self, descent_rate: f32, lateral_speed: f32) -> f32 {
+        let depth = ((descent_rate - self.onset) / self.onset).clamp(0.0, 1.0);
+        let escape = (1.0 - lateral_speed / self.escape_speed).clamp(0.0, 1.0);
+        depth * escape
+    }
+}
+
+/// Per-drone vortex ring state, read by `update_drone_forces`.
+#[derive(Component, Default)]
+pub struct VortexRing {
+    pub severity: f32,
+    /// Descent rate along the rotor axis (m/s), positive going down.
+    pub descent_rate: f32,
+    pub lateral_speed: f32,
+}
+
+impl VortexRing {
+    /// Fraction of the commanded thrust that's still produced.
+    pub fn thrust_factor(&self, model: &VortexRingModel) -> f32 {
+        1.0 - model.max_loss * self.severity
+    }
+}
+
+#[derive(Component)]
+pub struct VrsText;
+
+pub fn vortex_ring_plugin(app: &mut App) {
+    let model = VortexRingModel::from_args();
+    let enabled = model.enabled;
+
+    app.insert_resource(model).add_systems(
+        Update,
+        (
+            (add_vortex_ring, update_vortex_ring)
+                .chain()
+                .run_if(move || enabled),
+            update_vrs_text,
+        ),
+    );
+}
+
+pub fn add_vortex_ring(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<VortexRing>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(VortexRing::default());
+    }
+}
+
+pub fn update_vortex_ring(
+    time: Res<Time>,
+    model: Res<VortexRingModel>,
+    mut drone_query: Query<(&Transform, &Velocity, &mut VortexRing)>,
+) {
+    let dt = time.delta_secs();
+
+    for (tf, velocity, mut ring) in drone_query.iter_mut() {
+        let up = tf.rotation * Vec3::Y;
+        let axial = velocity.linvel.dot(up);
+        ring.descent_rate = -axial;
+        ring.lateral_speed = (velocity.linvel - up * axial).length();
+
+        let target = model.severity(ring.descent_rate, ring.lateral_speed);
+        ring.severity += (target - ring.severity) * (dt / BUILD_TIME).min(1.0);
+    }
+}
+
+pub fn update_vrs_text(
+    model: Res<VortexRingModel>,
+    drone_query: Query<&VortexRing, With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<VrsText>>,
+) {
+    let ring = drone_query.single().ok();
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = match ring {
+            _ if !model.enabled => ("VRS: off".into(), Color::WHITE),
+            Some(ring) if ring.severity > 0.01 => (
+                format!(
+                    "VRS RISK: {:.0}% lift lost",
+                    (1.0 - ring.thrust_factor(&model)) * 100.0
+                )
+                .into(),
+                Color::srgb(1.0, 0.2, 0.2),
+            ),
+            Some(ring)
+                if ring.descent_rate > WARNING_FRACTION * model.onset
+                    && ring.lateral_speed < model.escape_speed =>
+            {
+                (
+                    format!("VRS RISK: sink {:.1} m/s", ring.descent_rate).into(),
+                    Color::srgb(1.0, 0.8, 0.2),
+                )
+            }
+            _ => ("VRS: clear".into(), Color::WHITE),
+        };
+    }
+}
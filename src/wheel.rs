@@ -0,0 +1,106 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use crate::{
+    DroneCameraParams, EngineState, HoverPid, Piloted, arg_value, clamp_setpoints, manual_control,
+};
+
+/// Touchpads scroll in pixels; this many make up one wheel notch.
+const PIXELS_PER_NOTCH: f32 = 50.0;
+/// Orbit distance change per notch, as a fraction of the current distance.
+const ZOOM_PER_NOTCH: f32 = 0.1;
+const MIN_CAMERA_RADIUS: f32 = 2.0;
+const MAX_CAMERA_RADIUS: f32 = 60.0;
+
+/// Mouse wheel input. The plain wheel zooms the camera; with Alt held each
+/// notch moves the target altitude by `step`, fed in at no more than
+/// `climb_rate` so the setpoint ramps instead of jumping.
+#[derive(Resource)]
+pub struct WheelAltitude {
+    /// Altitude change per notch (m).
+    pub step: f32,
+    /// Fastest the target altitude follows the wheel (m/s).
+    pub climb_rate: f32,
+    /// Altitude change scrolled in but not yet applied (m).
+    pub pending: f32,
+}
+
+impl WheelAltitude {
+    pub fn from_args() -> Self {
+        Self {
+            step: arg_value("--wheel-step")
+                .and_then(|step| step.parse::<f32>().ok())
+                .unwrap_or(0.5),
+            climb_rate: arg_value("--wheel-climb-rate")
+                .and_then(|rate| rate.parse::<f32>().ok())
+                .unwrap_or(3.0)
+                .max(0.1),
+            pending: 0.0,
+        }
+    }
+}
+
+pub fn wheel_plugin(app: &mut App) {
+    app.insert_resource(WheelAltitude::from_args()).add_systems(
+        Update,
+        (
+            mouse_wheel_control,
+            wheel_altitude
+                .after(manual_control)
+                .before(clamp_setpoints)
+                .run_if(in_state(EngineState::On)),
+        )
+            .chain(),
+    );
+}
+
+pub fn mouse_wheel_control(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    engine_state: Res<State<EngineState>>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut wheel: ResMut<WheelAltitude>,
+    mut cam_params: ResMut<DroneCameraParams>,
+) {
+    let altitude = keyboard.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+
+    for event in wheel_events.read() {
+        let notches = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_NOTCH,
+        };
+
+        if altitude {
+            // Like Space/Ctrl, the altitude only moves with the engine on
+            if *engine_state.get() == EngineState::On {
+                wheel.pending += notches * wheel.step;
+            }
+        } else {
+            cam_params.radius = (cam_params.radius * (1.0 - ZOOM_PER_NOTCH).powf(notches))
+                .clamp(MIN_CAMERA_RADIUS, MAX_CAMERA_RADIUS);
+        }
+    }
+}
+
+pub fn wheel_altitude(
+    time: Res<Time>,
+    mut wheel: ResMut<WheelAltitude>,
+    mut drone_query: Query<&mut HoverPid, Piloted>,
+) {
+    if wheel.pending == 0.0 {
+        return;
+    }
+
+    let max_step = wheel.climb_rate * time.delta_secs();
+    let step = wheel.pending.clamp(-max_step, max_step);
+    let mut at_limit = false;
+    for mut ctl_y in drone_query.iter_mut() {
+        let target = ctl_y.target_y + step;
+        ctl_y.target_y = target.clamp(ctl_y.min_y, ctl_y.max_y);
+        at_limit |= ctl_y.target_y != target;
+    }
+
+    // Drop the rest at a limit so scrolling back responds straight away
+    wheel.pending = if at_limit { 0.0 } else { wheel.pending - step };
+}
@@ -0,0 +1,248 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{ActiveDrone, Drone, arg_value};
+
+const AIR_DENSITY: f32 = 1.225;
+const DRAG_AREA: f32 = 0.002;
+
+/// Wind at a given altitude. `direction` is the heading the wind blows
+/// towards in degrees, where 0 is -Z (forward) and 90 is +X (right).
+#[derive(Deserialize, Clone, Debug)]
+pub struct WindLayer {
+    pub altitude: f32,
+    pub speed: f32,
+    pub direction: f32,
+}
+
+impl WindLayer {
+    pub fn velocity(&self) -> Vec3 {
+        let direction = self.direction.to_radians();
+        Vec3::new(direction.sin(), 0.0, -direction.cos()) * self.speed
+    }
+}
+
+#[derive(Component)]
+pub struct WindText;
+
+#[derive(Resource, Default)]
+pub struct Wind {
+    pub layers: Vec<WindLayer>,
+    /// Reference area the drag coefficients are scaled by (m²).
+    pub drag_area: f32,
+    /// Drag coefficient along each body axis: X sideways, Y up/down, Z
+    /// forward/back.
+    pub drag_coefficients: Vec3,
+    /// Constant wind on top of the layers, from `--wind X,Y,Z` (m/s).
+    pub steady: Vec3,
+    /// Current point of the sinusoidal swell, see `WindSwell`.
+    pub swell: Vec3,
+    /// Gust on top of the layered wind, the same at every altitude.
+    pub gust: Vec3,
+}
+
+impl Wind {
+    pub fn new(layers: Vec<WindLayer>) -> Self {
+        let mut wind = Self {
+            layers: Vec::new(),
+            drag_area: DRAG_AREA,
+            drag_coefficients: drag_coefficients_from_args(),
+            steady: steady_wind_from_args(),
+            swell: Vec3::ZERO,
+            gust: Vec3::ZERO,
+        };
+        wind.set_layers(layers);
+        wind
+    }
+
+    pub fn set_layers(&mut self, mut layers: Vec<WindLayer>) {
+        layers.sort_by(|a, b| a.altitude.total_cmp(&b.altitude));
+        self.layers = layers;
+    }
+
+    /// Wind velocity at `altitude`, including the steady wind, swell and any gust.
+    pub fn sample(&self, altitude: f32) -> Vec3 {
+        self.layer_velocity(altitude) + self.steady + self.swell + self.gust
+    }
+
+    /// Linearly interpolates the wind velocity between the layers around `altitude`.
+    fn layer_velocity(&self, altitude: f32) -> Vec3 {
+        let (Some(first), Some(last)) = (self.layers.first(), self.layers.last()) else {
+            return Vec3::ZERO;
+        };
+
+        if altitude <= first.altitude {
+            return first.velocity();
+        }
+        if altitude >= last.altitude {
+            return last.velocity();
+        }
+
+        for pair in self.layers.windows(2) {
+            let (low, high) = (&pair[0], &pair[1]);
+            if altitude <= high.altitude {
+                let t =
+                    (altitude - low.altitude) / (high.altitude - low.altitude).max(f32::EPSILON);
+                return low.velocity().lerp(high.velocity(), t);
+            }
+        }
+
+        last.velocity()
+    }
+
+    /// Aerodynamic drag of the air moving relative to a body at `altitude`
+    /// with orientation `rotation`. Each body axis has its own coefficient, so
+    /// the same airspeed drags differently depending on the attitude.
+    pub fn drag(&self, altitude: f32, velocity: Vec3, rotation: Quat) -> Vec3 {
+        let v_rel = self.sample(altitude) - velocity;
+        let v_body = rotation.inverse() * v_rel;
+        let drag_body =
+            0.5 * AIR_DENSITY * self.drag_area * v_rel.length() * self.drag_coefficients * v_body;
+        rotation * drag_body
+    }
+
+    /// The relative air velocity (wind minus drone velocity) that would
+    /// produce the horizontal `drag`, inverting `drag` with the mean of the
+    /// sideways and forward coefficients plus a `linear` drag term (N·s/m).
+    pub fn airspeed_from_drag(&self, drag: Vec3, linear: f32) -> Vec3 {
+        let cd = 0.5 * (self.drag_coefficients.x + self.drag_coefficients.z);
+        let k = 0.5 * AIR_DENSITY * self.drag_area * cd;
+        let drag = Vec3::new(drag.x, 0.0, drag.z);
+        let force = drag.length();
+        if force <= f32::EPSILON {
+            return Vec3::ZERO;
+        }
+        // k·s² + linear·s = force
+        let speed = if k > 0.0 {
+            ((linear * linear + 4.0 * k * force).sqrt() - linear) / (2.0 * k)
+        } else if linear > 0.0 {
+            force / linear
+        } else {
+            return Vec3::ZERO;
+        };
+        drag.normalize() * speed
+    }
+}
+
+/// Compass heading (deg) a horizontal velocity points towards, 0 = -Z.
+pub fn heading_deg(v: Vec3) -> f32 {
+    v.x.atan2(-v.z).to_degrees().rem_euclid(360.0)
+}
+
+fn drag_coefficients_from_args() -> Vec3 {
+    let Some(value) = arg_value("--drag-coefficients") else {
+        return Vec3::ONE;
+    };
+
+    let coefficients = value
+        .split(',')
+        .map(|cd| cd.trim().parse::<f32>().ok().filter(|cd| *cd >= 0.0))
+        .collect::<Option<Vec<_>>>();
+    match coefficients.as_deref() {
+        Some(&[x, y, z]) => Vec3::new(x, y, z),
+        _ => {
+            warn!("Invalid --drag-coefficients {value}, expected X,Y,Z");
+            Vec3::ONE
+        }
+    }
+}
+
+fn steady_wind_from_args() -> Vec3 {
+    let Some(value) = arg_value("--wind") else {
+        return Vec3::ZERO;
+    };
+
+    let components = value
+        .split(',')
+        .map(|v| v.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<_>>>();
+    match components.as_deref() {
+        Some(&[x, y, z]) => Vec3::new(x, y, z),
+        _ => {
+            warn!("Invalid --wind {value}, expected X,Y,Z");
+            Vec3::ZERO
+        }
+    }
+}
+
+/// Slow sinusoidal variation of the wind, for checking how the controllers
+/// track a disturbance that never settles: the wind swings by `amplitude`
+/// either way along `direction` every `period` seconds. Off unless
+/// `--wind-swell <amplitude>` is given.
+#[derive(Resource)]
+pub struct WindSwell {
+    /// Peak swell speed (m/s).
+    pub amplitude: f32,
+    pub period: f32,
+    /// Heading the swell blows towards at its peak (deg, 0 = forward, 90 = right).
+    pub direction: f32,
+    pub elapsed: f32,
+}
+
+impl WindSwell {
+    pub fn from_args() -> Self {
+        Self {
+            amplitude: arg_value("--wind-swell")
+                .and_then(|amplitude| amplitude.parse::<f32>().ok())
+                .unwrap_or(0.0),
+            period: arg_value("--wind-swell-period")
+                .and_then(|period| period.parse::<f32>().ok())
+                .unwrap_or(6.0)
+                .max(0.1),
+            direction: arg_value("--wind-swell-direction")
+                .and_then(|direction| direction.parse::<f32>().ok())
+                .unwrap_or(90.0),
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn velocity(&self) -> Vec3 {
+        let speed = self.amplitude * (TAU * self.elapsed / self.period).sin();
+        let direction = self.direction.to_radians();
+        Vec3::new(direction.sin(), 0.0, -direction.cos()) * speed
+    }
+}
+
+pub fn swell_wind(time: Res<Time<Fixed>>, mut swell: ResMut<WindSwell>, mut wind: ResMut<Wind>) {
+    if swell.amplitude == 0.0 {
+        return;
+    }
+    swell.elapsed += time.delta_secs();
+    wind.swell = swell.velocity();
+}
+
+/// The swell starts from calm on the next flight.
+pub fn stop_swell(mut swell: ResMut<WindSwell>, mut wind: ResMut<Wind>) {
+    swell.elapsed = 0.0;
+    wind.swell = Vec3::ZERO;
+}
+
+pub fn apply_wind(
+    time: Res<Time<Fixed>>,
+    wind: Res<Wind>,
+    mut drone_query: Query<(&Transform, &Velocity, &mut ExternalImpulse), With<Drone>>,
+) {
+    let dt = time.delta_secs();
+
+    for (tf, velocity, mut ext_impulse) in drone_query.iter_mut() {
+        ext_impulse.impulse += wind.drag(tf.translation.y, velocity.linvel, tf.rotation) * dt;
+    }
+}
+
+pub fn update_wind_text(
+    wind: Res<Wind>,
+    drone_query: Query<&Transform, With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<WindText>>,
+) {
+    for tf in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            let v = wind.sample(tf.translation.y);
+            let heading = heading_deg(v);
+            let gust = if wind.gust == Vec3::ZERO { "" } else { " GUST" };
+            *text = format!("Wind: {:.1} m/s {:>3.0} deg{gust}", v.length(), heading).into();
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ActiveDrone, DragModel, Drone, EngineState, Wind, arg_value, has_flag, wind::heading_deg,
+};
+
+/// Estimates the wind each drone is flying in from how its controllers lean
+/// into it. Holding still in a wind, the position integrator winds up a tilt
+/// whose horizontal thrust cancels the drag; with acceleration taken out,
+/// whatever horizontal force the drone is pushing against is drag, and
+/// inverting the drag model gives the airspeed and, adding the drone's own
+/// velocity, the wind. Off unless `--wind-estimate` is given.
+#[derive(Resource)]
+pub struct WindEstimator {
+    pub enabled: bool,
+    /// Time constant of the low-pass on the estimated drag (s).
+    pub tau: f32,
+}
+
+impl WindEstimator {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: has_flag("--wind-estimate"),
+            tau: arg_value("--wind-estimate-tau")
+                .and_then(|tau| tau.parse::<f32>().ok())
+                .unwrap_or(2.0)
+                .max(0.05),
+        }
+    }
+}
+
+/// Per-drone wind estimate.
+#[derive(Component, Default)]
+pub struct WindEstimate {
+    /// Filtered horizontal drag (N).
+    pub drag: Vec3,
+    /// Estimated wind velocity (m/s).
+    pub wind: Vec3,
+    /// Thrust force and velocity from the last frame; this frame's velocity
+    /// change is the result of that force.
+    last: Option<(Vec3, Vec3)>,
+}
+
+#[derive(Component)]
+pub struct WindEstimateText;
+
+pub fn wind_estimate_plugin(app: &mut App) {
+    let estimator = WindEstimator::from_args();
+    let enabled = estimator.enabled;
+    app.insert_resource(estimator)
+        .add_systems(
+            Update,
+            (
+                add_wind_estimate,
+                estimate_wind.run_if(in_state(EngineState::On)),
+                update_wind_estimate_text,
+            )
+                .chain()
+                .run_if(move || enabled),
+        )
+        .add_systems(OnExit(EngineState::On), reset_wind_estimates);
+}
+
+pub fn add_wind_estimate(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<WindEstimate>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(WindEstimate::default());
+    }
+}
+
+/// Horizontal force balance: `m·a = thrust + drag`, so the drag is what's left
+/// of the mass times the measured acceleration after the thrust the
+/// controllers commanded last frame.
+#[allow(clippy::type_complexity)]
+pub fn estimate_wind(
+    time: Res<Time>,
+    estimator: Res<WindEstimator>,
+    wind: Res<Wind>,
+    mut drone_query: Query<(
+        &Velocity,
+        &ExternalForce,
+        &ReadMassProperties,
+        Option<&DragModel>,
+        &mut WindEstimate,
+    )>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+    let horizontal = |v: Vec3| Vec3::new(v.x, 0.0, v.z);
+    let t = (dt / estimator.tau).min(1.0);
+
+    for (velocity, ext_force, mass_props, drag_model, mut estimate) in drone_query.iter_mut() {
+        let linvel = horizontal(velocity.linvel);
+        if let Some((thrust, last_velocity)) = estimate.last {
+            let accel = (linvel - last_velocity) / dt;
+            let drag = mass_props.get().mass * accel - thrust;
+            estimate.drag = estimate.drag.lerp(drag, t);
+            let linear = drag_model.map_or(0.0, |drag_model| drag_model.linear);
+            estimate.wind = wind.airspeed_from_drag(estimate.drag, linear) + linvel;
+        }
+        estimate.last = Some((horizontal(ext_force.force), linvel));
+    }
+}
+
+pub fn reset_wind_estimates(mut drone_query: Query<&mut WindEstimate>) {
+    for mut estimate in drone_query.iter_mut() {
+        *estimate = WindEstimate::default();
+    }
+}
+
+pub fn update_wind_estimate_text(
+    wind: Res<Wind>,
+    drone_query: Query<(&Transform, &WindEstimate), With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<WindEstimateText>>,
+) {
+    let Ok((tf, estimate)) = drone_query.single() else {
+        return;
+    };
+    let truth = wind.sample(tf.translation.y);
+    let truth = Vec3::new(truth.x, 0.0, truth.z);
+
+    for mut text in text_query.iter_mut() {
+        *text = format!(
+            "Wind est: {:.1} m/s {:>3.0} deg (off {:.1})",
+            estimate.wind.length(),
+            heading_deg(estimate.wind),
+            (estimate.wind - truth).length()
+        )
+        .into();
+    }
+}
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::{
+    ActiveDrone, EngineState, PitchPid, RollPid, Wind, WindLayer, arg_value,
+    position::{PositionPid, release_tilt},
+};
+
+const DEMO_ALTITUDE: f32 = 5.0;
+/// Position integral gain for the demo. The default is tuned to stay out of
+/// the way, which would take minutes to cancel the wind.
+const DEMO_KI: f32 = 0.3;
+
+/// Scripted position hold in a steady wind. Proportional action alone leaves
+/// the drone blown off its hold point by the error it needs to lean into the
+/// wind; the HUD shows the integral term building up until it supplies that
+/// lean instead and the error goes to zero.
+#[derive(Resource)]
+pub struct WindHoldDemo {
+    pub active: bool,
+    pub speed: f32,
+    /// Heading the wind blows towards (deg), as in the level's wind layers.
+    pub direction: f32,
+    /// Wind layers and position integral gain to restore afterwards.
+    pub saved_layers: Vec<WindLayer>,
+    pub saved_ki: f32,
+}
+
+impl WindHoldDemo {
+    pub fn from_args() -> Self {
+        Self {
+            active: false,
+            speed: arg_value("--hold-wind-speed")
+                .and_then(|speed| speed.parse::<f32>().ok())
+                .unwrap_or(4.0)
+                .max(0.0),
+            direction: arg_value("--hold-wind-direction")
+                .and_then(|direction| direction.parse::<f32>().ok())
+                .unwrap_or(90.0),
+            saved_layers: Vec::new(),
+            saved_ki: 0.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct WindHoldText;
+
+pub fn wind_hold_demo_plugin(app: &mut App) {
+    app.insert_resource(WindHoldDemo::from_args())
+        .add_systems(
+            Update,
+            (toggle_wind_hold_demo, update_wind_hold_text).chain(),
+        )
+        .add_systems(OnExit(EngineState::On), stop_wind_hold_demo);
+}
+
+pub fn toggle_wind_hold_demo(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut demo: ResMut<WindHoldDemo>,
+    mut wind: ResMut<Wind>,
+    mut next_engine_state: ResMut<NextState<EngineState>>,
+    mut drone_query: Query<
+        (&Transform, &mut PositionPid, &mut PitchPid, &mut RollPid),
+        With<ActiveDrone>,
+    >,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyZ) {
+        return;
+    }
+
+    if demo.active {
+        end_demo(&mut demo, &mut wind, &mut drone_query);
+        info!("Wind hold demo stopped");
+        return;
+    }
+
+    for (tf, mut ctl_pos, ctl_pitch, ctl_roll) in drone_query.iter_mut() {
+        demo.saved_layers = std::mem::replace(
+            &mut wind.layers,
+            vec![WindLayer {
+                altitude: 0.0,
+                speed: demo.speed,
+                direction: demo.direction,
+            }],
+        );
+        demo.saved_ki = ctl_pos.ki;
+        demo.active = true;
+
+        ctl_pos.ki = DEMO_KI;
+        let target = Vec3::new(tf.translation.x, DEMO_ALTITUDE, tf.translation.z);
+        let tilt = Vec2::new(ctl_pitch.target_angle, ctl_roll.target_angle);
+        ctl_pos.engage(target, tf.translation, tilt);
+        next_engine_state.set(EngineState::On);
+        info!(
+            "Wind hold demo started: {:.1} m/s wind towards {:.0} deg",
+            demo.speed, demo.direction
+        );
+    }
+}
+
+/// Ends the demo when the engine stops, e.g. on a tumble disarm.
+#[allow(clippy::type_complexity)]
+pub fn stop_wind_hold_demo(
+    mut demo: ResMut<WindHoldDemo>,
+    mut wind: ResMut<Wind>,
+    mut drone_query: Query<
+        (&Transform, &mut PositionPid, &mut PitchPid, &mut RollPid),
+        With<ActiveDrone>,
+    >,
+) {
+    if demo.active {
+        end_demo(&mut demo, &mut wind, &mut drone_query);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn end_demo(
+    demo: &mut WindHoldDemo,
+    wind: &mut Wind,
+    drone_query: &mut Query<
+        (&Transform, &mut PositionPid, &mut PitchPid, &mut RollPid),
+        With<ActiveDrone>,
+    >,
+) {
+    wind.layers = std::mem::take(&mut demo.saved_layers);
+    for (_, mut ctl_pos, mut ctl_pitch, mut ctl_roll) in drone_query.iter_mut() {
+        ctl_pos.disengage();
+        ctl_pos.ki = demo.saved_ki;
+        release_tilt(&mut ctl_pitch, &mut ctl_roll);
+    }
+    demo.active = false;
+}
+
+pub fn update_wind_hold_text(
+    demo: Res<WindHoldDemo>,
+    drone_query: Query<(&Transform, &PositionPid), With<ActiveDrone>>,
+    mut text_query: Query<&mut Text, With<WindHoldText>>,
+) {
+    for (tf, ctl_pos) in drone_query.iter() {
+        for mut text in text_query.iter_mut() {
+            *text = if demo.active {
+                let error = Vec2::new(
+                    ctl_pos.target.x - tf.translation.x,
+                    ctl_pos.target.z - tf.translation.z,
+                );
+                // The integral's share of the commanded acceleration
+                let integral = ctl_pos.ki * ctl_pos.integral_e;
+                format!(
+                    "Hold err {:.2}m I {:.2}m/s2",
+                    error.length(),
+                    integral.length()
+                )
+                .into()
+            } else {
+                "Wind hold: press Z".into()
+            };
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{AntiWindup, Drone, EngineState, GRAVITY, HoverPid};
+
+const DEMO_ALTITUDE: f32 = 5.0;
+const SETTLE_TIME: f32 = 3.0;
+const DISTURB_TIME: f32 = 5.0;
+const RELEASE_TIME: f32 = 6.0;
+/// Downward push while disturbed, in multiples of gravity. Strong enough to
+/// hold the drone well below its target so the integral keeps accumulating.
+const DISTURBANCE_G: f32 = 1.5;
+/// The two runs: first without anti-windup, then with the integral clamped.
+const DEMO_MODES: [AntiWindup; 2] = [AntiWindup::Off, AntiWindup::Clamp];
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DemoPhase {
+    #[default]
+    Idle,
+    Settle,
+    Disturb,
+    Release,
+}
+
+/// Scripted integral windup demonstration on the hover controller: hold the
+/// drone down with a sustained disturbance, let go, and measure the overshoot,
+/// once with anti-windup off and once with it clamped.
+#[derive(Resource, Default)]
+pub struct WindupDemo {
+    pub phase: DemoPhase,
+    pub run: usize,
+    pub elapsed: f32,
+    pub peak_overshoot: f32,
+    pub overshoot: [Option<f32>; 2],
+    /// Hover anti-windup mode to restore once the demo is over.
+    pub saved_mode: AntiWindup,
+}
+
+#[derive(Component)]
+pub struct WindupText;
+
+pub fn windup_demo_plugin(app: &mut App) {
+    app.init_resource::<WindupDemo>().add_systems(
+        Update,
+        (start_windup_demo, run_windup_demo, update_windup_text).chain(),
+    );
+}
+
+pub fn start_windup_demo(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut demo: ResMut<WindupDemo>,
+    mut next_engine_state: ResMut<NextState<EngineState>>,
+    mut drone_query: Query<&mut HoverPid, With<Drone>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    for mut ctl_y in drone_query.iter_mut() {
+        if demo.phase == DemoPhase::Idle {
+            demo.saved_mode = ctl_y.anti_windup;
+        }
+        *demo = WindupDemo {
+            saved_mode: demo.saved_mode,
+            ..Default::default()
+        };
+        begin_run(&mut demo, &mut ctl_y);
+        next_engine_state.set(EngineState::On);
+        info!("Windup demo started");
+    }
+}
+
+fn begin_run(demo: &mut WindupDemo, ctl_y: &mut HoverPid) {
+    demo.phase = DemoPhase::Settle;
+    demo.elapsed = 0.0;
+    demo.peak_overshoot = 0.0;
+    ctl_y.anti_windup = DEMO_MODES[demo.run];
+    ctl_y.target_y = DEMO_ALTITUDE;
+    ctl_y.integral_e = 0.0;
+}
+
+pub fn run_windup_demo(
+    time: Res<Time>,
+    mut demo: ResMut<WindupDemo>,
+    mut drone_query: Query<
+        (
+            &Transform,
+            &ReadMassProperties,
+            &mut HoverPid,
+            &mut ExternalImpulse,
+        ),
+        With<Drone>,
+    >,
+) {
+    if demo.phase == DemoPhase::Idle {
+        return;
+    }
+
+    let dt = time.delta_secs();
+    demo.elapsed += dt;
+
+    for (tf, mass_props, mut ctl_y, mut ext_impulse) in drone_query.iter_mut() {
+        match demo.phase {
+            DemoPhase::Idle => {}
+            DemoPhase::Settle => {
+                if demo.elapsed >= SETTLE_TIME {
+                    demo.phase = DemoPhase::Disturb;
+                    demo.elapsed = 0.0;
+                }
+            }
+            DemoPhase::Disturb => {
+                ext_impulse.impulse += Vec3::NEG_Y * mass_props.mass * DISTURBANCE_G * GRAVITY * dt;
+                if demo.elapsed >= DISTURB_TIME {
+                    demo.phase = DemoPhase::Release;
+                    demo.elapsed = 0.0;
+                }
+            }
+            DemoPhase::Release => {
+                let overshoot = tf.translation.y - ctl_y.target_y;
+                demo.peak_overshoot = demo.peak_overshoot.max(overshoot);
+                if demo.elapsed < RELEASE_TIME {
+                    continue;
+                }
+
+                let run = demo.run;
+                demo.overshoot[run] = Some(demo.peak_overshoot);
+                info!(
+                    "Windup demo run {} ({:?}): peak overshoot {:.2} m",
+                    run + 1,
+                    DEMO_MODES[run],
+                    demo.peak_overshoot
+                );
+
+                if run + 1 < DEMO_MODES.len() {
+                    demo.run += 1;
+                    begin_run(&mut demo, &mut ctl_y);
+                } else {
+                    demo.phase = DemoPhase::Idle;
+                    ctl_y.anti_windup = demo.saved_mode;
+                }
+            }
+        }
+    }
+}
+
+pub fn update_windup_text(
+    demo: Res<WindupDemo>,
+    drone_query: Query<&HoverPid, With<Drone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<WindupText>>,
+) {
+    for ctl_y in drone_query.iter() {
+        for (mut text, mut color) in text_query.iter_mut() {
+            let saturated = matches!(
+                ctl_y.anti_windup,
+                AntiWindup::Clamp | AntiWindup::Conditional
+            ) && ctl_y.integral_e.abs() >= ctl_y.integral_max;
+            let results = demo
+                .overshoot
+                .iter()
+                .zip(DEMO_MODES)
+                .filter_map(|(overshoot, mode)| overshoot.map(|o| format!("{mode:?} +{o:.2} m")))
+                .collect::<Vec<_>>()
+                .join("  ");
+
+            *text = match demo.phase {
+                DemoPhase::Idle if results.is_empty() => "Windup demo: press B".into(),
+                DemoPhase::Idle => format!("Windup: {results}").into(),
+                phase => format!(
+                    "Windup {}/{} {:?} {:?} I:{:.1}{}",
+                    demo.run + 1,
+                    DEMO_MODES.len(),
+                    DEMO_MODES[demo.run],
+                    phase,
+                    ctl_y.integral_e,
+                    if saturated { " I-SAT" } else { "" }
+                )
+                .into(),
+            };
+            color.0 = if saturated || demo.phase == DemoPhase::Disturb {
+                Color::srgb(1.0, 0.6, 0.0)
+            } else {
+                Color::WHITE
+            };
+        }
+    }
+}
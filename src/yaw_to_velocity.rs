@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ActiveDrone, Drone, EngineState, YawPid, angle_error, arg_value, clamp_setpoints,
+    gamepad_control, has_flag, limit_attitude_rate, manual_control, position::PositionPid,
+    position_control, wind::heading_deg,
+};
+
+const AUTO_YAW_KEY: KeyCode = KeyCode::Digit5;
+/// How long the assist keeps its hands off after the pilot last turned the
+/// heading themselves (s).
+const MANUAL_HOLD: f32 = 1.0;
+/// Yaw setpoint change that counts as somebody else steering (rad).
+const OVERRIDE_THRESHOLD: f32 = 1e-4;
+
+/// Auto-yaw for forward-facing flight: above `min_speed` the heading setpoint
+/// turns towards the direction of horizontal travel, no faster than
+/// `rate_limit`, so the nose (and an FPV camera) looks where the drone is
+/// going. Yawing by hand with `Q`/`E` or the stick takes over for a moment,
+/// and the autopilots keep their own heading. On with `--yaw-to-velocity` or
+/// `5`; `--yaw-min-speed` (1 m/s) and `--yaw-rate-limit` (90 deg/s) tune it.
+#[derive(Resource)]
+pub struct YawToVelocity {
+    pub enabled: bool,
+    /// Horizontal speed below which the heading is left alone (m/s).
+    pub min_speed: f32,
+    /// Fastest the assist turns the heading setpoint (rad/s).
+    pub rate_limit: f32,
+}
+
+impl YawToVelocity {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: has_flag("--yaw-to-velocity"),
+            min_speed: arg_value("--yaw-min-speed")
+                .and_then(|speed| speed.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .max(0.1),
+            rate_limit: arg_value("--yaw-rate-limit")
+                .and_then(|rate| rate.parse::<f32>().ok())
+                .unwrap_or(90.0)
+                .max(1.0)
+                .to_radians(),
+        }
+    }
+}
+
+/// What the assist is doing with a drone's heading.
+#[derive(Component, Default)]
+pub struct AutoYaw {
+    /// Yaw setpoint flown last frame, to notice the pilot turning it.
+    last_target: Option<f32>,
+    /// Time left before the assist steers again after a manual turn (s).
+    hold: f32,
+    /// Compass course being turned towards (deg, 0 = forward), while steering.
+    pub course: Option<f32>,
+}
+
+#[derive(Component)]
+pub struct AutoYawText;
+
+pub fn yaw_to_velocity_plugin(app: &mut App) {
+    app.insert_resource(YawToVelocity::from_args())
+        .add_systems(
+            Update,
+            (
+                add_auto_yaw,
+                toggle_yaw_to_velocity,
+                yaw_to_velocity
+                    .after(manual_control)
+                    .after(gamepad_control)
+                    .after(position_control)
+                    .before(clamp_setpoints)
+                    .run_if(in_state(EngineState::On)),
+                update_auto_yaw_text,
+            )
+                .chain(),
+        )
+        .add_systems(Update, remember_yaw_target.after(limit_attitude_rate));
+}
+
+pub fn add_auto_yaw(
+    mut commands: Commands,
+    drone_query: Query<Entity, (With<Drone>, Without<AutoYaw>)>,
+) {
+    for drone in drone_query.iter() {
+        commands.entity(drone).insert(AutoYaw::default());
+    }
+}
+
+pub fn toggle_yaw_to_velocity(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut assist: ResMut<YawToVelocity>,
+) {
+    if !keyboard.just_pressed(AUTO_YAW_KEY) {
+        return;
+    }
+
+    assist.enabled = !assist.enabled;
+    info!(
+        "Yaw to velocity {}",
+        if assist.enabled { "on" } else { "off" }
+    );
+}
+
+pub fn yaw_to_velocity(
+    time: Res<Time>,
+    assist: Res<YawToVelocity>,
+    mut drone_query: Query<(&Velocity, &PositionPid, &mut YawPid, &mut AutoYaw)>,
+) {
+    let dt = time.delta_secs();
+
+    for (velocity, ctl_pos, mut ctl_yaw, mut auto_yaw) in drone_query.iter_mut() {
+        // A setpoint that moved since last frame was turned by the pilot
+        let overridden = auto_yaw
+            .last_target
+            .is_some_and(|last| angle_error(ctl_yaw.target_angle, last).abs() > OVERRIDE_THRESHOLD);
+        auto_yaw.hold = if overridden {
+            MANUAL_HOLD
+        } else {
+            (auto_yaw.hold - dt).max(0.0)
+        };
+        auto_yaw.course = None;
+
+        let horizontal = Vec2::new(velocity.linvel.x, velocity.linvel.z);
+        if assist.enabled
+            && !ctl_pos.enabled
+            && auto_yaw.hold == 0.0
+            && horizontal.length() >= assist.min_speed
+        {
+            // The nose is -Z, so a heading of 0 flies towards -Z
+            let heading = (-horizontal.x).atan2(-horizontal.y);
+            let step = assist.rate_limit * dt;
+            ctl_yaw.target_angle += angle_error(heading, ctl_yaw.target_angle).clamp(-step, step);
+            auto_yaw.course = Some(heading_deg(velocity.linvel));
+        }
+    }
+}
+
+/// Runs after the setpoint is clamped and rate limited, so only changes made
+/// before the assist next frame count as the pilot's.
+pub fn remember_yaw_target(mut drone_query: Query<(&YawPid, &mut AutoYaw)>) {
+    for (ctl_yaw, mut auto_yaw) in drone_query.iter_mut() {
+        auto_yaw.last_target = Some(ctl_yaw.target_angle);
+    }
+}
+
+pub fn update_auto_yaw_text(
+    assist: Res<YawToVelocity>,
+    drone_query: Query<&AutoYaw, With<ActiveDrone>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<AutoYawText>>,
+) {
+    let Ok(auto_yaw) = drone_query.single() else {
+        return;
+    };
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        (*text, color.0) = if !assist.enabled {
+            ("Auto yaw: off".into(), Color::WHITE)
+        } else if auto_yaw.hold > 0.0 {
+            ("Auto yaw: manual".into(), Color::srgb(1.0, 0.8, 0.2))
+        } else if let Some(course) = auto_yaw.course {
+            (
+                format!("Auto yaw: {course:>3.0} deg").into(),
+                Color::srgb(0.4, 0.8, 1.0),
+            )
+        } else {
+            ("Auto yaw: on".into(), Color::WHITE)
+        };
+    }
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_rapier3d::prelude::*;
+use pid_simulation::{Drone, EngineState, HoverPid, TumbleDisarm, build_app};
+
+/// One frame at 60 fps, the default physics rate.
+const FRAME: Duration = Duration::from_micros(16_667);
+/// Speed the drone is thrown at the wall with (m/s).
+const IMPACT_SPEED: f32 = 6.0;
+
+#[test]
+fn wall_crash_disarms() {
+    let mut app = build_app(true);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(FRAME));
+    // Startup spawns the drone 3 m up at the origin
+    app.update();
+
+    // A wall 2 m ahead of the drone, its top edge 4 cm below the drone's
+    // middle so it catches the underside
+    app.world_mut().spawn((
+        Transform::from_xyz(0.0, 0.46, -2.5),
+        RigidBody::Fixed,
+        Collider::cuboid(5.0, 2.5, 0.25),
+    ));
+    app.world_mut()
+        .resource_mut::<NextState<EngineState>>()
+        .set(EngineState::On);
+    let mut drone_query = app
+        .world_mut()
+        .query_filtered::<(&mut HoverPid, &mut Velocity), With<Drone>>();
+    for (mut ctl_y, mut velocity) in drone_query.iter_mut(app.world_mut()) {
+        ctl_y.target_y = 3.0;
+        velocity.linvel = Vec3::NEG_Z * IMPACT_SPEED;
+    }
+
+    for _ in 0..120 {
+        app.update();
+    }
+
+    assert!(
+        app.world().resource::<TumbleDisarm>().tripped,
+        "the drone hit the wall without tumbling"
+    );
+    assert_eq!(
+        *app.world().resource::<State<EngineState>>().get(),
+        EngineState::Off
+    );
+}
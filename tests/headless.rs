@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use pid_simulation::{Drone, EngineState, HoverPid, PitchPid, build_app};
+
+/// One frame at 60 fps, the default physics rate, so each update steps the
+/// controllers and physics once.
+const FRAME: Duration = Duration::from_micros(16_667);
+/// Largest altitude error (m) and vertical speed (m/s) counted as settled.
+const TOLERANCE: f32 = 0.1;
+
+/// Headless sim with the engine running and every drone's altitude setpoint
+/// at `target_y`.
+fn hovering_at(target_y: f32) -> App {
+    let mut app = build_app(true);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(FRAME));
+    // Startup spawns the drones
+    app.update();
+
+    app.world_mut()
+        .resource_mut::<NextState<EngineState>>()
+        .set(EngineState::On);
+    let mut drone_query = app
+        .world_mut()
+        .query_filtered::<&mut HoverPid, With<Drone>>();
+    for mut ctl_y in drone_query.iter_mut(app.world_mut()) {
+        ctl_y.target_y = target_y;
+    }
+    app
+}
+
+/// Altitude and vertical speed of the drone after `seconds` of flight.
+fn fly(app: &mut App, seconds: f32) -> (f32, f32) {
+    let frames = (seconds / FRAME.as_secs_f32()).round() as usize;
+    for _ in 0..frames {
+        app.update();
+    }
+
+    let mut drone_query = app
+        .world_mut()
+        .query_filtered::<(&Transform, &bevy_rapier3d::prelude::Velocity), With<Drone>>();
+    let (tf, velocity) = drone_query
+        .single(app.world())
+        .expect("the headless app spawns one drone");
+    (tf.translation.y, velocity.linvel.y)
+}
+
+#[test]
+fn climbs_to_target_altitude() {
+    let mut app = hovering_at(8.0);
+    let (y, v_y) = fly(&mut app, 15.0);
+    assert!((y - 8.0).abs() < TOLERANCE, "settled at {y:.2} m");
+    assert!(v_y.abs() < TOLERANCE, "still moving at {v_y:.2} m/s");
+}
+
+#[test]
+fn descends_to_target_altitude() {
+    let mut app = hovering_at(1.0);
+    let (y, v_y) = fly(&mut app, 15.0);
+    assert!((y - 1.0).abs() < TOLERANCE, "settled at {y:.2} m");
+    assert!(v_y.abs() < TOLERANCE, "still moving at {v_y:.2} m/s");
+}
+
+/// Largest altitude change (m) while the drone tilts into forward flight.
+fn altitude_loss_in_tilt(tilt_compensation: bool) -> f32 {
+    let mut app = hovering_at(3.0);
+    fly(&mut app, 5.0);
+
+    let mut drone_query = app
+        .world_mut()
+        .query_filtered::<(&mut HoverPid, &mut PitchPid), With<Drone>>();
+    for (mut ctl_y, mut ctl_pitch) in drone_query.iter_mut(app.world_mut()) {
+        ctl_y.tilt_compensation = tilt_compensation;
+        ctl_pitch.target_angle = -20_f32.to_radians();
+    }
+
+    let mut deviation = 0.0_f32;
+    for _ in 0..180 {
+        let (y, _) = fly(&mut app, FRAME.as_secs_f32());
+        deviation = deviation.max((y - 3.0).abs());
+    }
+    deviation
+}
+
+#[test]
+fn holds_altitude_in_tilt() {
+    let compensated = altitude_loss_in_tilt(true);
+    assert!(compensated < TOLERANCE, "moved {compensated:.2} m");
+
+    let uncompensated = altitude_loss_in_tilt(false);
+    assert!(uncompensated < 1.0, "moved {uncompensated:.2} m");
+    assert!(compensated < uncompensated);
+}
+
+/// Highest the drone gets in 40 s of climbing from 3 m to the 120 m ceiling,
+/// with or without its altitude integral.
+fn long_climb_peak(integral: bool) -> f32 {
+    let mut app = hovering_at(120.0);
+    if !integral {
+        let mut drone_query = app
+            .world_mut()
+            .query_filtered::<&mut HoverPid, With<Drone>>();
+        for mut ctl_y in drone_query.iter_mut(app.world_mut()) {
+            ctl_y.ki = 0.0;
+        }
+    }
+
+    let mut peak = 0.0_f32;
+    for _ in 0..(40.0 / FRAME.as_secs_f32()) as usize {
+        let (y, _) = fly(&mut app, FRAME.as_secs_f32());
+        peak = peak.max(y);
+    }
+    peak
+}
+
+#[test]
+fn long_climb_does_not_wind_up() {
+    // The P and D terms alone overshoot a 117 m step by several metres;
+    // the integral, clamped by default, mustn't add to that
+    let peak = long_climb_peak(true);
+    let without_integral = long_climb_peak(false);
+    assert!(peak - 120.0 < 10.0, "overshot to {peak:.2} m");
+    assert!(
+        peak - without_integral < 0.5,
+        "overshot to {peak:.2} m, {without_integral:.2} m without the integral"
+    );
+}
+
+/// Mean altitude error (m) over the first 2 s of a climb at 1 m/s, with the
+/// altitude setpoint moved every frame.
+fn ramp_tracking_error(feedforward_gain: f32) -> f32 {
+    let mut app = hovering_at(3.0);
+    fly(&mut app, 5.0);
+
+    let mut drone_query = app
+        .world_mut()
+        .query_filtered::<&mut HoverPid, With<Drone>>();
+    for mut ctl_y in drone_query.iter_mut(app.world_mut()) {
+        ctl_y.feedforward_gain = feedforward_gain;
+    }
+
+    let frames = (2.0 / FRAME.as_secs_f32()) as usize;
+    let mut error = 0.0;
+    for frame in 1..=frames {
+        let target_y = 3.0 + frame as f32 * FRAME.as_secs_f32();
+        for mut ctl_y in drone_query.iter_mut(app.world_mut()) {
+            ctl_y.target_y = target_y;
+        }
+        let (y, _) = fly(&mut app, FRAME.as_secs_f32());
+        error += (target_y - y).abs();
+    }
+    error / frames as f32
+}
+
+#[test]
+fn feedforward_reduces_ramp_lag() {
+    let without = ramp_tracking_error(0.0);
+    let with = ramp_tracking_error(0.5);
+    assert!(
+        with < 0.8 * without,
+        "lagged {with:.3} m, {without:.3} m without feedforward"
+    );
+}